@@ -0,0 +1,135 @@
+//! Measures the per-event cost of the event handler and checks that it does not allocate.
+//!
+//! Feeds pre-built taps and interrupted holds to `start_event_handler`, with injected input
+//! discarded, and counts heap allocations made while the events are handled.
+//!
+//! Run with `cargo bench --bench hot_path`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    io,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use prevent_alt_win_menu::{
+    event_handler::{
+        Config, KBDLLHOOKSTRUCT,
+        KeyboardAndMouse::{INPUT, VIRTUAL_KEY, VK_E, VK_LWIN},
+        KeyboardEvent, WmKeyState, start_event_handler,
+    },
+    input::{InputSender, set_input_sender},
+};
+
+/// Rounds of a tap and an interrupted hold fed to the handler, after the warm-up.
+const ROUNDS: usize = 100_000;
+/// Rounds handled before measuring, so that lazily allocated state already exists.
+const WARMUP_ROUNDS: usize = 100;
+/// Events per round.
+const ROUND_EVENTS: usize = 6;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Discards injected input, so that the benchmark does not type into the desktop.
+struct NullSender;
+
+impl InputSender for NullSender {
+    fn send(&self, _inputs: &[INPUT]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn event(vk: VIRTUAL_KEY, key_state: WmKeyState) -> KeyboardEvent {
+    KeyboardEvent::new(
+        KBDLLHOOKSTRUCT {
+            vkCode: vk.0 as u32,
+            ..Default::default()
+        },
+        key_state,
+    )
+}
+
+/// Yields the pre-built events and measures while the handler consumes them.
+struct Measured {
+    events: std::vec::IntoIter<KeyboardEvent>,
+    handled: usize,
+    start: Option<(Instant, u64)>,
+}
+
+impl Iterator for Measured {
+    type Item = KeyboardEvent;
+
+    fn next(&mut self) -> Option<KeyboardEvent> {
+        if self.handled == WARMUP_ROUNDS * ROUND_EVENTS {
+            self.start = Some((Instant::now(), ALLOCATIONS.load(Ordering::Relaxed)));
+        }
+        self.handled += 1;
+
+        let event = self.events.next();
+        if event.is_none()
+            && let Some((start, allocations)) = self.start
+        {
+            let elapsed = start.elapsed();
+            let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+            report(ROUNDS * ROUND_EVENTS, elapsed, allocations);
+        }
+        event
+    }
+}
+
+fn report(events: usize, elapsed: Duration, allocations: u64) {
+    println!("events:      {events}");
+    println!("total:       {elapsed:?}");
+    println!("per event:   {:?}", elapsed / events as u32);
+    println!("allocations: {allocations}");
+    assert_eq!(
+        allocations, 0,
+        "the event handler allocated on the hot path"
+    );
+}
+
+fn main() {
+    set_input_sender(NullSender);
+
+    let mut events = Vec::with_capacity((WARMUP_ROUNDS + ROUNDS) * ROUND_EVENTS);
+    for _ in 0..WARMUP_ROUNDS + ROUNDS {
+        // A tap, which is suppressed.
+        events.push(event(VK_LWIN, WmKeyState::KeyDown));
+        events.push(event(VK_LWIN, WmKeyState::KeyUp));
+        // `Win+E`, which is interrupted and passed through.
+        events.push(event(VK_LWIN, WmKeyState::KeyDown));
+        events.push(event(VK_E, WmKeyState::KeyDown));
+        events.push(event(VK_E, WmKeyState::KeyUp));
+        events.push(event(VK_LWIN, WmKeyState::KeyUp));
+    }
+
+    let events = Measured {
+        events: events.into_iter(),
+        handled: 0,
+        start: None,
+    };
+    start_event_handler(events, Config::default())
+        .join()
+        .expect("the event handler panicked");
+}
@@ -0,0 +1,119 @@
+//! Measures the end-to-end latency from a Win key tap to the suppression input.
+//!
+//! Injects synthetic `Win` taps with `SendInput` and records when the dummy key is emitted
+//! through the [`InputSender`], which then injects it as usual. Prints the distribution of
+//! the time in between.
+//!
+//! Run with `cargo bench --bench latency -- [low-level|inline] [taps]` on an idle desktop.
+//! If suppression fails or is too slow, the Start menu opens.
+
+use std::{
+    env, io,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use prevent_alt_win_menu::{
+    event_handler::{
+        Config,
+        KeyboardAndMouse::{
+            INPUT, INPUT_0, INPUT_KEYBOARD, KEYBD_EVENT_FLAGS, KEYBDINPUT, KEYEVENTF_KEYUP,
+            SendInput, VIRTUAL_KEY, VK_LWIN,
+        },
+    },
+    input::{InputSender, SendInputSender, set_input_sender},
+};
+
+/// The pause between taps, so that each tap is handled on its own.
+const PAUSE: Duration = Duration::from_millis(50);
+/// How long to wait for the suppression input of a tap.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Stamps suppression input before injecting it.
+struct TimingSender {
+    emitted: mpsc::Sender<Instant>,
+}
+
+impl InputSender for TimingSender {
+    fn send(&self, inputs: &[INPUT]) -> io::Result<()> {
+        let _ = self.emitted.send(Instant::now());
+        SendInputSender.send(inputs)
+    }
+}
+
+fn key(vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                dwFlags: flags,
+                ..Default::default()
+            },
+        },
+    }
+}
+
+fn tap_win() -> Instant {
+    let inputs = [
+        key(VK_LWIN, KEYBD_EVENT_FLAGS(0)),
+        key(VK_LWIN, KEYEVENTF_KEYUP),
+    ];
+    let sent = Instant::now();
+    let count = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    assert_eq!(count as usize, inputs.len(), "SendInput failed");
+    sent
+}
+
+fn percentile(sorted: &[Duration], q: f64) -> Duration {
+    let index = ((sorted.len() as f64 * q).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[index]
+}
+
+fn main() {
+    let mut args = env::args().skip(1).filter(|a| a != "--bench");
+    let backend = args.next().unwrap_or_else(|| "low-level".to_owned());
+    let taps: usize = args
+        .next()
+        .map_or(200, |n| n.parse().expect("invalid tap count"));
+
+    let (tx, rx) = mpsc::channel();
+    set_input_sender(TimingSender { emitted: tx });
+
+    match backend.as_str() {
+        "low-level" => {
+            prevent_alt_win_menu::start(Config::default()).expect("failed to start");
+        }
+        "inline" => {
+            prevent_alt_win_menu::start_inline(Config::default()).expect("failed to start");
+        }
+        other => panic!("unknown backend {other:?}, expected `low-level` or `inline`"),
+    }
+    thread::sleep(PAUSE);
+
+    let mut latencies = Vec::with_capacity(taps);
+    let mut missed = 0;
+    for _ in 0..taps {
+        let sent = tap_win();
+        match rx.recv_timeout(TIMEOUT) {
+            Ok(emitted) => latencies.push(emitted.saturating_duration_since(sent)),
+            Err(_) => missed += 1,
+        }
+        thread::sleep(PAUSE);
+        // Discard input emitted late for an earlier tap.
+        while rx.try_recv().is_ok() {}
+    }
+
+    latencies.sort();
+    println!("backend: {backend}");
+    println!("taps:    {taps} ({missed} without suppression input)");
+    if latencies.is_empty() {
+        return;
+    }
+    println!("min:     {:?}", latencies[0]);
+    for (name, q) in [("p50", 0.5), ("p90", 0.9), ("p99", 0.99)] {
+        println!("{name}:     {:?}", percentile(&latencies, q));
+    }
+    println!("max:     {:?}", latencies[latencies.len() - 1]);
+}
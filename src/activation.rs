@@ -0,0 +1,79 @@
+//! Window activation changes as a typed stream.
+//!
+//! The foreground watcher (see [`start_foreground_watcher`]) is the single source of
+//! activation changes: besides counting them for [`Config::set_reset_on_foreground_change`],
+//! it captures the newly activated window and publishes it to every receiver created with
+//! [`subscribe`]. The latest activation is also cached, so per-application decisions such as
+//! [`RuleSet::evaluate`] don't need to query the foreground window again.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{activation, foreground::start_foreground_watcher};
+//!
+//! let activations = activation::subscribe();
+//! start_foreground_watcher()?;
+//!
+//! for activation in activations {
+//!     println!("activated: {:?}", activation.window.process_name);
+//! }
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! [`start_foreground_watcher`]: crate::foreground::start_foreground_watcher
+//! [`Config::set_reset_on_foreground_change`]: crate::event_handler::Config::set_reset_on_foreground_change
+//! [`RuleSet::evaluate`]: crate::rules::RuleSet::evaluate
+
+use std::{
+    sync::{Mutex, PoisonError, mpsc},
+    time::Instant,
+};
+
+use crate::foreground::ForegroundWindow;
+
+static SUBSCRIBERS: Mutex<Vec<mpsc::Sender<Activation>>> = Mutex::new(Vec::new());
+static CURRENT: Mutex<Option<Activation>> = Mutex::new(None);
+
+/// A window that became the foreground window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Activation {
+    /// The activated window.
+    pub window: ForegroundWindow,
+    /// When the change was observed.
+    pub timestamp: Instant,
+}
+
+/// Returns a receiver for all subsequent activation changes.
+///
+/// Nothing is received unless the foreground watcher is running. Dropping the receiver
+/// unsubscribes it.
+pub fn subscribe() -> mpsc::Receiver<Activation> {
+    let (tx, rx) = mpsc::channel();
+    SUBSCRIBERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(tx);
+    rx
+}
+
+/// Returns the latest activation observed by the foreground watcher, or `None` if the watcher
+/// has not observed any yet.
+pub fn current() -> Option<Activation> {
+    CURRENT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Records `window` as the current activation and sends it to all subscribers.
+pub(crate) fn publish(window: ForegroundWindow) {
+    let activation = Activation {
+        window,
+        timestamp: Instant::now(),
+    };
+
+    SUBSCRIBERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .retain(|tx| tx.send(activation.clone()).is_ok());
+
+    *CURRENT.lock().unwrap_or_else(PoisonError::into_inner) = Some(activation);
+}
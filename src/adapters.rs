@@ -0,0 +1,269 @@
+//! Iterator adapters for building pipelines on keyboard events.
+//!
+//! [`EventIteratorExt`] is implemented for every iterator over [`MenuTriggerEvent`]s, such
+//! as [`KeyboardEvents`](crate::keyboard_hook::KeyboardEvents) or a receiver returned by
+//! [`crate::tee::tee`]:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use prevent_alt_win_menu::adapters::EventIteratorExt;
+//!
+//! let (events, _hook) = prevent_alt_win_menu::keyboard_hook::start_keyboard_hook()?;
+//! for hold in events.debounce(Duration::from_millis(5)).holds() {
+//!     println!("{} held for {:?}", hold.trigger, hold.release.timestamp - hold.press.timestamp);
+//! }
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::event_handler::{
+    HoldEvent, HoldStates, KeyState, KeyboardAndMouse::VIRTUAL_KEY, MenuTriggerEvent,
+    TrackingOptions,
+};
+
+/// Adapters for iterators over keyboard events.
+pub trait EventIteratorExt<T: MenuTriggerEvent>: Iterator<Item = T> + Sized {
+    /// Keeps only events of menu trigger keys (Alt, Win and others mapped by
+    /// [`MenuTriggerEvent::menu_trigger`]).
+    fn only_triggers(self) -> impl Iterator<Item = T> {
+        self.filter(|event| event.menu_trigger().is_some())
+    }
+
+    /// Keeps only key releases.
+    fn key_ups(self) -> impl Iterator<Item = T> {
+        self.filter(|event| !event.is_key_down())
+    }
+
+    /// Tracks trigger keys and yields a [`HoldEvent`] whenever one is released.
+    ///
+    /// This is the tracking the event handler does, with the default
+    /// [`Config`](crate::event_handler::Config): triggers are taken from
+    /// [`MenuTriggerEvent::menu_trigger`] and other keys pressed in between mark the hold as
+    /// interrupted, but are not recorded.
+    fn holds(self) -> Holds<Self, T> {
+        Holds {
+            events: self,
+            states: HoldStates::default(),
+        }
+    }
+
+    /// Drops changes of a key's state that follow the previous accepted change of the same
+    /// key within `window`, filtering out contact bounce of worn switches.
+    ///
+    /// Events are timed by [`MenuTriggerEvent::received_at`], or when they are taken from
+    /// the iterator if they carry no timestamp. Events without a virtual key code are
+    /// passed through unchanged.
+    fn debounce(self, window: Duration) -> Debounce<Self> {
+        Debounce {
+            events: self,
+            window,
+            last: HashMap::new(),
+        }
+    }
+}
+
+impl<T: MenuTriggerEvent, I: Iterator<Item = T>> EventIteratorExt<T> for I {}
+
+/// The iterator returned by [`EventIteratorExt::holds`].
+pub struct Holds<I, T> {
+    events: I,
+    states: HoldStates<T>,
+}
+
+impl<T: MenuTriggerEvent, I: Iterator<Item = T>> Iterator for Holds<I, T> {
+    type Item = HoldEvent<T>;
+
+    fn next(&mut self) -> Option<HoldEvent<T>> {
+        loop {
+            let event = self.events.next()?;
+            let trigger = event.menu_trigger();
+            if let Some(hold) = self
+                .states
+                .update(event, trigger, TrackingOptions::default())
+            {
+                return Some(hold);
+            }
+        }
+    }
+}
+
+/// The iterator returned by [`EventIteratorExt::debounce`].
+pub struct Debounce<I> {
+    events: I,
+    window: Duration,
+    /// The latest accepted state change per key.
+    last: HashMap<VIRTUAL_KEY, (KeyState, Instant)>,
+}
+
+impl<T: MenuTriggerEvent, I: Iterator<Item = T>> Iterator for Debounce<I> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            let event = self.events.next()?;
+            let Some(vk) = event.virtual_key_code() else {
+                return Some(event);
+            };
+            let (state, at) = (
+                event.key_state(),
+                event.received_at().unwrap_or_else(Instant::now),
+            );
+            match self.last.get(&vk) {
+                // Auto-repeat is not a state change.
+                Some(&(last_state, _)) if last_state == state => {}
+                Some(&(_, last_at)) if at.saturating_duration_since(last_at) < self.window => {
+                    continue;
+                }
+                _ => {
+                    self.last.insert(vk, (state, at));
+                }
+            }
+            return Some(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::Win32::UI::WindowsAndMessaging::KBDLLHOOKSTRUCT;
+
+    use super::*;
+    use crate::event_handler::{
+        KeyboardAndMouse::{VK_A, VK_LMENU, VK_LWIN},
+        KeyboardEvent, MenuTrigger, WmKeyState,
+    };
+
+    /// Returns an event of `vk` received `ms` milliseconds after `base`.
+    fn key(base: Instant, vk: VIRTUAL_KEY, down: bool, ms: u64) -> KeyboardEvent {
+        let state = if down {
+            WmKeyState::KeyDown
+        } else {
+            WmKeyState::KeyUp
+        };
+        let mut event = KeyboardEvent::new(
+            KBDLLHOOKSTRUCT {
+                vkCode: vk.0 as u32,
+                ..Default::default()
+            },
+            state,
+        );
+        event.timestamp = base + Duration::from_millis(ms);
+        event
+    }
+
+    /// Returns the key, direction and time of `events`, relative to `base`.
+    fn summary(
+        base: Instant,
+        events: impl IntoIterator<Item = KeyboardEvent>,
+    ) -> Vec<(VIRTUAL_KEY, bool, u128)> {
+        events
+            .into_iter()
+            .map(|e| {
+                let ms = e.timestamp.duration_since(base).as_millis();
+                (e.virtual_key(), e.is_key_down(), ms)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn filters_triggers_and_key_ups() {
+        let base = Instant::now();
+        let events = [
+            key(base, VK_LWIN, true, 0),
+            key(base, VK_A, true, 1),
+            key(base, VK_A, false, 2),
+            key(base, VK_LWIN, false, 3),
+            key(base, VK_LMENU, true, 4),
+        ];
+
+        assert_eq!(
+            summary(base, events.into_iter().only_triggers()),
+            [(VK_LWIN, true, 0), (VK_LWIN, false, 3), (VK_LMENU, true, 4)]
+        );
+        assert_eq!(
+            summary(base, events.into_iter().key_ups()),
+            [(VK_A, false, 2), (VK_LWIN, false, 3)]
+        );
+        assert_eq!(
+            summary(base, events.into_iter().only_triggers().key_ups()),
+            [(VK_LWIN, false, 3)]
+        );
+    }
+
+    #[test]
+    fn yields_holds_on_release() {
+        let base = Instant::now();
+        let events = [
+            key(base, VK_LWIN, true, 0),
+            key(base, VK_LWIN, true, 500),
+            key(base, VK_A, true, 510),
+            key(base, VK_A, false, 520),
+            key(base, VK_LWIN, false, 600),
+            key(base, VK_LMENU, true, 700),
+            key(base, VK_LMENU, false, 750),
+        ];
+
+        let holds: Vec<_> = events.into_iter().holds().collect();
+        assert_eq!(holds.len(), 2);
+
+        assert_eq!(holds[0].trigger, MenuTrigger::Win);
+        assert_eq!(holds[0].duration(), Duration::from_millis(600));
+        assert_eq!(holds[0].repeat_count(), 1);
+        assert!(holds[0].was_interrupted());
+
+        assert_eq!(holds[1].trigger, MenuTrigger::Alt);
+        assert_eq!(holds[1].duration(), Duration::from_millis(50));
+        assert_eq!(holds[1].repeat_count(), 0);
+        assert!(!holds[1].was_interrupted());
+    }
+
+    #[test]
+    fn debounce_drops_changes_within_the_window() {
+        let base = Instant::now();
+        let events = [
+            key(base, VK_A, true, 0),
+            // Bounce right after the press.
+            key(base, VK_A, false, 2),
+            // Other keys are debounced separately.
+            key(base, VK_LWIN, true, 3),
+            // Same state as the last accepted change, passed on like an auto-repeat.
+            key(base, VK_A, true, 4),
+            key(base, VK_A, false, 10),
+            key(base, VK_A, true, 12),
+            key(base, VK_A, true, 20),
+            key(base, VK_LWIN, false, 30),
+        ];
+
+        assert_eq!(
+            summary(base, events.into_iter().debounce(Duration::from_millis(5))),
+            [
+                (VK_A, true, 0),
+                (VK_LWIN, true, 3),
+                (VK_A, true, 4),
+                (VK_A, false, 10),
+                (VK_A, true, 20),
+                (VK_LWIN, false, 30),
+            ]
+        );
+    }
+
+    #[test]
+    fn debounce_accepts_changes_at_the_window() {
+        let base = Instant::now();
+        let events = [
+            key(base, VK_A, true, 0),
+            key(base, VK_A, false, 5),
+            key(base, VK_A, true, 9),
+        ];
+
+        assert_eq!(
+            summary(base, events.into_iter().debounce(Duration::from_millis(5))),
+            [(VK_A, true, 0), (VK_A, false, 5)]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Keyboard events and suppression for Tokio applications (`tokio` feature).
+//!
+//! [`start_keyboard_hook_async`] sends events from the hook thread straight into a
+//! [`tokio::sync::mpsc`] channel, so no bridge thread is needed. [`run_event_handler`]
+//! consumes them as a task:
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{async_hook, event_handler::Config};
+//!
+//! # async fn run() -> prevent_alt_win_menu::error::Result<()> {
+//! let (events, hook) = async_hook::start_keyboard_hook_async()?;
+//! // e.g. tokio::spawn(...)
+//! async_hook::run_event_handler(events, Config::default()).await;
+//! hook.shutdown().ok();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Events are sent with `try_send` from inside the hook procedure, which never waits.
+//! Unlike the queue of [`crate::keyboard_hook::start_keyboard_hook`], the channel allocates
+//! storage in blocks as it fills, so the hook procedure occasionally allocates.
+
+use std::sync::{Arc, RwLock};
+
+use tokio::sync::mpsc;
+
+use crate::{
+    error::Result,
+    event_handler::{Config, Handler, KeyboardEvent, MenuTriggerEvent, SharedConfig},
+    keyboard_hook::{self, HookOptions, HookThread},
+};
+
+/// How many events may be pending before further events are dropped.
+const CAPACITY: usize = 1024;
+
+/// Starts a global keyboard hook whose events are received from a Tokio channel.
+///
+/// # Errors
+/// Same as [`keyboard_hook::start_keyboard_hook`].
+pub fn start_keyboard_hook_async() -> Result<(mpsc::Receiver<KeyboardEvent>, HookThread)> {
+    start_keyboard_hook_async_with(HookOptions::default())
+}
+
+/// Like [`start_keyboard_hook_async`], with [`HookOptions`] applied inside the hook.
+///
+/// # Errors
+/// Same as [`keyboard_hook::start_keyboard_hook`].
+pub fn start_keyboard_hook_async_with(
+    options: HookOptions,
+) -> Result<(mpsc::Receiver<KeyboardEvent>, HookThread)> {
+    let (tx, rx) = mpsc::channel(CAPACITY);
+    let hook = keyboard_hook::start_keyboard_hook_callback(options, move |event| {
+        let _ = tx.try_send(event);
+    })?;
+    Ok((rx, hook))
+}
+
+/// Handles events from `rx` until the channel closes, i.e. until the hook is shut down.
+///
+/// The async counterpart of [`crate::event_handler::start_event_handler`]. Each event is
+/// handled synchronously as soon as it is received; handling only injects input and runs
+/// the callbacks of `config`, which should not block.
+pub async fn run_event_handler<T: MenuTriggerEvent + Clone>(
+    rx: mpsc::Receiver<T>,
+    config: Config<T>,
+) {
+    run_event_handler_shared(rx, Arc::new(RwLock::new(config))).await
+}
+
+/// Like [`run_event_handler`], but reads the configuration from a [`SharedConfig`] that can
+/// be swapped at runtime.
+pub async fn run_event_handler_shared<T: MenuTriggerEvent + Clone>(
+    mut rx: mpsc::Receiver<T>,
+    config: SharedConfig<T>,
+) {
+    #[cfg(feature = "log")]
+    log::debug!("started async event handler");
+
+    let mut handler = Handler::with_shared(config);
+    while let Some(event) = rx.recv().await {
+        handler.step(&event);
+    }
+}
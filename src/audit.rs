@@ -0,0 +1,290 @@
+//! A size-capped, rotating audit log of suppression decisions.
+//!
+//! Software that modifies input on shared terminals may have to keep a record of what it did.
+//! An [`AuditLog`] set with [`Config::set_audit_log`] appends one line per completed hold:
+//!
+//! ```text
+//! 2026-10-15T09:41:07.512Z Suppressed Win held_ms=183 process=explorer.exe
+//! 2026-10-15T09:41:09.004Z PassedThrough Alt held_ms=412 process=notepad.exe
+//! ```
+//!
+//! Once the file would exceed [`AuditLog::set_max_size`], it is renamed to `<path>.1`, older
+//! files are shifted to `<path>.2` and so on, and files beyond [`AuditLog::set_retention`]
+//! are deleted.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{audit::AuditLog, event_handler::Config};
+//!
+//! let audit = AuditLog::open(r"C:\ProgramData\Kiosk\suppression.log")?
+//!     .set_max_size(4 * 1024 * 1024)
+//!     .set_retention(10);
+//! let _handles = prevent_alt_win_menu::start(Config::default().set_audit_log(audit))?;
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! Write errors are reported as `Error::AuditLog` through `on_error`.
+//!
+//! [`Config::set_audit_log`]: crate::event_handler::Config::set_audit_log
+
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::{Error, Result},
+    record::SuppressionRecord,
+};
+
+/// The default of [`AuditLog::set_max_size`]: 1 MiB.
+pub const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+
+/// The default of [`AuditLog::set_retention`].
+pub const DEFAULT_RETENTION: usize = 5;
+
+/// Appends suppression decisions to a rotating file. See the [module docs](self).
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    max_size: u64,
+    retention: usize,
+    file: Mutex<Current>,
+}
+
+#[derive(Debug)]
+struct Current {
+    file: File,
+    size: u64,
+}
+
+impl AuditLog {
+    /// Opens `path` for appending, creating it if needed.
+    ///
+    /// # Errors
+    /// Returns `Error::AuditLog` if the file cannot be opened.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let current = Current::open(&path).map_err(Error::AuditLog)?;
+        Ok(Self {
+            path,
+            max_size: DEFAULT_MAX_SIZE,
+            retention: DEFAULT_RETENTION,
+            file: Mutex::new(current),
+        })
+    }
+
+    /// Sets the size in bytes at which the file is rotated. 1 MiB by default.
+    ///
+    /// # Returns
+    /// A modified [`AuditLog`] instance (builder pattern).
+    pub fn set_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Sets how many rotated files are kept besides the current one. 5 by default; with 0,
+    /// the file is truncated when it is full.
+    ///
+    /// # Returns
+    /// A modified [`AuditLog`] instance (builder pattern).
+    pub fn set_retention(mut self, files: usize) -> Self {
+        self.retention = files;
+        self
+    }
+
+    /// Appends the line of `record`, rotating the file first if it would exceed the maximum
+    /// size.
+    pub(crate) fn write(&self, record: &SuppressionRecord) -> io::Result<()> {
+        let line = format_line(record);
+        let mut current = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        if current.size > 0 && current.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+            *current = Current::open(&self.path)?;
+        }
+        current.file.write_all(line.as_bytes())?;
+        current.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&self) -> io::Result<()> {
+        if self.retention == 0 {
+            return fs::remove_file(&self.path);
+        }
+        match fs::remove_file(self.rotated(self.retention)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        for n in (1..self.retention).rev() {
+            match fs::rename(self.rotated(n), self.rotated(n + 1)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+    }
+
+    /// Returns the path of the `n`th rotated file, e.g. `audit.log.1`.
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{n}"));
+        path.into()
+    }
+}
+
+impl Current {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { file, size })
+    }
+}
+
+fn format_line(record: &SuppressionRecord) -> String {
+    let mut line = format!(
+        "{} {:?} {}",
+        format_utc(record.timestamp),
+        record.action,
+        record.trigger
+    );
+    if let Some(held) = record.hold_ms {
+        line.push_str(&format!(" held_ms={held}"));
+    }
+    if let Some(process) = &record.foreground_process {
+        line.push_str(&format!(" process={process}"));
+    }
+    line.push('\n');
+    line
+}
+
+/// Formats `time` as an RFC 3339 timestamp in UTC with milliseconds.
+fn format_utc(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01, after Howard Hinnant's `civil_from_days`.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{event_handler::MenuTrigger, observer::Outcome};
+
+    fn at(secs: u64, millis: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_millis(millis)
+    }
+
+    fn record(hold_ms: u64) -> SuppressionRecord {
+        SuppressionRecord {
+            timestamp: UNIX_EPOCH,
+            trigger: MenuTrigger::Win,
+            hold_ms: Some(hold_ms),
+            action: Outcome::Suppressed,
+            foreground_process: None,
+        }
+    }
+
+    /// Returns an empty directory for `name`, unique to this process.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("paw-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_utc(at(1_709_210_096, 789)),
+            "2024-02-29T12:34:56.789Z"
+        );
+        assert_eq!(format_utc(at(946_684_799, 999)), "1999-12-31T23:59:59.999Z");
+        assert_eq!(format_utc(at(946_684_800, 0)), "2000-01-01T00:00:00.000Z");
+        // 2100 is not a leap year.
+        assert_eq!(format_utc(at(4_107_542_400, 0)), "2100-03-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn formats_lines() {
+        assert_eq!(
+            format_line(&record(183)),
+            "1970-01-01T00:00:00.000Z Suppressed Win held_ms=183\n"
+        );
+    }
+
+    #[test]
+    fn rotates_and_keeps_the_retention() {
+        let dir = temp_dir("audit-rotation");
+        let path = dir.join("audit.log");
+        let line_len = format_line(&record(100)).len() as u64;
+        let log = AuditLog::open(&path)
+            .unwrap()
+            .set_max_size(2 * line_len)
+            .set_retention(2);
+        for hold_ms in 100..107 {
+            log.write(&record(hold_ms)).unwrap();
+        }
+
+        let read = |path: &Path| fs::read_to_string(path).unwrap();
+        assert_eq!(read(&path), format_line(&record(106)));
+        assert_eq!(
+            read(&log.rotated(1)),
+            format_line(&record(104)) + &format_line(&record(105))
+        );
+        assert_eq!(
+            read(&log.rotated(2)),
+            format_line(&record(102)) + &format_line(&record(103))
+        );
+        assert!(!log.rotated(3).exists());
+
+        drop(log);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn truncates_without_retention() {
+        let dir = temp_dir("audit-truncation");
+        let path = dir.join("audit.log");
+        let line_len = format_line(&record(100)).len() as u64;
+        let log = AuditLog::open(&path)
+            .unwrap()
+            .set_max_size(line_len)
+            .set_retention(0);
+        log.write(&record(100)).unwrap();
+        log.write(&record(101)).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format_line(&record(101))
+        );
+        assert!(!log.rotated(1).exists());
+
+        drop(log);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
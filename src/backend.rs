@@ -0,0 +1,82 @@
+//! Interchangeable sources of keyboard events.
+//!
+//! The event handler only needs an iterator of [`MenuTriggerEvent`]s. A [`HookBackend`]
+//! produces one, so the low-level hook, a thread hook, the Interception driver or a test
+//! double can be swapped without touching the handler. Start a backend together with the
+//! handler using [`crate::start_with_backend`].
+
+use std::sync::mpsc;
+
+use crate::{
+    error::Result,
+    event_handler::{KeyboardEvent, MenuTriggerEvent},
+    keyboard_hook::{self, HookOptions, HookThread, KeyboardEvents},
+    thread_hook::{self, ThreadHook},
+};
+
+/// A source of keyboard events for the event handler.
+pub trait HookBackend {
+    /// The event type produced by this backend.
+    type Event: MenuTriggerEvent + Clone + Send + 'static;
+    /// The stream of events passed to the event handler.
+    type Events: IntoIterator<Item = Self::Event> + Send + 'static;
+    /// A value that keeps the backend running, such as a thread handle or a guard.
+    type Handle;
+
+    /// Starts capturing events.
+    fn start(&self) -> Result<(Self::Events, Self::Handle)>;
+}
+
+/// The global low-level keyboard hook (`WH_KEYBOARD_LL`). This is what [`crate::start`] uses.
+#[derive(Debug, Clone, Default)]
+pub struct LowLevelHook {
+    /// Options applied inside the hook.
+    pub options: HookOptions,
+}
+
+impl HookBackend for LowLevelHook {
+    type Event = KeyboardEvent;
+    type Events = KeyboardEvents;
+    type Handle = HookThread;
+
+    fn start(&self) -> Result<(Self::Events, Self::Handle)> {
+        keyboard_hook::start_keyboard_hook_with(self.options.clone())
+    }
+}
+
+/// A keyboard hook for the calling thread only (`WH_KEYBOARD`).
+///
+/// See [`crate::thread_hook`]. [`HookBackend::start`] must be called on a thread that pumps
+/// messages, and the returned [`ThreadHook`] must be kept on that thread.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadKeyboardHook;
+
+impl HookBackend for ThreadKeyboardHook {
+    type Event = KeyboardEvent;
+    type Events = mpsc::Receiver<KeyboardEvent>;
+    type Handle = ThreadHook;
+
+    fn start(&self) -> Result<(Self::Events, Self::Handle)> {
+        thread_hook::start_thread_keyboard_hook()
+    }
+}
+
+/// The Interception kernel driver. See [`crate::interception`].
+#[cfg(feature = "interception")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InterceptionBackend;
+
+#[cfg(feature = "interception")]
+impl HookBackend for InterceptionBackend {
+    type Event = crate::interception::InterceptionEvent;
+    type Events = mpsc::Receiver<crate::interception::InterceptionEvent>;
+    type Handle = (
+        crate::interception::Interception,
+        std::thread::JoinHandle<()>,
+    );
+
+    fn start(&self) -> Result<(Self::Events, Self::Handle)> {
+        let (rx, interception, handle) = crate::interception::start_interception()?;
+        Ok((rx, (interception, handle)))
+    }
+}
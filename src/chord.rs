@@ -0,0 +1,156 @@
+//! Detection of system key chords that involve a trigger key.
+//!
+//! Some keys on modern keyboards are not delivered as keys of their own, but synthesized by
+//! Windows as a chord of modifiers and another key. Because the extra modifiers reset the
+//! regular hold tracking, these chords are recognized separately.
+
+use std::fmt::Display;
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    VIRTUAL_KEY, VK_CONTROL, VK_F23, VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_MENU,
+    VK_RCONTROL, VK_RMENU, VK_RSHIFT, VK_RWIN, VK_SHIFT,
+};
+
+use crate::event_handler::{HoldEvent, MenuTrigger, MenuTriggerEvent};
+
+/// A key chord synthesized by Windows for a dedicated key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SystemChord {
+    /// The Copilot key, delivered as `Win+Shift+F23`.
+    Copilot,
+    /// The Office key, delivered as `Win+Ctrl+Alt+Shift`.
+    Office,
+}
+
+impl Display for SystemChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SystemChord::Copilot => "Copilot",
+            SystemChord::Office => "Office",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+pub(crate) enum ChordEvent<T> {
+    /// The chord has just been recognized.
+    Detected(SystemChord),
+    /// The Win key of a recognized chord has been released.
+    Released(SystemChord, HoldEvent<T>),
+}
+
+/// Tracks pressed keys to recognize [`SystemChord`]s.
+pub(crate) struct ChordTracker<T> {
+    /// One bit per virtual key code that is currently pressed.
+    pressed: [u64; 4],
+    /// The press of the Win key that ends a recognized chord when released.
+    win_press: Option<T>,
+    detected: Option<SystemChord>,
+}
+
+impl<T> Default for ChordTracker<T> {
+    fn default() -> Self {
+        Self {
+            pressed: [0; 4],
+            win_press: None,
+            detected: None,
+        }
+    }
+}
+
+impl<T> ChordTracker<T> {
+    /// Forgets all pressed keys, because their releases may have been missed.
+    ///
+    /// A recognized chord is kept: it still ends with the release of Win, which may well
+    /// follow a focus change caused by the chord itself.
+    pub(crate) fn reset(&mut self) {
+        self.pressed = [0; 4];
+        self.win_press = None;
+    }
+
+    fn set_pressed(&mut self, vk: VIRTUAL_KEY, pressed: bool) {
+        let Some(word) = self.pressed.get_mut(usize::from(vk.0) / 64) else {
+            return;
+        };
+        let bit = 1u64 << (vk.0 % 64);
+        if pressed {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    fn is_pressed(&self, vk: VIRTUAL_KEY) -> bool {
+        self.pressed
+            .get(usize::from(vk.0) / 64)
+            .is_some_and(|word| word & (1u64 << (vk.0 % 64)) != 0)
+    }
+}
+
+impl<T: MenuTriggerEvent + Clone> ChordTracker<T> {
+    pub(crate) fn update(&mut self, event: &T) -> Option<ChordEvent<T>> {
+        let vk = event.virtual_key_code()?;
+        let is_win = matches!(vk, VK_LWIN | VK_RWIN);
+
+        if event.is_key_down() {
+            if is_win && !self.is_pressed(vk) {
+                self.win_press = Some(event.clone());
+            }
+            self.set_pressed(vk, true);
+
+            if self.detected.is_none()
+                && let Some(chord) = self.recognize(vk)
+            {
+                self.detected = Some(chord);
+                return Some(ChordEvent::Detected(chord));
+            }
+            None
+        } else {
+            self.set_pressed(vk, false);
+
+            if !is_win {
+                return None;
+            }
+            let press = self.win_press.take();
+
+            let chord = self.detected.take()?;
+            Some(ChordEvent::Released(
+                chord,
+                HoldEvent::new(
+                    MenuTrigger::Win,
+                    event.side(),
+                    press.unwrap_or_else(|| event.clone()),
+                    event.clone(),
+                ),
+            ))
+        }
+    }
+
+    /// Returns the chord recognized in the current key sequence, if any.
+    ///
+    /// While a chord is active, trigger releases belong to the chord and must not be
+    /// treated as regular holds.
+    pub(crate) fn active(&self) -> Option<SystemChord> {
+        self.detected
+    }
+
+    fn recognize(&self, vk: VIRTUAL_KEY) -> Option<SystemChord> {
+        let win = self.is_down(&[VK_LWIN, VK_RWIN]);
+        let shift = self.is_down(&[VK_SHIFT, VK_LSHIFT, VK_RSHIFT]);
+        let ctrl = self.is_down(&[VK_CONTROL, VK_LCONTROL, VK_RCONTROL]);
+        let alt = self.is_down(&[VK_MENU, VK_LMENU, VK_RMENU]);
+
+        if vk == VK_F23 && win && shift {
+            Some(SystemChord::Copilot)
+        } else if win && shift && ctrl && alt {
+            Some(SystemChord::Office)
+        } else {
+            None
+        }
+    }
+
+    fn is_down(&self, keys: &[VIRTUAL_KEY]) -> bool {
+        keys.iter().any(|&k| self.is_pressed(k))
+    }
+}
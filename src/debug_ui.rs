@@ -0,0 +1,164 @@
+//! A live viewer of events and suppression decisions (`debug-ui` feature).
+//!
+//! When tuning rules, it helps to see what the hook receives and what the event handler
+//! decides, as it happens. A [`DebugConsole`] opens a small console window that stays on
+//! top of other windows and prints:
+//!
+//! - every keyboard event the hook delivers, with the trigger keys currently held,
+//! - every completed hold, with its duration, interruption and the [`Outcome`],
+//! - hook re-registrations and dropped events.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{debug_ui::DebugConsole, event_handler::Config};
+//!
+//! let console = DebugConsole::open()?;
+//! let handles = prevent_alt_win_menu::start(Config::default().set_observer(console.clone()))?;
+//! console.watch(&handles.keyboard_hook)?;
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! In a process that already has a console, that console is used. Output goes to the
+//! standard output, so it interleaves with other output of the application.
+
+use std::{collections::BTreeSet, thread, time::Instant};
+
+use windows::{
+    Win32::{
+        System::Console::{AllocConsole, GetConsoleWindow, SetConsoleTitleW},
+        UI::WindowsAndMessaging::{HWND_TOPMOST, SWP_NOMOVE, SWP_NOSIZE, SetWindowPos},
+    },
+    core::w,
+};
+
+use crate::{
+    error::{Error, Result},
+    event_handler::{HoldEvent, KeyboardEvent, MenuTriggerEvent},
+    keyboard_hook::HookThread,
+    observer::{Observer, Outcome},
+};
+
+/// A console window showing live events and decisions. See the [module docs](self).
+///
+/// Cloning is cheap; all clones print to the same console.
+#[derive(Debug, Clone, Copy)]
+pub struct DebugConsole {
+    opened_at: Instant,
+}
+
+impl DebugConsole {
+    /// Opens the console window, or reuses the console of the process, and keeps it on top.
+    ///
+    /// # Errors
+    /// Returns `Error::DebugConsole` if no console could be allocated.
+    pub fn open() -> Result<Self> {
+        unsafe {
+            if GetConsoleWindow().is_invalid() {
+                AllocConsole().map_err(|e| Error::DebugConsole(e.into()))?;
+            }
+            let _ = SetConsoleTitleW(w!("prevent-alt-win-menu"));
+            let _ = SetWindowPos(
+                GetConsoleWindow(),
+                Some(HWND_TOPMOST),
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE,
+            );
+        }
+        Ok(Self {
+            opened_at: Instant::now(),
+        })
+    }
+
+    /// Prints the events delivered by `hook` on a new thread, until the hook shuts down.
+    ///
+    /// # Errors
+    /// Same as [`HookThread::subscribe`].
+    pub fn watch(&self, hook: &HookThread) -> Result<thread::JoinHandle<()>> {
+        let events = hook.subscribe()?;
+        let console = *self;
+        Ok(thread::spawn(move || {
+            let mut held = BTreeSet::new();
+            for event in events {
+                console.print_event(&event, &mut held);
+            }
+            console.print("hook shut down");
+        }))
+    }
+
+    fn print_event(&self, event: &KeyboardEvent, held: &mut BTreeSet<String>) {
+        if let Some(trigger) = event.menu_trigger() {
+            let key = match event.side() {
+                Some(side) => format!("{side} {trigger}"),
+                None => trigger.to_string(),
+            };
+            if event.is_key_down() {
+                held.insert(key);
+            } else {
+                held.remove(&key);
+            }
+        }
+
+        let mut flags = String::new();
+        if event.is_repeat() {
+            flags.push_str(" repeat");
+        }
+        if event.is_injected() {
+            flags.push_str(" injected");
+        }
+        self.print(&format!(
+            "{:<4} vk {:#04x} sc {:#04x}{flags:<18} held [{}]",
+            if event.is_key_down() { "down" } else { "up" },
+            event.kbd.vkCode,
+            event.kbd.scanCode,
+            held.iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ));
+    }
+
+    fn print(&self, line: &str) {
+        let at = self.opened_at.elapsed();
+        println!("{:>6}.{:03} {line}", at.as_secs(), at.subsec_millis());
+    }
+}
+
+impl<T: MenuTriggerEvent> Observer<T> for DebugConsole {
+    fn on_hook_registered(&self, thread_id: u32) {
+        self.print(&format!("hook registered on thread {thread_id}"));
+    }
+
+    fn on_hook_reinstalled(&self) {
+        self.print("hook re-registered by the watchdog, events may have been missed");
+    }
+
+    fn on_suppressed(&self, hold: &HoldEvent<T>, outcome: Outcome) {
+        let held = hold
+            .release
+            .elapsed_since(&hold.press)
+            .map_or_else(|| "?".to_owned(), |held| format!("{} ms", held.as_millis()));
+        let mut details = String::new();
+        if hold.was_interrupted() {
+            details.push_str(", interrupted");
+        }
+        if hold.repeat_count() > 0 {
+            details.push_str(&format!(", {} repeats", hold.repeat_count()));
+        }
+        if let Some(process) = hold
+            .foreground_at_release()
+            .and_then(|window| window.process_name.as_deref())
+        {
+            details.push_str(&format!(", in {process}"));
+        }
+        self.print(&format!(
+            "=> {} released after {held}{details}: {outcome:?}",
+            hold.trigger
+        ));
+    }
+
+    fn on_dropped_events(&self, count: u64) {
+        self.print(&format!("{count} events dropped, the handler fell behind"));
+    }
+}
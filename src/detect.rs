@@ -0,0 +1,59 @@
+//! Detection of the Start menu through UI Automation.
+//!
+//! Useful for checking that suppression works, e.g. in tests of applications that embed this
+//! crate. Detection looks for the `Windows.UI.Core.CoreWindow` named "Start" near the root of
+//! the UI Automation tree, so it is comparatively slow and should not be used on a hot path.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use prevent_alt_win_menu::detect::wait_for_start_menu;
+//!
+//! // ... press and release the Windows key ...
+//! assert!(!wait_for_start_menu(Duration::from_millis(500)));
+//! ```
+
+use std::time::Duration;
+
+use uiautomation::{UIAutomation, UIElement, UIMatcher};
+
+/// Localized names of the Start menu window.
+const START_MENU_NAMES: &[&str] = &["Start", "スタート"];
+
+/// Returns `true` if the Start menu is currently open.
+///
+/// Returns `false` if UI Automation is unavailable.
+pub fn start_menu_is_open() -> bool {
+    find_start_menu(Duration::ZERO)
+}
+
+/// Waits up to `timeout` for the Start menu to open.
+///
+/// Returns `true` as soon as it is found, or `false` if it did not open within `timeout` or
+/// UI Automation is unavailable.
+pub fn wait_for_start_menu(timeout: Duration) -> bool {
+    find_start_menu(timeout)
+}
+
+fn find_start_menu(timeout: Duration) -> bool {
+    let Ok(automation) = UIAutomation::new() else {
+        return false;
+    };
+    start_menu_matcher(&automation, timeout).is_some_and(|m| m.find_first().is_ok())
+}
+
+fn start_menu_matcher(automation: &UIAutomation, timeout: Duration) -> Option<UIMatcher> {
+    let root = automation.get_root_element().ok()?;
+
+    Some(
+        automation
+            .create_matcher()
+            .from_ref(&root)
+            .timeout(timeout.as_millis() as u64)
+            .depth(2)
+            .filter_fn(Box::new(|e: &UIElement| {
+                let name = e.get_name()?;
+                Ok(START_MENU_NAMES.contains(&name.as_str()))
+            }))
+            .classname("Windows.UI.Core.CoreWindow"),
+    )
+}
@@ -0,0 +1,386 @@
+//! Detection of other tools that hook the keyboard.
+//!
+//! Low-level keyboard hooks are called in reverse order of installation, and a tool that
+//! remaps or swallows Alt or Win keys can hide them from this crate, or act on them before
+//! the dummy key arrives. When suppression "doesn't work", running [`diagnose`] reveals the
+//! most common culprits.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::diagnostics::diagnose;
+//!
+//! for conflict in diagnose().conflicts {
+//!     eprintln!("warning: {conflict}");
+//! }
+//! ```
+//!
+//! For bug reports, [`JoinHandles::diagnostics`](crate::JoinHandles::diagnostics) returns a
+//! [`StateDump`] of the running suppression: whether the threads are alive, the holds in
+//! progress, a summary of the configuration, the statistics and the latest errors. With the
+//! `serde` feature, it can be serialized, e.g. to JSON.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use windows::{
+    Win32::{
+        Foundation::{CloseHandle, HWND, LPARAM},
+        System::Diagnostics::ToolHelp::{
+            CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW,
+            TH32CS_SNAPPROCESS,
+        },
+        UI::WindowsAndMessaging::{EnumWindows, GetClassNameW, GetWindowThreadProcessId},
+    },
+    core::BOOL,
+};
+
+use crate::{
+    elevation::Capabilities,
+    error::Error,
+    event_handler::Config,
+    foreground::process_name,
+    health::{self, Health},
+    keyboard_hook::hook_reinstall_count,
+    privacy,
+    stats::{self, Stats},
+};
+
+/// How many errors [`StateDump::last_errors`] keeps.
+const ERROR_CAPACITY: usize = 16;
+
+static PENDING_HOLDS: AtomicUsize = AtomicUsize::new(0);
+static LAST_ERRORS: Mutex<VecDeque<ErrorRecord>> = Mutex::new(VecDeque::new());
+
+/// A tool known to interfere with menu suppression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConflictingTool {
+    /// The Keyboard Manager of Microsoft PowerToys, which remaps keys with its own hook.
+    PowerToysKeyboardManager,
+    /// AutoHotkey, including compiled scripts. Its `~LWin Up` style hotkeys suppress menus
+    /// on their own.
+    AutoHotkey,
+    /// Kanata, a keyboard remapper.
+    Kanata,
+}
+
+impl ConflictingTool {
+    fn from_process_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name == "powertoys.keyboardmanagerengine.exe" {
+            Some(Self::PowerToysKeyboardManager)
+        } else if name.starts_with("autohotkey") && name.ends_with(".exe") {
+            Some(Self::AutoHotkey)
+        } else if name.starts_with("kanata") && name.ends_with(".exe") {
+            Some(Self::Kanata)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ConflictingTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PowerToysKeyboardManager => "PowerToys Keyboard Manager",
+            Self::AutoHotkey => "AutoHotkey",
+            Self::Kanata => "Kanata",
+        })
+    }
+}
+
+/// A running process of a [`ConflictingTool`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conflict {
+    /// The detected tool.
+    pub tool: ConflictingTool,
+    /// The ID of its process.
+    pub process_id: u32,
+    /// The executable file name of its process.
+    pub process_name: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is running ({}, pid {}) and may intercept Alt or Windows key events",
+            self.tool, self.process_name, self.process_id
+        )
+    }
+}
+
+/// The result of [`diagnose`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// Running tools that may conflict with suppression.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Diagnostics {
+    /// Returns `true` if any conflicting tool was found.
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+}
+
+/// Scans running processes and top-level windows for [`ConflictingTool`]s.
+///
+/// Processes are recognized by executable name; compiled AutoHotkey scripts, which can have
+/// any name, by the class of their main window.
+pub fn diagnose() -> Diagnostics {
+    let mut conflicts = process_conflicts();
+
+    let seen: HashSet<u32> = conflicts.iter().map(|c| c.process_id).collect();
+    for pid in autohotkey_window_processes() {
+        if !seen.contains(&pid) {
+            conflicts.push(Conflict {
+                tool: ConflictingTool::AutoHotkey,
+                process_id: pid,
+                process_name: process_name(pid).unwrap_or_default(),
+            });
+        }
+    }
+
+    #[cfg(feature = "log")]
+    for conflict in &conflicts {
+        log::warn!("{}", conflict);
+    }
+
+    Diagnostics { conflicts }
+}
+
+fn process_conflicts() -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let Ok(snapshot) = (unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) }) else {
+        return conflicts;
+    };
+
+    let mut entry = PROCESSENTRY32W {
+        dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+    let mut next = unsafe { Process32FirstW(snapshot, &mut entry) };
+    while next.is_ok() {
+        let len = entry
+            .szExeFile
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(entry.szExeFile.len());
+        let name = String::from_utf16_lossy(&entry.szExeFile[..len]);
+
+        if let Some(tool) = ConflictingTool::from_process_name(&name) {
+            conflicts.push(Conflict {
+                tool,
+                process_id: entry.th32ProcessID,
+                process_name: name,
+            });
+        }
+        next = unsafe { Process32NextW(snapshot, &mut entry) };
+    }
+
+    unsafe {
+        let _ = CloseHandle(snapshot);
+    }
+    conflicts
+}
+
+/// Returns the IDs of processes owning a top-level window of class `AutoHotkey`.
+fn autohotkey_window_processes() -> Vec<u32> {
+    let mut pids = Vec::new();
+    unsafe {
+        let _ = EnumWindows(
+            Some(enum_window_proc),
+            LPARAM(&mut pids as *mut Vec<u32> as isize),
+        );
+    }
+    pids.sort_unstable();
+    pids.dedup();
+    pids
+}
+
+unsafe extern "system" fn enum_window_proc(hwnd: HWND, l_param: LPARAM) -> BOOL {
+    let pids = unsafe { &mut *(l_param.0 as *mut Vec<u32>) };
+
+    let mut class = [0u16; 32];
+    let len = unsafe { GetClassNameW(hwnd, &mut class) } as usize;
+    if String::from_utf16_lossy(&class[..len]) == "AutoHotkey" {
+        let mut pid = 0;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+        pids.push(pid);
+    }
+
+    true.into()
+}
+
+/// A snapshot of the state of a running suppression, returned by
+/// [`JoinHandles::diagnostics`](crate::JoinHandles::diagnostics).
+///
+/// Attach its `Debug` output (or its JSON, with the `serde` feature) to bug reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StateDump {
+    /// The version of this crate.
+    pub version: &'static str,
+    /// Whether the hook thread is running and published a heartbeat recently.
+    pub hook_installed: bool,
+    /// Whether the keyboard hook thread is still running.
+    pub hook_thread_alive: bool,
+    /// Whether the event handler thread is still running.
+    pub handler_thread_alive: bool,
+    /// The number of trigger keys currently held, as tracked by the event handler.
+    pub pending_holds: usize,
+    /// How often the watchdog re-registered a removed hook.
+    pub hook_reinstalls: u64,
+    /// Whether [privacy mode](crate::privacy) is enabled.
+    pub privacy: bool,
+    /// What the process can inject input into.
+    pub capabilities: Capabilities,
+    /// The options of the active configuration.
+    pub config: ConfigSummary,
+    /// The counters of the event handler, see [`stats::counters`].
+    pub stats: Stats,
+    /// The heartbeats and queue counters, see [`health::health`].
+    pub health: Health,
+    /// The latest errors reported through `on_error`, oldest first.
+    pub last_errors: Vec<ErrorRecord>,
+    /// Running tools that may conflict with suppression, see [`diagnose`].
+    pub conflicts: Vec<Conflict>,
+}
+
+impl StateDump {
+    /// Takes a snapshot; the caller provides what only the [`crate::JoinHandles`] know.
+    pub(crate) fn capture<T>(
+        config: &Config<T>,
+        capabilities: Capabilities,
+        hook_thread_alive: bool,
+        handler_thread_alive: bool,
+    ) -> Self {
+        let health = health::health();
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            hook_installed: hook_thread_alive
+                && health
+                    .hook_heartbeat
+                    .is_some_and(|age| age <= 2 * health::HEARTBEAT_INTERVAL),
+            hook_thread_alive,
+            handler_thread_alive,
+            pending_holds: PENDING_HOLDS.load(Ordering::Relaxed),
+            hook_reinstalls: hook_reinstall_count(),
+            privacy: privacy::is_enabled(),
+            capabilities,
+            config: ConfigSummary::from(config),
+            stats: stats::counters(),
+            health,
+            last_errors: last_errors(),
+            conflicts: diagnose().conflicts,
+        }
+    }
+}
+
+/// The options of a [`Config`] that matter for bug reports. Callbacks are left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigSummary {
+    /// See [`Config::set_tap_threshold`].
+    pub tap_threshold: Duration,
+    /// See [`Config::set_cooldown`].
+    pub cooldown: Option<Duration>,
+    /// Whether a [`Config::set_schedule`] is set.
+    pub scheduled: bool,
+    /// Whether custom [`Config::set_triggers`] are set.
+    pub custom_triggers: bool,
+    /// See [`Config::set_handle_interrupted`].
+    pub handle_interrupted: bool,
+    /// See [`Config::set_reset_on_foreground_change`].
+    pub reset_on_foreground_change: bool,
+    /// See [`Config::set_track_mouse_clicks`].
+    pub track_mouse_clicks: bool,
+    /// See [`Config::set_pause_on_lock`].
+    pub pause_on_lock: bool,
+    /// The [`Config::set_remote_session_mode`], as its `Debug` output.
+    pub remote_session: String,
+    /// See [`Config::set_auto_close`].
+    pub auto_close: Option<Duration>,
+    /// See [`Config::set_verification`].
+    pub verify_window: Option<Duration>,
+    /// The number of [`Config::add_middleware`] calls.
+    pub middleware: usize,
+    /// See [`Config::set_batch_injections`].
+    pub batch_injections: bool,
+    /// See [`Config::set_injection_rate_limit`].
+    pub injection_rate_limit: Option<u32>,
+    /// See [`Config::set_collect_stats`].
+    pub collect_stats: bool,
+    /// Whether a [`Config::set_observer`] is set.
+    pub observer: bool,
+    /// Whether a [`Config::set_audit_log`] is set.
+    pub audit_log: bool,
+}
+
+impl<T> From<&Config<T>> for ConfigSummary {
+    fn from(config: &Config<T>) -> Self {
+        Self {
+            tap_threshold: config.tap_threshold,
+            cooldown: config.cooldown,
+            scheduled: config.schedule.is_some(),
+            custom_triggers: config.triggers.is_some(),
+            handle_interrupted: config.handle_interrupted,
+            reset_on_foreground_change: config.reset_on_foreground_change,
+            track_mouse_clicks: config.track_mouse_clicks,
+            pause_on_lock: config.pause_on_lock,
+            remote_session: format!("{:?}", config.remote_session),
+            auto_close: config.auto_close,
+            verify_window: config.verify_window,
+            middleware: config.middleware.len(),
+            batch_injections: config.batch_injections,
+            injection_rate_limit: config.injection_rate_limit,
+            collect_stats: config.collect_stats,
+            observer: config.observer.is_some(),
+            audit_log: config.audit_log.is_some(),
+        }
+    }
+}
+
+/// An error reported through `on_error`, kept for [`StateDump::last_errors`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorRecord {
+    /// When the error was reported.
+    pub timestamp: SystemTime,
+    /// The `Display` output of the error.
+    pub message: String,
+}
+
+/// Keeps `error` for [`StateDump::last_errors`], dropping the oldest beyond the capacity.
+pub(crate) fn record_error(error: &Error) {
+    let mut errors = LAST_ERRORS.lock().unwrap_or_else(PoisonError::into_inner);
+    if errors.len() == ERROR_CAPACITY {
+        errors.pop_front();
+    }
+    errors.push_back(ErrorRecord {
+        timestamp: SystemTime::now(),
+        message: error.to_string(),
+    });
+}
+
+fn last_errors() -> Vec<ErrorRecord> {
+    LAST_ERRORS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .cloned()
+        .collect()
+}
+
+/// Publishes the number of trigger keys the event handler tracks as held.
+pub(crate) fn set_pending_holds(count: usize) {
+    PENDING_HOLDS.store(count, Ordering::Relaxed);
+}
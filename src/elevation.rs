@@ -0,0 +1,110 @@
+//! Elevation checks for User Interface Privilege Isolation (UIPI).
+//!
+//! Windows silently drops input injected by a process into windows of a process with a higher
+//! integrity level: `SendInput` reports success, but an elevated foreground window never sees
+//! the dummy key, and the menu opens anyway. [`input_blocked_by_uipi`] detects that situation,
+//! and the event handler reports it as `Error::ElevatedForeground` after a suppression.
+//!
+//! Processes that run elevated or with `uiAccess` are not affected. [`Capabilities`] tells
+//! which applies to the current process; [`crate::start`] checks it once, logs a warning if
+//! elevated windows will not be covered, and exposes the result as
+//! [`JoinHandles::capabilities`](crate::JoinHandles::capabilities).
+
+use windows::Win32::{
+    Foundation::{CloseHandle, HANDLE},
+    Security::{
+        GetTokenInformation, TOKEN_ELEVATION, TOKEN_INFORMATION_CLASS, TOKEN_QUERY, TokenElevation,
+        TokenUIAccess,
+    },
+    System::Threading::{
+        GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+    },
+    UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId},
+};
+
+/// What the current process is allowed to inject input into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities {
+    /// The process runs elevated (as administrator).
+    pub elevated: bool,
+    /// The process has `uiAccess`, i.e. it is a signed accessibility tool installed in a
+    /// secure location with `uiAccess="true"` in its manifest.
+    pub ui_access: bool,
+}
+
+impl Capabilities {
+    /// Detects the capabilities of the current process.
+    pub fn current() -> Self {
+        let process = unsafe { GetCurrentProcess() };
+        Self {
+            elevated: is_elevated(process).unwrap_or(false),
+            ui_access: query_token::<u32>(process, TokenUIAccess).is_some_and(|v| v != 0),
+        }
+    }
+
+    /// Returns `true` if injected input also reaches elevated windows, so that suppression
+    /// works regardless of the foreground window.
+    pub fn covers_elevated_windows(&self) -> bool {
+        self.elevated || self.ui_access
+    }
+}
+
+/// Returns `true` if the current process runs elevated (as administrator).
+pub fn is_current_process_elevated() -> bool {
+    is_elevated(unsafe { GetCurrentProcess() }).unwrap_or(false)
+}
+
+/// Returns whether the process owning the foreground window runs elevated.
+///
+/// Returns `None` if there is no foreground window or its process cannot be opened at all.
+/// A process whose token cannot be queried from here is assumed to be elevated.
+pub fn is_foreground_elevated() -> Option<bool> {
+    // Not `ForegroundWindow::current`, which also queries the process name: this runs after
+    // every suppression and must not allocate.
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_invalid() {
+        return None;
+    }
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+    let elevated = is_elevated(process).unwrap_or(true);
+    unsafe {
+        let _ = CloseHandle(process);
+    }
+    Some(elevated)
+}
+
+/// Returns `true` if the foreground window belongs to an elevated process while this process
+/// is neither elevated nor has `uiAccess`, so that injected input does not reach it.
+pub fn input_blocked_by_uipi() -> bool {
+    is_foreground_elevated() == Some(true) && !Capabilities::current().covers_elevated_windows()
+}
+
+fn is_elevated(process: HANDLE) -> Option<bool> {
+    query_token::<TOKEN_ELEVATION>(process, TokenElevation).map(|e| e.TokenIsElevated != 0)
+}
+
+/// Queries fixed-size information of the token of `process`.
+fn query_token<T: Default>(process: HANDLE, class: TOKEN_INFORMATION_CLASS) -> Option<T> {
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) }.ok()?;
+
+    let mut info = T::default();
+    let mut len = 0;
+    let result = unsafe {
+        GetTokenInformation(
+            token,
+            class,
+            Some(&mut info as *mut T as *mut _),
+            std::mem::size_of::<T>() as u32,
+            &mut len,
+        )
+    };
+    unsafe {
+        let _ = CloseHandle(token);
+    }
+
+    result.ok().map(|()| info)
+}
@@ -6,6 +6,58 @@ pub enum Error {
     HookRegistrationFailed(std::io::Error),
     #[error("the hook thread terminated unexpectedly")]
     HookThreadCrashed,
+    #[error("failed to send a command to the hook thread")]
+    HookCommandFailed(std::io::Error),
+    #[error("the keyboard hook was removed by the system and has been registered again")]
+    HookReinstalled,
+    #[error("failed to read the configuration file")]
+    ConfigRead(std::io::Error),
+    #[error("failed to parse the configuration file: {0}")]
+    ConfigParse(String),
+    #[error("unsupported configuration file format: {}", .0.display())]
+    UnsupportedConfigFormat(std::path::PathBuf),
+    #[error("failed to install the WinEvent hook")]
+    WinEventHookFailed,
+    #[error("failed to register for session notifications")]
+    SessionNotificationFailed,
+    #[error("failed to send the dummy key")]
+    InjectionFailed(std::io::Error),
+    #[error("more than {0} injections per second, skipping until the rate drops")]
+    InjectionRateLimited(u32),
+    #[error("the foreground window belongs to an elevated process, which blocks injected input")]
+    ElevatedForeground,
+    #[cfg(feature = "interception")]
+    #[error("the Interception driver is not available")]
+    InterceptionUnavailable,
+    #[error("the menu opened despite suppression with every configured strategy")]
+    SuppressionFailed,
+    #[error("failed to subclass the window")]
+    SubclassFailed,
+    #[cfg(feature = "raw-window-handle")]
+    #[error("the window handle is not a Win32 window handle")]
+    UnsupportedWindowHandle,
+    #[error("the inline handler took {0:?}, exceeding its time budget")]
+    InlineBudgetExceeded(std::time::Duration),
+    #[cfg(feature = "event-log")]
+    #[error("failed to register the event log source")]
+    EventSourceRegistration(std::io::Error),
+    #[cfg(feature = "prometheus")]
+    #[error("failed to bind the Prometheus exporter")]
+    ExporterBind(std::io::Error),
+    #[error("failed to write the audit log")]
+    AuditLog(std::io::Error),
+    #[cfg(feature = "debug-ui")]
+    #[error("failed to open the debug console")]
+    DebugConsole(std::io::Error),
+    #[cfg(feature = "json")]
+    #[error("failed to write the recording")]
+    Recording(std::io::Error),
+    #[cfg(feature = "overlay")]
+    #[error("failed to create the overlay window")]
+    OverlayFailed(std::io::Error),
+    #[cfg(feature = "watch")]
+    #[error("failed to watch the configuration file")]
+    ConfigWatch(notify::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
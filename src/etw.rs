@@ -0,0 +1,110 @@
+//! ETW (TraceLogging) events for Windows-native tooling (`etw` feature).
+//!
+//! Hook lifecycle and suppression events are written to the TraceLogging provider
+//! `PreventAltWinMenu`, so that WPA, xperf or `tracelog` can correlate them with input
+//! latency traces of the system:
+//!
+//! ```text
+//! wpr -start GeneralProfile
+//! xperf -start paw -on *PreventAltWinMenu
+//! ```
+//!
+//! The provider is registered on the first event. Events cost next to nothing while no
+//! session listens.
+//!
+//! | Event                 | Fields                                |
+//! |-----------------------|---------------------------------------|
+//! | `HookRegistered`      | `ThreadId`                            |
+//! | `HookUnregistered`    | `ThreadId`                            |
+//! | `HookReinstalled`     | `Succeeded`                           |
+//! | `Suppressed`          | `Trigger`, `HeldMs`, `DummyKey`       |
+//! | `PassedThrough`       | `Trigger`, `HeldMs`, `Interrupted`    |
+//! | `InjectionFailed`     | `Error`                               |
+
+use std::sync::Once;
+
+use tracelogging as tlg;
+
+use crate::event_handler::MenuTrigger;
+
+tlg::define_provider!(PROVIDER, "PreventAltWinMenu");
+
+/// Registers the provider for the lifetime of the process, once.
+fn register() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        unsafe { PROVIDER.register() };
+    });
+}
+
+fn trigger_name(trigger: MenuTrigger) -> &'static str {
+    match trigger {
+        MenuTrigger::Win => "Win",
+        MenuTrigger::Alt => "Alt",
+    }
+}
+
+pub(crate) fn hook_registered(thread_id: u32) {
+    register();
+    tlg::write_event!(
+        PROVIDER,
+        "HookRegistered",
+        level(Informational),
+        u32("ThreadId", &thread_id),
+    );
+}
+
+pub(crate) fn hook_unregistered(thread_id: u32) {
+    register();
+    tlg::write_event!(
+        PROVIDER,
+        "HookUnregistered",
+        level(Informational),
+        u32("ThreadId", &thread_id),
+    );
+}
+
+pub(crate) fn hook_reinstalled(succeeded: bool) {
+    register();
+    tlg::write_event!(
+        PROVIDER,
+        "HookReinstalled",
+        level(Warning),
+        bool32("Succeeded", &(succeeded as i32)),
+    );
+}
+
+pub(crate) fn suppressed(trigger: MenuTrigger, held_ms: Option<u64>, dummy_key: u16) {
+    register();
+    tlg::write_event!(
+        PROVIDER,
+        "Suppressed",
+        level(Verbose),
+        str8("Trigger", trigger_name(trigger)),
+        u64("HeldMs", &held_ms.unwrap_or_default()),
+        u16("DummyKey", &dummy_key),
+    );
+}
+
+pub(crate) fn passed_through(trigger: MenuTrigger, held_ms: Option<u64>, interrupted: bool) {
+    register();
+    tlg::write_event!(
+        PROVIDER,
+        "PassedThrough",
+        level(Verbose),
+        str8("Trigger", trigger_name(trigger)),
+        u64("HeldMs", &held_ms.unwrap_or_default()),
+        bool32("Interrupted", &(interrupted as i32)),
+    );
+}
+
+pub(crate) fn injection_failed(error: &std::io::Error) {
+    let error = error.to_string();
+    register();
+    tlg::write_event!(
+        PROVIDER,
+        "InjectionFailed",
+        level(Error),
+        str8("Error", &error),
+    );
+}
@@ -12,19 +12,60 @@
 //! In other words, this module offers a flexible way to integrate with existing keyboard event sources
 //! and suppress menu activation accordingly.
 
-use std::{fmt::Display, thread, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    hash::{BuildHasher, Hasher, RandomState},
+    io,
+    sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
 
 use windows::Win32::{
     Foundation::{LPARAM, WPARAM},
+    System::SystemInformation::GetTickCount,
     UI::{
         Input::KeyboardAndMouse::{
-            INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, SendInput, VIRTUAL_KEY,
-            VK__none_, VK_LMENU, VK_LWIN, VK_MENU, VK_RMENU, VK_RWIN,
+            INPUT, INPUT_0, INPUT_KEYBOARD, KEYBD_EVENT_FLAGS, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY,
+            KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, KEYEVENTF_UNICODE, VIRTUAL_KEY, VK__none_, VK_F13,
+            VK_F14, VK_F15, VK_F16, VK_F17, VK_F18, VK_F19, VK_F20, VK_F21, VK_F22, VK_F23, VK_F24,
+            VK_LMENU, VK_LWIN, VK_MENU, VK_RMENU, VK_RWIN,
+        },
+        WindowsAndMessaging::{
+            LLKHF_ALTDOWN, LLKHF_EXTENDED, LLKHF_INJECTED, LLKHF_LOWER_IL_INJECTED, WM_KEYDOWN,
+            WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP,
         },
-        WindowsAndMessaging::{WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP},
     },
 };
 
+use crate::{
+    audit::AuditLog,
+    chord::{ChordEvent, ChordTracker, SystemChord},
+    diagnostics, elevation,
+    error::Error,
+    fallback::escalation_level,
+    foreground::{ForegroundWindow, foreground_change_count},
+    health,
+    holds::{self, HoldReceiver, HoldSender},
+    input,
+    keyboard_hook::{HookOptions, KeyboardEvents, hook_reinstall_count},
+    keyboard_state,
+    middleware::{EventMiddleware, Next},
+    mouse_hook::click_count,
+    observer::{Observer, Outcome},
+    record::SuppressionRecord,
+    schedule::Schedule,
+    self_test, session,
+    sound::{self, Sound},
+    stats,
+    strategy::{DummyKeyUp, ScanCodeKeyUp, SuppressionStrategy},
+    win_event::WinEvent,
+};
+
+#[cfg(feature = "json")]
+use crate::recording::Recorder;
+
 pub use windows::Win32::UI::WindowsAndMessaging::KBDLLHOOKSTRUCT;
 
 pub use windows::Win32::UI::Input::KeyboardAndMouse;
@@ -47,17 +88,68 @@ pub fn start_event_handler<
     rx: I,
     config: Config<T>,
 ) -> thread::JoinHandle<()> {
-    let mut handler = Handler {
-        config,
-        state: Default::default(),
-    };
+    start_event_handler_shared(rx, Arc::new(RwLock::new(config)))
+}
+
+/// A [`Config`] shared between the event handler and other threads.
+///
+/// Replacing the value behind the lock applies the new configuration to the running
+/// handler starting with the next event.
+pub type SharedConfig<T = KeyboardEvent> = Arc<RwLock<Config<T>>>;
+
+/// Starts an event-handling thread that reads its configuration from a [`SharedConfig`].
+///
+/// This behaves like [`start_event_handler`], but the configuration can be swapped
+/// at runtime by writing to `config`.
+pub fn start_event_handler_shared<
+    T: MenuTriggerEvent + Clone + Send + 'static,
+    I: IntoIterator<Item = T> + Send + 'static,
+>(
+    rx: I,
+    config: SharedConfig<T>,
+) -> thread::JoinHandle<()> {
+    spawn_handler(rx, Handler::with_shared(config))
+}
+
+/// Runs `handler` on a new thread with the events of a keyboard hook.
+///
+/// Unlike [`spawn_handler`], this looks ahead in the receiver, so that injections can be
+/// batched while events are queued (see [`Config::set_batch_injections`]).
+pub(crate) fn spawn_hook_handler(
+    events: KeyboardEvents,
+    mut handler: Handler,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        #[cfg(feature = "log")]
+        log::debug!("started event handler");
+
+        while let Some(mut event) = events.recv() {
+            loop {
+                let next = events.try_recv();
+                handler.step_queued(&event, next.is_some());
+                match next {
+                    Some(next) => event = next,
+                    None => break,
+                }
+            }
+        }
+    })
+}
 
+/// Runs `handler` on a new thread with the events of `rx`.
+pub(crate) fn spawn_handler<
+    T: MenuTriggerEvent + Clone + Send + 'static,
+    I: IntoIterator<Item = T> + Send + 'static,
+>(
+    rx: I,
+    mut handler: Handler<T>,
+) -> thread::JoinHandle<()> {
     thread::spawn(move || {
         #[cfg(feature = "log")]
         log::debug!("started event handler");
 
         for event in rx {
-            handler.handle_keyboard_event(&event);
+            handler.step(&event);
         }
     })
 }
@@ -76,6 +168,13 @@ pub trait MenuTriggerEvent {
     /// Returns the current state of the key (pressed or released).
     fn key_state(&self) -> KeyState;
 
+    /// Returns whether the left or right key was used, if known.
+    ///
+    /// The default implementation returns `None`.
+    fn side(&self) -> Option<Side> {
+        None
+    }
+
     /// Returns `true` if the key is currently pressed. (Default implementation provided.)
     fn is_key_down(&self) -> bool {
         matches!(self.key_state(), KeyState::Down)
@@ -85,10 +184,53 @@ pub trait MenuTriggerEvent {
     fn is_key_up(&self) -> bool {
         !self.is_key_down()
     }
+
+    /// Returns `true` if this is an auto-repeat key-down, i.e. the key was already held.
+    ///
+    /// The event handler coalesces repeats into the pending hold (see
+    /// [`HoldEvent::repeat_count`]); other consumers can use this to skip them explicitly.
+    /// The default implementation returns `false`.
+    fn is_repeat(&self) -> bool {
+        false
+    }
+
+    /// Returns the virtual key code of the event, if the event type carries one.
+    ///
+    /// Used to match events against [`Config::triggers`]. The default implementation returns `None`.
+    fn virtual_key_code(&self) -> Option<VIRTUAL_KEY> {
+        None
+    }
+
+    /// Returns the hardware scan code of the event, if the event type carries one.
+    ///
+    /// Used to match events against [`Config::triggers`]. The default implementation returns `None`.
+    fn scan_code(&self) -> Option<u32> {
+        None
+    }
+
+    /// Returns the time elapsed since `earlier`, if the event type carries timestamps.
+    ///
+    /// The default implementation returns `None`.
+    fn elapsed_since(&self, _earlier: &Self) -> Option<Duration>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Returns when the event was received from the system, if the event type carries
+    /// timestamps.
+    ///
+    /// Used to record the latency of the event handler (see [`crate::stats::latency`]). The
+    /// default implementation returns `None`.
+    fn received_at(&self) -> Option<Instant> {
+        None
+    }
 }
 
 /// Indicates which modifier key was used to trigger a menu.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MenuTrigger {
     /// The Windows key (either left or right).
     Win,
@@ -106,6 +248,77 @@ impl Display for MenuTrigger {
     }
 }
 
+/// Indicates whether the left or right instance of a modifier key was used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    /// The left key, e.g. `LWin` or `LAlt`.
+    Left,
+    /// The right key, e.g. `RWin` or `RAlt`.
+    Right,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Side::Left => "left",
+            Side::Right => "right",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A key treated as a menu trigger, see [`Config::set_triggers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerKey {
+    /// The virtual key code to match.
+    pub virtual_key: VIRTUAL_KEY,
+    /// If set, only events with this hardware scan code match.
+    ///
+    /// Useful to tell a physical key apart from the same virtual key sent by
+    /// a macro pad or remapping software.
+    pub scan_code: Option<u32>,
+    /// The trigger this key counts as.
+    pub trigger: MenuTrigger,
+}
+
+impl TriggerKey {
+    /// Creates a trigger key matching any scan code.
+    pub const fn new(virtual_key: VIRTUAL_KEY, trigger: MenuTrigger) -> Self {
+        Self {
+            virtual_key,
+            scan_code: None,
+            trigger,
+        }
+    }
+
+    /// Restricts the trigger key to the given hardware scan code.
+    ///
+    /// # Returns
+    /// A modified [`TriggerKey`] instance (builder pattern).
+    pub const fn set_scan_code(mut self, scan_code: u32) -> Self {
+        self.scan_code = Some(scan_code);
+        self
+    }
+
+    /// Returns the keys that are treated as triggers by default:
+    /// `VK_LWIN`, `VK_RWIN`, `VK_MENU`, `VK_LMENU` and `VK_RMENU`.
+    pub fn defaults() -> Vec<TriggerKey> {
+        vec![
+            TriggerKey::new(VK_LWIN, MenuTrigger::Win),
+            TriggerKey::new(VK_RWIN, MenuTrigger::Win),
+            TriggerKey::new(VK_MENU, MenuTrigger::Alt),
+            TriggerKey::new(VK_LMENU, MenuTrigger::Alt),
+            TriggerKey::new(VK_RMENU, MenuTrigger::Alt),
+        ]
+    }
+
+    fn matches(&self, virtual_key: Option<VIRTUAL_KEY>, scan_code: Option<u32>) -> bool {
+        virtual_key == Some(self.virtual_key)
+            && self.scan_code.is_none_or(|sc| scan_code == Some(sc))
+    }
+}
+
 /// Represents the state of a key: pressed or released.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeyState {
@@ -115,26 +328,607 @@ pub enum KeyState {
     Up,
 }
 
-struct Handler<T = KeyboardEvent> {
-    config: Config<T>,
+/// The suppression logic, fed one event at a time.
+///
+/// This is what the thread started by [`start_event_handler`] runs. Use it directly to
+/// handle events on a thread of your own, e.g. once per frame in a game loop (see
+/// [`crate::pump::EventPump`]).
+///
+/// ```no_run
+/// use prevent_alt_win_menu::event_handler::{Config, Handler};
+///
+/// let (events, _hook) = prevent_alt_win_menu::keyboard_hook::start_keyboard_hook()?;
+/// let mut handler = Handler::new(Config::default());
+/// loop {
+///     for event in events.try_iter() {
+///         handler.step(&event);
+///     }
+///     // render the frame ...
+/// #   break;
+/// }
+/// # Ok::<(), prevent_alt_win_menu::error::Error>(())
+/// ```
+pub struct Handler<T = KeyboardEvent> {
+    config: SharedConfig<T>,
     state: HoldStates<T>,
+    last_injection: HashMap<MenuTrigger, Instant>,
+    rotation: usize,
+    chords: ChordTracker<T>,
+    foreground_changes: u64,
+    hook_reinstalls: u64,
+    session_resumes: u64,
+    rate_window: Option<(Instant, u32)>,
+    rate_limited_presses: HashSet<HoldKey>,
+    deferred: Vec<DecidedHold<T>>,
+    hold_senders: Vec<HoldSender<T>>,
+    dropped_events: u64,
+    notices_shown: u32,
+}
+
+impl<T> Handler<T> {
+    /// Creates a handler with a fixed configuration.
+    pub fn new(config: Config<T>) -> Self {
+        Self::with_shared(Arc::new(RwLock::new(config)))
+    }
+
+    /// Creates a handler that reads its configuration from a [`SharedConfig`], so that it
+    /// can be swapped between events.
+    pub fn with_shared(config: SharedConfig<T>) -> Self {
+        Self {
+            config,
+            state: Default::default(),
+            last_injection: Default::default(),
+            rotation: 0,
+            chords: Default::default(),
+            foreground_changes: foreground_change_count(),
+            hook_reinstalls: hook_reinstall_count(),
+            session_resumes: session::resume_count(),
+            rate_window: None,
+            rate_limited_presses: HashSet::new(),
+            deferred: Vec::new(),
+            hold_senders: Vec::new(),
+            dropped_events: health::dropped_events(),
+            notices_shown: 0,
+        }
+    }
+
+    /// Returns a receiver of every hold this handler completes, after the suppression
+    /// decision was made (see [`HoldEvent::was_suppressed`]).
+    pub fn subscribe_holds(&mut self) -> HoldReceiver<T> {
+        let (tx, rx) = holds::channel();
+        self.hold_senders.push(tx);
+        rx
+    }
+
+    /// Reports `error` through the configured `on_error` callback.
+    pub(crate) fn report(&self, error: &Error) {
+        (read_config(&self.config).on_error)(error);
+    }
+
+    /// Counts an injection against the rate limit, returning `false` if it must be skipped.
+    ///
+    /// Skipped injections are counted in [`stats::rate_limited`]; the first one of each
+    /// second is reported as [`Error::InjectionRateLimited`].
+    fn allow_injection(&mut self, config: &Config<T>) -> bool {
+        let Some(limit) = config.injection_rate_limit else {
+            return true;
+        };
+        let now = Instant::now();
+        let (start, count) = match self.rate_window {
+            Some((start, count)) if now.duration_since(start) < Duration::from_secs(1) => {
+                (start, count)
+            }
+            _ => (now, 0),
+        };
+        self.rate_window = Some((start, count.saturating_add(1)));
+        if count < limit {
+            return true;
+        }
+
+        stats::record_rate_limited();
+        if count == limit {
+            #[cfg(feature = "log")]
+            log::warn!("more than {} injections per second, skipping", limit);
+            (config.on_error)(&Error::InjectionRateLimited(limit));
+        }
+        false
+    }
+}
+
+impl<T: MenuTriggerEvent> Handler<T> {
+    /// Returns whether the injections that complete `hold` were skipped by the rate limit.
+    ///
+    /// Strategies that inject on press were counted when the trigger was pressed, and the
+    /// release must follow that decision; other strategies are counted only if `suppress`
+    /// is about to inject.
+    fn release_rate_limited(
+        &mut self,
+        hold: &HoldEvent<T>,
+        suppress: bool,
+        config: &Config<T>,
+    ) -> bool {
+        if self
+            .rate_limited_presses
+            .remove(&HoldKey::of(hold.trigger, &hold.press))
+        {
+            return true;
+        }
+        suppress
+            && !config.strategy_for(hold.trigger).injects_on_press()
+            && !self.allow_injection(config)
+    }
 }
 
 impl<T: MenuTriggerEvent + Clone> Handler<T> {
-    fn handle_keyboard_event(&mut self, event: &T) {
-        if let Some((_trigger, hold)) = self.state.update(event.clone()) {
-            if let Some(dummy_key) = (self.config.on_released)(hold) {
-                if let Err(_e) = send_keyup(dummy_key) {
+    /// Handles a single event, injecting input as needed.
+    ///
+    /// Events must be passed in the order they were received, and all of them, not only
+    /// menu triggers: other keys decide whether a hold was interrupted.
+    pub fn step(&mut self, event: &T) {
+        self.step_queued(event, false);
+    }
+
+    /// Like [`Handler::step`], but if [`Config::set_batch_injections`] is enabled, input is
+    /// held back while `more_queued` tells that the receiver has further events ready.
+    pub(crate) fn step_queued(&mut self, event: &T, more_queued: bool) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "handle_event",
+            vk = event.virtual_key_code().map(|vk| vk.0),
+            down = event.is_key_down(),
+        )
+        .entered();
+
+        let batch = read_config(&self.config).batch_injections;
+        if batch {
+            input::defer();
+        }
+        self.handle_keyboard_event(event);
+        diagnostics::set_pending_holds(self.state.held_count());
+        // Keep deferring while more events are queued, then inject everything at once.
+        if !(batch && more_queued) {
+            self.flush();
+        }
+        if let Some(received_at) = event.received_at() {
+            stats::record_latency(received_at);
+        }
+    }
+
+    /// Passes `event` through the middleware of the configuration, then handles it.
+    pub(crate) fn handle_keyboard_event(&mut self, event: &T) {
+        let shared = self.config.clone();
+        let config = read_config(&shared);
+        if config.middleware.is_empty() {
+            self.suppress_stage(event, &config);
+        } else {
+            Next::new(&config, self).run(event.clone());
+        }
+    }
+
+    /// The last stage of the middleware chain, which tracks holds and suppresses menus.
+    pub(crate) fn suppress_stage(&mut self, event: &T, config: &Config<T>) {
+        if config.collect_stats {
+            stats::record_event();
+        }
+        let trigger = config.trigger_of(event);
+
+        // Other keys matter while a trigger is held, since they interrupt the hold.
+        #[cfg(feature = "json")]
+        if let Some(recorder) = &config.recorder
+            && (trigger.is_some() || self.state.held_count() > 0)
+            && let Err(e) = recorder.record_event(event, trigger)
+        {
+            (config.on_error)(&Error::Recording(e));
+        }
+
+        let reinstalls = hook_reinstall_count();
+        if reinstalls != self.hook_reinstalls {
+            // Events were missed while the hook was gone, so pending holds are unreliable.
+            self.hook_reinstalls = reinstalls;
+            self.reset(config);
+            (config.on_error)(&Error::HookReinstalled);
+            if let Some(observer) = &config.observer {
+                observer.on_hook_reinstalled();
+            }
+        }
+
+        if let Some(observer) = &config.observer {
+            let dropped = health::dropped_events();
+            if dropped != self.dropped_events {
+                observer.on_dropped_events(dropped.wrapping_sub(self.dropped_events));
+                self.dropped_events = dropped;
+            }
+        }
+
+        if config.pause_on_lock {
+            let resumes = session::resume_count();
+            if session::is_paused() || resumes != self.session_resumes {
+                #[cfg(feature = "log")]
+                log::debug!("session locked or resumed, reset hold state");
+                self.session_resumes = resumes;
+                self.reset(config);
+            }
+            if session::is_paused() {
+                return;
+            }
+        }
+
+        if config.remote_session == RemoteSessionMode::Pause && session::is_remote_session() {
+            self.reset(config);
+            return;
+        }
+
+        if config.reset_on_foreground_change {
+            let changes = foreground_change_count();
+            if changes != self.foreground_changes {
+                #[cfg(feature = "log")]
+                log::debug!("foreground window changed, reset hold state");
+                self.foreground_changes = changes;
+                self.reset(config);
+            }
+        }
+
+        if let Some(trigger) = trigger
+            && event.is_key_down()
+            && !self.state.is_held(HoldKey::of(trigger, event))
+        {
+            let key = HoldKey::of(trigger, event);
+            let strategy = config.strategy_for(trigger);
+            self.rate_limited_presses.remove(&key);
+            if strategy.injects_on_press() && !self.allow_injection(config) {
+                // Remembered so that the release does not complete an injection never made.
+                self.rate_limited_presses.insert(key);
+            } else if let Err(e) = strategy.on_press(event) {
+                #[cfg(feature = "log")]
+                log::error!("failed to prepare {} menu suppression: {:?}", trigger, e);
+                injection_failed(config, e);
+            }
+        }
+
+        match self.chords.update(event) {
+            Some(ChordEvent::Detected(chord)) => {
+                #[cfg(feature = "log")]
+                log::info!("{} key pressed", chord);
+
+                if let Some(on_chord) = &config.on_chord {
+                    on_chord(chord);
+                }
+            }
+            Some(ChordEvent::Released(chord, hold))
+                if config.suppressed_chords.contains(&chord) =>
+            {
+                let dummy_key = self.choose_dummy_key(VK__none_, config);
+                let strategy = config.strategy_for(hold.trigger);
+                if self.release_rate_limited(&hold, true, config) {
+                    #[cfg(feature = "log")]
+                    log::debug!("{} action not prevented, rate limited", chord);
+                } else if strategy.injects_on_press() {
+                    // The strategy released its key when the chord was detected, while Win
+                    // was still held; see `Handler::reset_holds`.
+                    stats::record_suppression(hold.trigger, config.collect_stats);
+                } else if let Err(e) = strategy.suppress(&hold, dummy_key) {
                     #[cfg(feature = "log")]
-                    log::error!("failed to prevent {} menu: {:?}", _trigger, _e);
+                    log::error!("failed to prevent {} action: {:?}", chord, e);
+                    injection_failed(config, e);
                 } else {
                     #[cfg(feature = "log")]
-                    log::info!("prevented {} menu by sending {:?}", _trigger, dummy_key);
+                    log::info!("prevented {} action by sending {:?}", chord, dummy_key);
+                    stats::record_suppression(hold.trigger, config.collect_stats);
+                }
+            }
+            _ => {}
+        }
+
+        if self.chords.active().is_some() {
+            // Releases that are part of a chord are handled above, not as regular holds.
+            self.reset_holds(config);
+            return;
+        }
+
+        if let Some(hold) = self
+            .state
+            .update(event.clone(), trigger, TrackingOptions::from(config))
+        {
+            let trigger = hold.trigger;
+            let is_tap = hold
+                .release
+                .elapsed_since(&hold.press)
+                .is_none_or(|held| held <= config.tap_threshold)
+                && !hold.was_interrupted();
+
+            #[cfg(feature = "tracing")]
+            let span = tracing::info_span!(
+                "hold",
+                trigger = %trigger,
+                held_ms = hold
+                    .release
+                    .elapsed_since(&hold.press)
+                    .map(|held| held.as_millis() as u64),
+                interrupted = hold.was_interrupted(),
+                outcome = tracing::field::Empty,
+            );
+            #[cfg(feature = "tracing")]
+            let _entered = span.clone().entered();
+
+            if !hold.was_interrupted()
+                && let Some(held) = hold.release.elapsed_since(&hold.press)
+            {
+                stats::record_hold(
+                    trigger,
+                    held,
+                    &config.hold_duration_buckets,
+                    config.collect_stats,
+                );
+            }
+
+            let decision = self.decide(trigger, &hold, config);
+            let mut decided = DecidedHold {
+                hold,
+                outcome: Outcome::PassedThrough,
+                dummy_key: VK__none_,
+                is_tap,
+                #[cfg(feature = "tracing")]
+                span,
+            };
+
+            if self.release_rate_limited(&decided.hold, decision.is_some(), config) {
+                // Counted by the rate limit already; nothing is injected, not even a release.
+                decided.outcome = Outcome::RateLimited;
+            } else if let Some(dummy_key) = decision {
+                decided.dummy_key = self.choose_dummy_key(dummy_key, config);
+                let strategy = config.strategy_for(trigger);
+                decided.outcome = match strategy.suppress(&decided.hold, decided.dummy_key) {
+                    Ok(()) => Outcome::Suppressed,
+                    Err(e) => {
+                        #[cfg(feature = "log")]
+                        log::error!("failed to prevent {} menu: {:?}", trigger, e);
+                        injection_failed(config, e);
+                        Outcome::Failed
+                    }
+                };
+            } else {
+                if config.collect_stats {
+                    stats::record_pass_through();
                 }
+                if let Err(e) = config.strategy_for(trigger).pass_through(&decided.hold) {
+                    injection_failed(config, e);
+                }
+            }
+
+            if input::is_deferring() {
+                // Reported by `Handler::flush` once the batch was actually injected.
+                self.deferred.push(decided);
             } else {
+                self.complete(decided, config);
+            }
+        }
+    }
+
+    /// Forgets all pressed keys, because events were missed or are stale.
+    ///
+    /// Unlike [`Handler::reset_holds`], this also forgets the keys tracked for chords, so that
+    /// a key whose release was missed does not take part in later chords.
+    fn reset(&mut self, config: &Config<T>) {
+        self.chords.reset();
+        self.reset_holds(config);
+    }
+
+    /// Forgets all pending holds, because events were missed or belong to a chord.
+    ///
+    /// Strategies that inject on press have already pressed their key for these holds, so it
+    /// is released through [`SuppressionStrategy::pass_through`] instead of staying pressed.
+    fn reset_holds(&mut self, config: &Config<T>) {
+        for (key, press) in self.state.take_pending() {
+            let strategy = config.strategy_for(key.trigger);
+            if !strategy.injects_on_press() || self.rate_limited_presses.contains(&key) {
+                continue;
+            }
+            let hold = HoldEvent::new(key.trigger, press.side(), press.clone(), press);
+            if let Err(e) = strategy.pass_through(&hold) {
+                #[cfg(feature = "log")]
+                log::error!("failed to release {} pre-emptive key: {:?}", key.trigger, e);
+                injection_failed(config, e);
+            }
+        }
+    }
+
+    /// Injects the input deferred while batching and reports the holds waiting for it.
+    fn flush(&mut self) {
+        let result = input::flush();
+        let shared = self.config.clone();
+        let config = read_config(&shared);
+        let failed = result.is_err();
+        if let Err(e) = result {
+            #[cfg(feature = "log")]
+            log::error!("failed to inject batched input: {:?}", e);
+            injection_failed(&config, e);
+        }
+
+        for mut decided in std::mem::take(&mut self.deferred) {
+            if failed && decided.outcome == Outcome::Suppressed {
+                decided.outcome = Outcome::Failed;
+            }
+            self.complete(decided, &config);
+        }
+    }
+
+    /// Reports a hold whose input was injected: updates the state that depends on a
+    /// successful suppression, runs the callbacks and notifies observers and subscribers.
+    fn complete(&mut self, decided: DecidedHold<T>, config: &Config<T>) {
+        let DecidedHold {
+            mut hold,
+            outcome,
+            dummy_key,
+            is_tap,
+            #[cfg(feature = "tracing")]
+            span,
+        } = decided;
+        let trigger = hold.trigger;
+        #[cfg(not(any(feature = "etw", feature = "log")))]
+        let _ = dummy_key;
+
+        #[cfg(feature = "tracing")]
+        let span = span.entered();
+        #[cfg(feature = "tracing")]
+        span.record(
+            "outcome",
+            match outcome {
+                Outcome::Suppressed => "suppressed",
+                Outcome::PassedThrough => "passed_through",
+                Outcome::RateLimited => "rate_limited",
+                Outcome::Failed => "failed",
+            },
+        );
+
+        match outcome {
+            Outcome::Suppressed => {
+                #[cfg(feature = "log")]
+                log::info!("prevented {} menu by sending {:?}", trigger, dummy_key);
+                // A failed injection neither starts the cooldown nor counts as recent.
+                self.last_injection.insert(trigger, Instant::now());
+                *LAST_INJECTION
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner) = Some(Instant::now());
+                hold.suppressed = true;
+                #[cfg(feature = "etw")]
+                crate::etw::suppressed(
+                    trigger,
+                    hold.release
+                        .elapsed_since(&hold.press)
+                        .map(|held| held.as_millis() as u64),
+                    dummy_key.0,
+                );
+                stats::record_suppression(trigger, config.collect_stats);
+
+                if elevation::input_blocked_by_uipi() {
+                    #[cfg(feature = "log")]
+                    log::warn!("the dummy key was probably dropped by UIPI");
+                    (config.on_error)(&Error::ElevatedForeground);
+                }
+
+                if let Some(sound) = config.suppression_sound {
+                    sound::play(sound);
+                }
+
+                if let Some(on_tap) = config.on_tap.as_ref().filter(|_| is_tap) {
+                    on_tap(trigger);
+                }
+
+                if let Some(notice) = &config.suppression_notice
+                    && self.notices_shown < config.suppression_notice_limit
+                {
+                    self.notices_shown += 1;
+                    notice(trigger);
+                }
+            }
+            Outcome::PassedThrough => {
+                #[cfg(feature = "etw")]
+                crate::etw::passed_through(
+                    trigger,
+                    hold.release
+                        .elapsed_since(&hold.press)
+                        .map(|held| held.as_millis() as u64),
+                    hold.was_interrupted(),
+                );
+            }
+            Outcome::RateLimited => {
                 #[cfg(feature = "log")]
-                log::info!("{} key released, but did not prevent menu", _trigger);
+                log::debug!("{} key released, but injection was rate limited", trigger);
+            }
+            Outcome::Failed => {}
+        }
+
+        if config.observer.is_some() || config.audit_log.is_some() {
+            let record = SuppressionRecord::new(&hold, outcome);
+            if let Some(observer) = &config.observer {
+                observer.on_suppressed(&hold, outcome);
+                observer.on_record(&record);
+            }
+            if let Some(audit_log) = &config.audit_log
+                && let Err(e) = audit_log.write(&record)
+            {
+                (config.on_error)(&Error::AuditLog(e));
+            }
+        }
+        #[cfg(feature = "json")]
+        if let Some(recorder) = &config.recorder
+            && let Err(e) = recorder.record_decision(&hold, outcome)
+        {
+            (config.on_error)(&Error::Recording(e));
+        }
+        self_test::record_decision(outcome);
+        if outcome != Outcome::PassedThrough {
+            *LAST_SUPPRESSION
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(LastSuppression {
+                trigger,
+                at: Instant::now(),
+                held: hold.release.elapsed_since(&hold.press),
+                action: outcome,
+            });
+        }
+        self.hold_senders.retain(|tx| tx.send(&hold));
+    }
+
+    /// Returns the dummy key to send, or `None` if the menu should not be suppressed.
+    fn decide(
+        &self,
+        trigger: MenuTrigger,
+        hold: &HoldEvent<T>,
+        config: &Config<T>,
+    ) -> Option<VIRTUAL_KEY> {
+        if hold.was_interrupted() && !config.handle_interrupted {
+            return None;
+        }
+
+        if !config.schedule.as_ref().is_none_or(Schedule::is_active) {
+            #[cfg(feature = "log")]
+            log::debug!("{} key released outside of the schedule, skipped", trigger);
+            return None;
+        }
+
+        if self.is_cooling_down(trigger, config) {
+            #[cfg(feature = "log")]
+            log::debug!("{} key released during cooldown, skipped", trigger);
+            return None;
+        }
+
+        let dummy_key = (config.on_released)(hold.clone());
+
+        #[cfg(feature = "log")]
+        if dummy_key.is_none() {
+            log::info!("{} key released, but did not prevent menu", trigger);
+        }
+
+        dummy_key
+    }
+
+    fn choose_dummy_key(&mut self, requested: VIRTUAL_KEY, config: &Config<T>) -> VIRTUAL_KEY {
+        if let RemoteSessionMode::DummyKey(key) = config.remote_session
+            && session::is_remote_session()
+        {
+            return key;
+        }
+
+        match &config.dummy_key_strategy {
+            DummyKeyStrategy::Fixed => requested,
+            DummyKeyStrategy::Rotate(keys) if !keys.is_empty() => {
+                let key = keys[self.rotation % keys.len()];
+                self.rotation = self.rotation.wrapping_add(1);
+                key
+            }
+            DummyKeyStrategy::Random(keys) if !keys.is_empty() => {
+                let random = RandomState::new().build_hasher().finish();
+                keys[(random % keys.len() as u64) as usize]
             }
+            DummyKeyStrategy::Rotate(_) | DummyKeyStrategy::Random(_) => requested,
+        }
+    }
+
+    fn is_cooling_down(&self, trigger: MenuTrigger, config: &Config<T>) -> bool {
+        match (config.cooldown, self.last_injection.get(&trigger)) {
+            (Some(cooldown), Some(last)) => last.elapsed() < cooldown,
+            _ => false,
         }
     }
 }
@@ -144,200 +938,1389 @@ impl<T: MenuTriggerEvent + Clone> Handler<T> {
 /// Typically passed to callbacks like `on_released` to determine how to handle
 /// modifier key interactions.
 ///
-/// Note: The key pressed and the key released may differ.
-/// For example, consider the following sequence:
+/// Each physical key is tracked separately when the event type reports its
+/// [`virtual_key_code`](MenuTriggerEvent::virtual_key_code) and
+/// [`scan_code`](MenuTriggerEvent::scan_code). For example, the sequence
 ///
 /// 1. `LAlt` is pressed
 /// 2. `RAlt` is pressed
 /// 3. `LAlt` is released
 /// 4. `RAlt` is released
 ///
-/// In this case, `press` may be `LAlt` and `release` may be `RAlt`.
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// produces two hold events, one per key, and each release is evaluated and suppressed
+/// independently. For event types without key codes, holds are tracked per trigger, so
+/// the key pressed and the key released may differ: `press` may be `LAlt` and
+/// `release` may be `RAlt`.
+#[derive(Debug, Clone, PartialEq)]
 pub struct HoldEvent<T = KeyboardEvent> {
+    /// The trigger that was held.
+    pub trigger: MenuTrigger,
+    /// Whether the left or right key was held, if known.
+    pub side: Option<Side>,
     /// The event when the key was pressed.
     pub press: T,
     /// The event when the key was released.
     pub release: T,
+    repeat_count: u32,
+    interrupted: bool,
+    intervening_keys: Vec<VIRTUAL_KEY>,
+    foreground_at_press: Option<ForegroundWindow>,
+    foreground_at_release: Option<ForegroundWindow>,
+    mouse_clicks: u64,
+    suppressed: bool,
 }
 
-#[derive(Debug)]
-struct HoldStates<T = KeyboardEvent> {
-    win: HoldState<T>,
-    alt: HoldState<T>,
-}
-
-impl<T> HoldStates<T> {
-    fn get_mut(&mut self, trigger: MenuTrigger) -> &mut HoldState<T> {
-        match trigger {
-            MenuTrigger::Win => &mut self.win,
-            MenuTrigger::Alt => &mut self.alt,
+impl<T> HoldEvent<T> {
+    /// Creates an uninterrupted, unsuppressed hold of `trigger` from its press and release.
+    ///
+    /// The handler creates holds itself; this is useful for testing `on_released` callbacks
+    /// and rules.
+    pub fn new(trigger: MenuTrigger, side: Option<Side>, press: T, release: T) -> Self {
+        Self {
+            trigger,
+            side,
+            press,
+            release,
+            repeat_count: 0,
+            interrupted: false,
+            intervening_keys: Vec::new(),
+            foreground_at_press: None,
+            foreground_at_release: None,
+            mouse_clicks: 0,
+            suppressed: false,
         }
     }
 
-    fn reset(&mut self) {
-        self.win.reset();
-        self.alt.reset();
+    /// Returns the number of auto-repeat key-down events received while the key was held.
+    ///
+    /// A brief tap has a count of zero, while a key held past the keyboard's repeat delay
+    /// accumulates repeats at the repeat rate.
+    pub fn repeat_count(&self) -> u32 {
+        self.repeat_count
     }
-}
 
-impl<T: MenuTriggerEvent> HoldStates<T> {
-    fn update(&mut self, event: T) -> Option<(MenuTrigger, HoldEvent<T>)> {
-        if let Some(trigger) = event.menu_trigger() {
-            self.get_mut(trigger)
-                .update(event)
-                .map(|hold| (trigger, hold))
-        } else {
-            self.reset();
-            None
+    /// Returns `true` if a non-trigger key was pressed while the trigger key was held.
+    ///
+    /// Interrupted holds, such as `Win+E`, do not open the menu anyway, so they are only
+    /// passed to `on_released` if [`Config::handle_interrupted`] is set.
+    pub fn was_interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    /// Returns the virtual keys pressed while the trigger key was held, in order.
+    ///
+    /// Only recorded if [`Config::intervening_keys_limit`] is non-zero, and truncated to that
+    /// many keys. For example, a `Tab` in this list indicates an `Alt+Tab` flow.
+    pub fn intervening_keys(&self) -> &[VIRTUAL_KEY] {
+        &self.intervening_keys
+    }
+
+    /// Returns the foreground window when the trigger key was pressed.
+    ///
+    /// Only captured if [`Config::capture_foreground`] is set.
+    pub fn foreground_at_press(&self) -> Option<&ForegroundWindow> {
+        self.foreground_at_press.as_ref()
+    }
+
+    /// Returns the foreground window when the trigger key was released.
+    ///
+    /// Only captured if [`Config::capture_foreground`] is set.
+    pub fn foreground_at_release(&self) -> Option<&ForegroundWindow> {
+        self.foreground_at_release.as_ref()
+    }
+
+    /// Returns `true` if both foreground windows were captured and differ,
+    /// i.e. focus moved to another window during the hold.
+    pub fn focus_changed(&self) -> bool {
+        match (&self.foreground_at_press, &self.foreground_at_release) {
+            (Some(press), Some(release)) => press.handle != release.handle,
+            _ => false,
         }
     }
-}
+
+    /// Returns the number of mouse button presses while the trigger key was held.
+    ///
+    /// Only counted if [`Config::track_mouse_clicks`] is set. A non-zero count also marks
+    /// the hold as [interrupted](HoldEvent::was_interrupted), e.g. for `Alt+click`.
+    pub fn mouse_clicks(&self) -> u64 {
+        self.mouse_clicks
+    }
+
+    /// Returns `true` if the event handler suppressed the menu for this hold.
+    ///
+    /// Only set on holds received from a [`HoldReceiver`]; holds passed to strategies and
+    /// callbacks are not decided yet.
+    pub fn was_suppressed(&self) -> bool {
+        self.suppressed
+    }
+}
+
+/// Options controlling what is recorded while a trigger key is held.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TrackingOptions {
+    intervening_keys_limit: usize,
+    capture_foreground: bool,
+    track_mouse_clicks: bool,
+}
+
+impl<T> From<&Config<T>> for TrackingOptions {
+    fn from(config: &Config<T>) -> Self {
+        Self {
+            intervening_keys_limit: config.intervening_keys_limit,
+            capture_foreground: config.capture_foreground,
+            track_mouse_clicks: config.track_mouse_clicks,
+        }
+    }
+}
+
+fn capture_foreground(options: TrackingOptions) -> Option<ForegroundWindow> {
+    options
+        .capture_foreground
+        .then(ForegroundWindow::current)
+        .flatten()
+}
+
+/// Identifies an independently tracked hold: the trigger and, if known, the physical key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct HoldKey {
+    trigger: MenuTrigger,
+    virtual_key: Option<VIRTUAL_KEY>,
+    scan_code: Option<u32>,
+}
+
+impl HoldKey {
+    fn of<T: MenuTriggerEvent>(trigger: MenuTrigger, event: &T) -> Self {
+        Self {
+            trigger,
+            virtual_key: event.virtual_key_code(),
+            scan_code: event.scan_code(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct HoldStates<T = KeyboardEvent> {
+    holds: HashMap<HoldKey, HoldState<T>>,
+}
+
+impl<T> HoldStates<T> {
+    fn is_held(&self, key: HoldKey) -> bool {
+        self.holds.get(&key).is_some_and(|h| h.0.is_some())
+    }
+
+    /// Returns the number of keys currently held.
+    fn held_count(&self) -> usize {
+        self.holds.values().filter(|h| h.0.is_some()).count()
+    }
+
+    /// Forgets all pending holds, returning the key and the press event of each.
+    fn take_pending(&mut self) -> impl Iterator<Item = (HoldKey, T)> + '_ {
+        self.holds
+            .iter_mut()
+            .filter_map(|(key, hold)| hold.0.take().map(|pending| (*key, pending.press)))
+    }
+
+    fn interrupt(&mut self, key: Option<VIRTUAL_KEY>, limit: usize) {
+        self.holds
+            .values_mut()
+            .for_each(|hold| hold.interrupt(key, limit));
+    }
+}
+
+impl<T: MenuTriggerEvent> HoldStates<T> {
+    pub(crate) fn update(
+        &mut self,
+        event: T,
+        trigger: Option<MenuTrigger>,
+        options: TrackingOptions,
+    ) -> Option<HoldEvent<T>> {
+        if let Some(trigger) = trigger {
+            let side = event.side();
+            self.holds
+                .entry(HoldKey::of(trigger, &event))
+                .or_default()
+                .update(event, trigger, side, options)
+        } else {
+            if event.is_key_down() {
+                self.interrupt(event.virtual_key_code(), options.intervening_keys_limit);
+            }
+            None
+        }
+    }
+}
 
 impl<T> Default for HoldStates<T> {
     fn default() -> Self {
         Self {
-            win: Default::default(),
-            alt: Default::default(),
+            holds: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HoldState<T = KeyboardEvent>(Option<PendingHold<T>>);
+
+/// A trigger key that has been pressed but not yet released.
+#[derive(Debug)]
+struct PendingHold<T> {
+    press: T,
+    repeat_count: u32,
+    interrupted: bool,
+    intervening_keys: Vec<VIRTUAL_KEY>,
+    foreground_at_press: Option<ForegroundWindow>,
+    clicks_at_press: u64,
+}
+
+impl<T> HoldState<T> {
+    fn interrupt(&mut self, key: Option<VIRTUAL_KEY>, limit: usize) {
+        if let Some(pending) = &mut self.0 {
+            pending.interrupted = true;
+            if let Some(key) = key
+                && pending.intervening_keys.len() < limit
+            {
+                pending.intervening_keys.push(key);
+            }
+        }
+    }
+}
+
+impl<T: MenuTriggerEvent> HoldState<T> {
+    fn update(
+        &mut self,
+        event: T,
+        trigger: MenuTrigger,
+        side: Option<Side>,
+        options: TrackingOptions,
+    ) -> Option<HoldEvent<T>> {
+        match event.key_state() {
+            KeyState::Down => {
+                match &mut self.0 {
+                    Some(pending) => pending.repeat_count += 1,
+                    None => {
+                        self.0 = Some(PendingHold {
+                            press: event,
+                            repeat_count: 0,
+                            interrupted: false,
+                            intervening_keys: Vec::new(),
+                            foreground_at_press: capture_foreground(options),
+                            clicks_at_press: click_count(),
+                        })
+                    }
+                }
+                None
+            }
+            KeyState::Up => self.0.take().map(|pending| {
+                let mouse_clicks = if options.track_mouse_clicks {
+                    click_count().wrapping_sub(pending.clicks_at_press)
+                } else {
+                    0
+                };
+                HoldEvent {
+                    repeat_count: pending.repeat_count,
+                    interrupted: pending.interrupted || mouse_clicks > 0,
+                    intervening_keys: pending.intervening_keys,
+                    foreground_at_press: pending.foreground_at_press,
+                    foreground_at_release: capture_foreground(options),
+                    mouse_clicks,
+                    ..HoldEvent::new(trigger, side, pending.press, event)
+                }
+            }),
+        }
+    }
+}
+
+impl<T> Default for HoldState<T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+/// A callback type invoked when a key is released.
+///
+/// Receives a [`HoldEvent`] and returns a virtual key code (dummy key) to send,
+/// or `None` if no key should be sent.
+///
+/// Sending a virtual key allows Windows to treat it as a hotkey input,
+/// which prevents the default menu from being displayed when Alt or Win is released.
+pub type OnReleasedFn<T = KeyboardEvent> =
+    dyn Fn(HoldEvent<T>) -> Option<VIRTUAL_KEY> + Send + Sync + 'static;
+
+/// Determines which dummy key is actually sent when `on_released` requests suppression.
+///
+/// Some fullscreen applications misinterpret repeated key-ups of the same dummy key.
+/// Rotating among several innocuous keys avoids that.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DummyKeyStrategy {
+    /// Send the key returned by `on_released`. This is the default.
+    #[default]
+    Fixed,
+    /// Send the given keys in turn, one per suppression.
+    ///
+    /// Falls back to [`DummyKeyStrategy::Fixed`] if the list is empty.
+    Rotate(Vec<VIRTUAL_KEY>),
+    /// Send a randomly chosen key from the list for each suppression.
+    ///
+    /// Falls back to [`DummyKeyStrategy::Fixed`] if the list is empty.
+    Random(Vec<VIRTUAL_KEY>),
+}
+
+/// What to do while the session is connected through Remote Desktop.
+///
+/// See [`Config::set_remote_session_mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RemoteSessionMode {
+    /// Suppress as usual. This is the default.
+    #[default]
+    Suppress,
+    /// Do not suppress; let the remote client or the local machine handle the keys.
+    Pause,
+    /// Suppress with the given dummy key instead of the configured one.
+    DummyKey(VIRTUAL_KEY),
+}
+
+impl DummyKeyStrategy {
+    /// Returns the function keys `F13`–`F24`, which are not present on most keyboards.
+    pub fn function_keys() -> Vec<VIRTUAL_KEY> {
+        vec![
+            VK_F13, VK_F14, VK_F15, VK_F16, VK_F17, VK_F18, VK_F19, VK_F20, VK_F21, VK_F22, VK_F23,
+            VK_F24,
+        ]
+    }
+}
+
+impl<T: MenuTriggerEvent> Config<T> {
+    /// Returns the trigger `event` belongs to under this configuration.
+    pub fn trigger_of(&self, event: &T) -> Option<MenuTrigger> {
+        match &self.triggers {
+            Some(keys) => {
+                let (vk, sc) = (event.virtual_key_code(), event.scan_code());
+                keys.iter().find(|k| k.matches(vk, sc)).map(|k| k.trigger)
+            }
+            None => event.menu_trigger(),
+        }
+    }
+}
+
+static LAST_INJECTION: Mutex<Option<Instant>> = Mutex::new(None);
+static LAST_SUPPRESSION: Mutex<Option<LastSuppression>> = Mutex::new(None);
+
+/// Returns when the menu was last suppressed by any handler in this process.
+pub(crate) fn last_injection() -> Option<Instant> {
+    *LAST_INJECTION
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The most recent hold that a handler decided to suppress, see [`last_suppression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastSuppression {
+    /// The trigger that was held.
+    pub trigger: MenuTrigger,
+    /// When the decision was made.
+    pub at: Instant,
+    /// How long the trigger was held, if the events carry timestamps.
+    pub held: Option<Duration>,
+    /// Whether the menu was actually suppressed, or the injection failed or was rate
+    /// limited.
+    pub action: Outcome,
+}
+
+impl LastSuppression {
+    /// Returns the time since the decision, e.g. for "last blocked: Win, 2 s ago".
+    pub fn elapsed(&self) -> Duration {
+        self.at.elapsed()
+    }
+}
+
+/// Returns the most recent hold that any handler in this process decided to suppress.
+///
+/// Holds passed through are not included, so this keeps pointing at the last blocked menu.
+/// `None` until the first suppression.
+pub fn last_suppression() -> Option<LastSuppression> {
+    *LAST_SUPPRESSION
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Counts a failed injection if enabled, and reports it through `on_error`.
+fn injection_failed<T>(config: &Config<T>, error: io::Error) {
+    #[cfg(feature = "etw")]
+    crate::etw::injection_failed(&error);
+    stats::record_injection_failure(config.collect_stats);
+    (config.on_error)(&Error::InjectionFailed(error));
+}
+
+/// Acquires a read lock on a [`SharedConfig`], ignoring poisoning.
+pub(crate) fn read_config<T>(config: &SharedConfig<T>) -> RwLockReadGuard<'_, Config<T>> {
+    config.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A hold whose suppression was decided, waiting to be reported until its input is injected.
+struct DecidedHold<T> {
+    hold: HoldEvent<T>,
+    /// Tentatively [`Outcome::Suppressed`] while the injection is deferred.
+    outcome: Outcome,
+    dummy_key: VIRTUAL_KEY,
+    is_tap: bool,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+/// A callback type invoked when a trigger key is tapped and its menu was suppressed.
+pub type OnTapFn = dyn Fn(MenuTrigger) + Send + Sync + 'static;
+
+/// A callback type invoked when a [`SystemChord`] is recognized.
+pub type OnChordFn = dyn Fn(SystemChord) + Send + Sync + 'static;
+
+/// A callback type invoked when a menu opened despite suppression and was closed
+/// (see [`Config::set_auto_close`]).
+pub type OnFallbackFn = dyn Fn(WinEvent) + Send + Sync + 'static;
+
+/// A callback type invoked when a runtime error occurs after startup.
+///
+/// Errors reported here do not stop suppression; they are informational.
+pub type OnErrorFn = dyn Fn(&Error) + Send + Sync + 'static;
+
+/// Configuration for the event handler's behavior.
+///
+/// Used to define how to handle a modifier key after it has been pressed and released.
+/// For example, you can specify a callback to send a dummy key to prevent menu activation.
+///
+/// By default, it returns `Some(VK__none_)` to always suppress menu activation.
+pub struct Config<T = KeyboardEvent> {
+    /// A callback invoked when a key is released after being pressed.
+    pub on_released: Box<OnReleasedFn<T>>,
+    /// A callback invoked when an error occurs at runtime, such as a failed injection.
+    ///
+    /// By default, errors are ignored. (They are still logged if the `log` feature is enabled.)
+    pub on_error: Box<OnErrorFn>,
+    /// A callback invoked after the menu of a quickly tapped trigger key was suppressed.
+    pub on_tap: Option<Box<OnTapFn>>,
+    /// A callback invoked for the first [`Config::suppression_notice_limit`] suppressions,
+    /// to tell the user why the menu does not open.
+    pub suppression_notice: Option<Box<OnTapFn>>,
+    /// How many suppressions [`Config::suppression_notice`] is invoked for. Defaults to 3.
+    pub suppression_notice_limit: u32,
+    /// A sound played whenever a menu is suppressed. Disabled by default.
+    pub suppression_sound: Option<Sound>,
+    /// The maximum hold duration that still counts as a tap. Defaults to 300 ms.
+    ///
+    /// Ignored for event types whose [`MenuTriggerEvent::elapsed_since`] returns `None`;
+    /// every suppressed release then counts as a tap.
+    pub tap_threshold: Duration,
+    /// After injecting a dummy key for a trigger, further releases of the same trigger
+    /// within this duration are not suppressed. Disabled (`None`) by default.
+    pub cooldown: Option<Duration>,
+    /// When suppression is active. Always active (`None`) by default.
+    pub schedule: Option<Schedule>,
+    /// How the dummy key is chosen for each suppression.
+    pub dummy_key_strategy: DummyKeyStrategy,
+    /// How the menu is suppressed. Defaults to [`DummyKeyUp`].
+    pub strategy: Box<dyn SuppressionStrategy<T>>,
+    /// Per-trigger overrides of `strategy`.
+    pub trigger_strategies: HashMap<MenuTrigger, Box<dyn SuppressionStrategy<T>>>,
+    /// Keys treated as menu triggers.
+    ///
+    /// If `None` (the default), [`MenuTriggerEvent::menu_trigger`] decides.
+    pub triggers: Option<Vec<TriggerKey>>,
+    /// System chords whose action is suppressed. Empty by default.
+    pub suppressed_chords: HashSet<SystemChord>,
+    /// A callback invoked when a system chord, such as the Copilot key, is recognized.
+    pub on_chord: Option<Box<OnChordFn>>,
+    /// Whether interrupted holds (see [`HoldEvent::was_interrupted`]) are passed to
+    /// `on_released`. Disabled by default, in which case they are never suppressed.
+    pub handle_interrupted: bool,
+    /// The maximum number of intervening keys recorded per hold
+    /// (see [`HoldEvent::intervening_keys`]). `0` (the default) disables recording.
+    pub intervening_keys_limit: usize,
+    /// Whether the foreground window is captured at press and release time
+    /// (see [`HoldEvent::foreground_at_press`]). Disabled by default.
+    pub capture_foreground: bool,
+    /// Whether pending holds are discarded when the foreground window changes.
+    /// Disabled by default.
+    pub reset_on_foreground_change: bool,
+    /// Whether mouse clicks during a hold are counted (see [`HoldEvent::mouse_clicks`]).
+    /// Disabled by default.
+    pub track_mouse_clicks: bool,
+    /// Whether suppression is paused while the workstation is locked or the secure desktop
+    /// is active. Disabled by default.
+    pub pause_on_lock: bool,
+    /// What to do while the session is connected through Remote Desktop.
+    /// [`RemoteSessionMode::Suppress`] by default.
+    pub remote_session: RemoteSessionMode,
+    /// How long after a suppression a menu that opens anyway is closed automatically.
+    /// Disabled (`None`) by default.
+    pub auto_close: Option<Duration>,
+    /// A callback invoked when a menu was closed automatically.
+    pub on_fallback: Option<Box<OnFallbackFn>>,
+    /// How long after a suppression an opening menu counts as a failed suppression.
+    /// Disabled (`None`) by default.
+    pub verify_window: Option<Duration>,
+    /// Strategies to switch to, in order, when verification detects failures.
+    pub fallback_strategies: Vec<Box<dyn SuppressionStrategy<T>>>,
+    /// Middleware every event passes through before suppression, in order.
+    pub middleware: Vec<Mutex<Box<dyn EventMiddleware<T>>>>,
+    /// Whether input injected for events that were queued together is sent in one batch.
+    /// Disabled by default.
+    pub batch_injections: bool,
+    /// The maximum number of suppressions per second. Unlimited (`None`) by default.
+    pub injection_rate_limit: Option<u32>,
+    /// Whether the counters of [`crate::stats::counters`] are updated. Disabled by default.
+    pub collect_stats: bool,
+    /// Receives lifecycle and suppression events, see [`crate::observer`].
+    pub observer: Option<Box<dyn Observer<T>>>,
+    /// Appends each decision to a rotating file, see [`crate::audit`].
+    pub audit_log: Option<AuditLog>,
+    /// Appends trigger events and decisions to a file, see [`crate::recording`].
+    #[cfg(feature = "json")]
+    pub recorder: Option<Recorder>,
+    /// The upper bounds of the buckets of [`crate::stats::hold_durations`], ascending.
+    /// 50, 100, 150, 200, 300, 500, 1000 and 2000 ms by default.
+    pub hold_duration_buckets: Vec<Duration>,
+    /// Options applied inside the keyboard hook.
+    ///
+    /// Only takes effect when the hook is started by this crate, e.g. via [`crate::start`].
+    pub hook: HookOptions,
+}
+
+impl<T> Config<T> {
+    /// Sets the callback function to be invoked when a key is released.
+    ///
+    /// This method updates the `on_released` field with the provided function,
+    /// which takes a [`HoldEvent`] representing the press and release of a modifier key.
+    /// The callback should return a dummy [`VIRTUAL_KEY`] to send, or `None` if no key should be sent.
+    ///
+    /// # Arguments
+    /// - `f`: A closure or function of type `Fn(HoldEvent<T>) -> Option<VIRTUAL_KEY>`.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance with the new callback set (builder pattern).
+    pub fn set_on_released<F: Fn(HoldEvent<T>) -> Option<VIRTUAL_KEY> + Send + Sync + 'static>(
+        mut self,
+        f: F,
+    ) -> Self {
+        self.on_released = Box::new(f);
+        self
+    }
+
+    /// Sets the callback function to be invoked when a runtime error occurs.
+    ///
+    /// The errors are also kept for [`crate::diagnostics::StateDump::last_errors`].
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance with the new callback set (builder pattern).
+    pub fn set_on_error<F: Fn(&Error) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.on_error = Box::new(move |error| {
+            diagnostics::record_error(error);
+            f(error);
+        });
+        self
+    }
+
+    /// Sets the callback function to be invoked when a trigger key is tapped.
+    ///
+    /// A tap is a press and release of a trigger key, with no other key in between,
+    /// held no longer than `tap_threshold`. The callback runs only after the menu
+    /// has been suppressed, so it can be used to open a custom launcher instead of
+    /// the Start menu.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance with the new callback set (builder pattern).
+    pub fn set_on_tap<F: Fn(MenuTrigger) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.on_tap = Some(Box::new(f));
+        self
+    }
+
+    /// Sets a callback invoked for the first `limit` suppressed menus of each handler,
+    /// e.g. to explain to users of a kiosk why the Start menu does not open.
+    ///
+    /// With the `toast` feature, [`crate::toast::notify`] shows a Windows notification.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance with the new callback set (builder pattern).
+    pub fn set_suppression_notice<F: Fn(MenuTrigger) + Send + Sync + 'static>(
+        mut self,
+        limit: u32,
+        f: F,
+    ) -> Self {
+        self.suppression_notice = Some(Box::new(f));
+        self.suppression_notice_limit = limit;
+        self
+    }
+
+    /// Sets a sound played whenever a menu is suppressed, see [`crate::sound`].
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_suppression_sound(mut self, sound: Sound) -> Self {
+        self.suppression_sound = Some(sound);
+        self
+    }
+
+    /// Sets the maximum hold duration that still counts as a tap.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_tap_threshold(mut self, threshold: Duration) -> Self {
+        self.tap_threshold = threshold;
+        self
+    }
+
+    /// Sets a cooldown between dummy-key injections for the same trigger.
+    ///
+    /// Rapidly tapping a trigger key then results in a single injection,
+    /// which avoids flooding `SendInput` (some remote-desktop clients are confused by that).
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Restricts suppression to the times described by `schedule`.
+    ///
+    /// Outside of the schedule, releases are passed through without calling `on_released`.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Sets how the dummy key is chosen for each suppression.
+    ///
+    /// `on_released` still decides *whether* to suppress; with a strategy other than
+    /// [`DummyKeyStrategy::Fixed`] the key it returns is replaced.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_dummy_key_strategy(mut self, strategy: DummyKeyStrategy) -> Self {
+        self.dummy_key_strategy = strategy;
+        self
+    }
+
+    /// Sets the technique used to suppress the menu.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_strategy<S: SuppressionStrategy<T> + 'static>(mut self, strategy: S) -> Self {
+        self.strategy = Box::new(strategy);
+        self
+    }
+
+    /// Sets the technique used to suppress the menu of a single trigger,
+    /// overriding the one set with [`Config::set_strategy`].
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_strategy_for<S: SuppressionStrategy<T> + 'static>(
+        mut self,
+        trigger: MenuTrigger,
+        strategy: S,
+    ) -> Self {
+        self.trigger_strategies.insert(trigger, Box::new(strategy));
+        self
+    }
+
+    /// Makes the default suppression inject the given scan code (`KEYEVENTF_SCANCODE`)
+    /// instead of a virtual key.
+    ///
+    /// This is a shorthand for `set_strategy(ScanCodeKeyUp { scan })`; see
+    /// [`ScanCodeKeyUp`] for details.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_scan_code_injection(self, scan: u16) -> Self {
+        self.set_strategy(ScanCodeKeyUp { scan })
+    }
+
+    /// Sets the keys treated as menu triggers, replacing the built-in Alt/Win detection.
+    ///
+    /// Events are matched with [`MenuTriggerEvent::virtual_key_code`] and
+    /// [`MenuTriggerEvent::scan_code`]. Other keys interrupt a pending hold like any non-trigger
+    /// key; see [`HoldEvent::was_interrupted`].
+    ///
+    /// # Example
+    /// ```
+    /// use prevent_alt_win_menu::event_handler::{Config, KeyboardAndMouse::VK_LWIN, MenuTrigger, TriggerKey};
+    ///
+    /// // Only the physical left Win key (scan code 0x5B) counts, not a remapped one.
+    /// let config = Config::default().set_triggers(&[
+    ///     TriggerKey::new(VK_LWIN, MenuTrigger::Win).set_scan_code(0x5B),
+    /// ]);
+    /// ```
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_triggers(mut self, keys: &[TriggerKey]) -> Self {
+        self.triggers = Some(keys.to_vec());
+        self
+    }
+
+    /// Sets whether the action of a system chord is suppressed.
+    ///
+    /// For example, `set_suppress_chord(SystemChord::Copilot, true)` suppresses the Copilot
+    /// flyout when the Copilot key (`Win+Shift+F23`) is released, using the same strategy
+    /// as the Win menu.
+    ///
+    /// Trigger keys that are part of a recognized chord never produce regular [`HoldEvent`]s,
+    /// so chords that are not suppressed are passed through untouched.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_suppress_chord(mut self, chord: SystemChord, suppress: bool) -> Self {
+        if suppress {
+            self.suppressed_chords.insert(chord);
+        } else {
+            self.suppressed_chords.remove(&chord);
+        }
+        self
+    }
+
+    /// Sets the callback function to be invoked when a system chord is recognized.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance with the new callback set (builder pattern).
+    pub fn set_on_chord<F: Fn(SystemChord) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.on_chord = Some(Box::new(f));
+        self
+    }
+
+    /// Sets whether interrupted holds are passed to `on_released`.
+    ///
+    /// Enable this to make decisions based on [`HoldEvent::was_interrupted`] yourself.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_handle_interrupted(mut self, handle: bool) -> Self {
+        self.handle_interrupted = handle;
+        self
+    }
+
+    /// Enables recording of the keys pressed while a trigger key is held, up to `limit` keys.
+    ///
+    /// Holds with intervening keys are interrupted, so combine this with
+    /// [`Config::set_handle_interrupted`] to receive them in `on_released`. Unlike the rest of
+    /// the event handling, recording allocates.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_intervening_keys_limit(mut self, limit: usize) -> Self {
+        self.intervening_keys_limit = limit;
+        self
+    }
+
+    /// Sets whether the foreground window and its process name are captured when a trigger
+    /// key is pressed and released.
+    ///
+    /// The snapshots are available through [`HoldEvent::foreground_at_press`] and
+    /// [`HoldEvent::foreground_at_release`]. Unlike the rest of the event handling, capturing
+    /// the process name allocates.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_capture_foreground(mut self, capture: bool) -> Self {
+        self.capture_foreground = capture;
+        self
+    }
+
+    /// Sets whether pending holds are discarded when the foreground window changes.
+    ///
+    /// This prevents a stale press from being paired with a much later release after
+    /// `Alt+Tab`-like transitions. [`crate::start`] starts the required watcher
+    /// (see [`crate::foreground::start_foreground_watcher`]) automatically; with a custom
+    /// event source, start it yourself.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_reset_on_foreground_change(mut self, reset: bool) -> Self {
+        self.reset_on_foreground_change = reset;
+        self
+    }
+
+    /// Sets whether mouse clicks while a trigger key is held are counted.
+    ///
+    /// A hold with clicks is treated as interrupted, like a hold with another key pressed,
+    /// so `Alt+click` no longer gets suppressed by default. The count is available through
+    /// [`HoldEvent::mouse_clicks`]. [`crate::start`] starts the required mouse hook
+    /// (see [`crate::mouse_hook::start_mouse_hook`]) automatically; with a custom event
+    /// source, start it yourself.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_track_mouse_clicks(mut self, track: bool) -> Self {
+        self.track_mouse_clicks = track;
+        self
+    }
+
+    /// Sets whether suppression is paused while the workstation is locked or the secure
+    /// desktop (UAC prompts, `Ctrl+Alt+Del`) is active.
+    ///
+    /// No input is injected while paused, since it could not reach the other desktop anyway,
+    /// and pending holds are discarded when the user returns. [`crate::start`] starts the
+    /// required watcher (see [`crate::session::start_session_watcher`]) automatically; with a
+    /// custom event source, start it yourself.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_pause_on_lock(mut self, pause: bool) -> Self {
+        self.pause_on_lock = pause;
+        self
+    }
+
+    /// Sets what to do while the session is connected through Remote Desktop.
+    ///
+    /// Some RDP clients forward injected dummy keys differently than a local keyboard would,
+    /// so suppression can be paused or switched to another dummy key for remote sessions.
+    /// [`crate::start`] starts the required watcher (see
+    /// [`crate::session::start_session_watcher`]) automatically unless the mode is
+    /// [`RemoteSessionMode::Suppress`]; with a custom event source, start it yourself.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_remote_session_mode(mut self, mode: RemoteSessionMode) -> Self {
+        self.remote_session = mode;
+        self
+    }
+
+    /// Closes a menu that opens despite suppression by sending `Escape`.
+    ///
+    /// A menu bar, window menu or the Start menu that opens within `window` after a suppressed
+    /// release is treated as a failed suppression. [`crate::start`] starts the required
+    /// watcher (see [`crate::fallback::start_menu_watcher`]) automatically.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_auto_close(mut self, window: Duration) -> Self {
+        self.auto_close = Some(window);
+        self
+    }
+
+    /// Sets the callback function to be invoked when a menu was closed automatically
+    /// (see [`Config::set_auto_close`]).
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance with the new callback set (builder pattern).
+    pub fn set_on_fallback<F: Fn(WinEvent) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.on_fallback = Some(Box::new(f));
+        self
+    }
+
+    /// Enables verification: if a menu opens within `window` after a suppression, the
+    /// suppression is considered failed and later suppressions switch to the next strategy
+    /// added with [`Config::add_fallback_strategy`].
+    ///
+    /// Once every fallback has failed as well, `Error::SuppressionFailed` is reported through
+    /// `on_error`. [`crate::start`] starts the required watcher
+    /// (see [`crate::fallback::start_menu_watcher`]) automatically.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_verification(mut self, window: Duration) -> Self {
+        self.verify_window = Some(window);
+        self
+    }
+
+    /// Adds a strategy to switch to when verification detects that the current one fails.
+    ///
+    /// Fallbacks are tried in the order they were added and apply to all triggers.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn add_fallback_strategy<S: SuppressionStrategy<T> + 'static>(
+        mut self,
+        strategy: S,
+    ) -> Self {
+        self.fallback_strategies.push(Box::new(strategy));
+        self
+    }
+
+    /// Appends a middleware to the chain events pass through before suppression.
+    ///
+    /// Middleware run in the order they were added, on the event handler thread (or inside
+    /// the hook with [`crate::start_inline`]). See [`crate::middleware`].
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn add_middleware<M: EventMiddleware<T> + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(Mutex::new(Box::new(middleware)));
+        self
+    }
+
+    /// Sets whether input is batched while the event handler catches up.
+    ///
+    /// When several events are queued, e.g. after the handler was briefly stalled, the
+    /// dummy keys injected for them are collected and sent with a single `SendInput` call
+    /// once the handler's receiver is empty. This reduces syscalls and interleaving with real
+    /// input. Outcomes, callbacks and statistics of the batched holds are reported after the
+    /// batch was sent. Only the handlers of [`crate::start`] and [`crate::pump::EventPump`]
+    /// look ahead in their receiver; with other event sources every event is sent on its own.
+    /// Disabled by default.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_batch_injections(mut self, batch: bool) -> Self {
+        self.batch_injections = batch;
+        self
+    }
+
+    /// Limits suppressions to `per_second`, guarding against a stuck or bouncing key
+    /// flooding the input queue.
+    ///
+    /// Beyond the limit, releases are passed through as if they were not suppressed. Skipped
+    /// suppressions are counted in [`crate::stats::rate_limited`], and the first of each
+    /// second is reported as `Error::InjectionRateLimited` through `on_error`.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_injection_rate_limit(mut self, per_second: u32) -> Self {
+        self.injection_rate_limit = Some(per_second);
+        self
+    }
+
+    /// Sets whether suppressions, pass-throughs, injection failures and handled events are
+    /// counted, e.g. to show "prevented N menus today" in a tray icon.
+    ///
+    /// The counters are process-wide and read with [`crate::stats::counters`].
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_collect_stats(mut self, collect: bool) -> Self {
+        self.collect_stats = collect;
+        self
+    }
+
+    /// Sets the [`Observer`] notified of lifecycle and suppression events.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_observer<O: Observer<T> + 'static>(mut self, observer: O) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Sets the [`AuditLog`] each suppression decision is written to.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Sets the [`Recorder`] that trigger events and decisions are appended to.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    #[cfg(feature = "json")]
+    pub fn set_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Sets the upper bounds of the buckets hold durations are sorted into, see
+    /// [`crate::stats::hold_durations`]. Holds longer than the last bound are counted
+    /// separately. Changing the buckets discards the durations recorded so far.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_hold_duration_buckets(mut self, bounds: impl IntoIterator<Item = Duration>) -> Self {
+        self.hold_duration_buckets = bounds.into_iter().collect();
+        self.hold_duration_buckets.sort();
+        self.hold_duration_buckets.dedup();
+        self
+    }
+
+    /// Sets whether the Space key is blocked while Alt is held, preventing the window menu
+    /// opened by `Alt+Space`.
+    ///
+    /// The Space key is swallowed inside the keyboard hook, so no application receives
+    /// `Alt+Space` while this is enabled. Intended for kiosk-style deployments.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_block_alt_space(mut self, block: bool) -> Self {
+        self.hook.block_alt_space = block;
+        self
+    }
+
+    /// Sets whether injected events (e.g. from `SendInput` in AutoHotkey or remote-control
+    /// software) are ignored.
+    ///
+    /// Ignored events are filtered inside the keyboard hook and never reach the event handler,
+    /// so they neither start, interrupt nor complete a hold.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_ignore_injected(mut self, ignore: bool) -> Self {
+        self.hook.ignore_injected = ignore;
+        self
+    }
+
+    /// Sets whether the keys of `trigger` are swallowed inside the keyboard hook.
+    ///
+    /// Unlike suppression, which masks the release with a dummy key, this blocks the key-down
+    /// and key-up events before any application sees them and injects nothing. Combinations
+    /// with the trigger key stop working as well; see [`HookOptions::block_triggers`].
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_block_trigger(mut self, trigger: MenuTrigger, block: bool) -> Self {
+        if block {
+            self.hook.block_triggers.insert(trigger);
+        } else {
+            self.hook.block_triggers.remove(&trigger);
+        }
+        self
+    }
+
+    /// Sets the time budget for a single event handled by [`crate::start_inline`].
+    ///
+    /// Windows silently removes a low-level hook whose procedure is too slow, so the inline
+    /// handler is disabled once an event takes longer than this. Defaults to 10 ms.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_inline_budget(mut self, budget: Duration) -> Self {
+        self.hook.inline_budget = budget;
+        self
+    }
+
+    /// Enables a watchdog that re-registers the keyboard hook if Windows removed it.
+    ///
+    /// The keyboard state is checked every `interval`; see [`HookOptions::watchdog`].
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_watchdog(mut self, interval: Duration) -> Self {
+        self.hook.watchdog = Some(interval);
+        self
+    }
+
+    /// Sets whether the F10 key is blocked while Shift is held, preventing the context menu
+    /// opened by `Shift+F10`.
+    ///
+    /// Like [`Config::set_block_alt_space`], the key is swallowed inside the keyboard hook.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_block_shift_f10(mut self, block: bool) -> Self {
+        self.hook.block_shift_f10 = block;
+        self
+    }
+
+    /// Returns the strategy in effect for `trigger`.
+    ///
+    /// After verification detected failed suppressions (see [`Config::set_verification`]),
+    /// this is the corresponding fallback strategy.
+    pub fn strategy_for(&self, trigger: MenuTrigger) -> &dyn SuppressionStrategy<T> {
+        if let Some(level) = escalation_level().checked_sub(1)
+            && let Some(fallback) = self.fallback_strategies.get(level)
+        {
+            return fallback.as_ref();
+        }
+
+        self.trigger_strategies
+            .get(&trigger)
+            .unwrap_or(&self.strategy)
+            .as_ref()
+    }
+}
+
+impl<T> Default for Config<T> {
+    fn default() -> Self {
+        Self {
+            on_released: Box::new(|_| Some(VK__none_)),
+            on_error: Box::new(diagnostics::record_error),
+            on_tap: None,
+            suppression_notice: None,
+            suppression_notice_limit: 3,
+            suppression_sound: None,
+            tap_threshold: Duration::from_millis(300),
+            cooldown: None,
+            schedule: None,
+            dummy_key_strategy: DummyKeyStrategy::Fixed,
+            strategy: Box::new(DummyKeyUp),
+            trigger_strategies: HashMap::new(),
+            triggers: None,
+            suppressed_chords: HashSet::new(),
+            on_chord: None,
+            handle_interrupted: false,
+            intervening_keys_limit: 0,
+            capture_foreground: false,
+            reset_on_foreground_change: false,
+            pause_on_lock: false,
+            remote_session: RemoteSessionMode::default(),
+            track_mouse_clicks: false,
+            auto_close: None,
+            on_fallback: None,
+            verify_window: None,
+            fallback_strategies: Vec::new(),
+            middleware: Vec::new(),
+            batch_injections: false,
+            injection_rate_limit: None,
+            collect_stats: false,
+            observer: None,
+            audit_log: None,
+            #[cfg(feature = "json")]
+            recorder: None,
+            hold_duration_buckets: stats::DEFAULT_HOLD_BUCKETS
+                .iter()
+                .map(|&ms| Duration::from_millis(ms))
+                .collect(),
+            hook: HookOptions::default(),
         }
     }
 }
 
-#[derive(Debug)]
-struct HoldState<T = KeyboardEvent>(Option<T>);
+/// The value this crate stores in `KEYBDINPUT.dwExtraInfo` of every event it injects.
+///
+/// The keyboard hook started by this crate drops such events before they reach the event
+/// handler, so dummy keys never feed back into hold tracking. Other hooks can compare
+/// `KBDLLHOOKSTRUCT.dwExtraInfo` against this value (or use
+/// [`KeyboardEvent::is_own_injection`]) to ignore them as well.
+pub const INJECTED_INPUT_TAG: usize = 0x5041_574D; // "PAWM"
 
-impl<T> HoldState<T> {
-    fn reset(&mut self) {
-        self.0 = None;
-    }
+/// Sends a key-up event for the specified virtual key code.
+///
+/// This function uses the Windows `SendInput` API to emit a `KEYEVENTF_KEYUP`
+/// event for the given key. It is typically used to suppress system behavior
+/// such as menu activation after pressing modifier keys like Alt or Win.
+///
+/// # Arguments
+/// - `dummy_key`: The virtual key code for which to send a key-up event.
+///
+/// # Returns
+/// Returns `Ok(())` if the event was successfully sent, or an `std::io::Error` if it failed.
+pub fn send_keyup(dummy_key: VIRTUAL_KEY) -> std::io::Result<()> {
+    send_key(dummy_key, KEYEVENTF_KEYUP)
 }
 
-impl<T: MenuTriggerEvent> HoldState<T> {
-    fn update(&mut self, event: T) -> Option<HoldEvent<T>> {
-        match event.key_state() {
-            KeyState::Down => {
-                self.0.get_or_insert(event);
-                None
-            }
-            KeyState::Up => self.0.take().map(|hold_start_event| HoldEvent {
-                press: hold_start_event,
-                release: event,
-            }),
-        }
-    }
+/// Sends a key-down event for the specified virtual key code.
+///
+/// Pair it with [`send_keyup`] to hold a key across several callbacks.
+///
+/// # Returns
+/// Returns `Ok(())` if the event was successfully sent, or an `std::io::Error` if it failed.
+pub fn send_keydown(key: VIRTUAL_KEY) -> std::io::Result<()> {
+    send_key(key, KEYBD_EVENT_FLAGS(0))
 }
 
-impl<T> Default for HoldState<T> {
-    fn default() -> Self {
-        Self(Default::default())
-    }
+/// Sends a key-down and a key-up event for the specified virtual key code in one `SendInput` call.
+///
+/// Both events are inserted into the input stream atomically, so no other input can come
+/// between them.
+///
+/// # Returns
+/// Returns `Ok(())` if the events were successfully sent, or an `std::io::Error` if it failed.
+pub fn send_key_tap(key: VIRTUAL_KEY) -> std::io::Result<()> {
+    send_input(&[
+        keyboard_input(key, KEYBD_EVENT_FLAGS(0)),
+        keyboard_input(key, KEYEVENTF_KEYUP),
+    ])
 }
 
-/// A callback type invoked when a key is released.
+/// Sends a key-up event for the specified hardware scan code (`KEYEVENTF_SCANCODE`).
 ///
-/// Receives a [`HoldEvent`] and returns a virtual key code (dummy key) to send,
-/// or `None` if no key should be sent.
+/// Some games and other low-level consumers ignore the virtual key of injected input and
+/// only honor scan codes. Extended scan codes are given with the `0xE0` prefix in the high
+/// byte (e.g. `0xE05B` for the left Windows key), which sets `KEYEVENTF_EXTENDEDKEY`.
 ///
-/// Sending a virtual key allows Windows to treat it as a hotkey input,
-/// which prevents the default menu from being displayed when Alt or Win is released.
-pub type OnReleasedFn<T = KeyboardEvent> =
-    dyn Fn(HoldEvent<T>) -> Option<VIRTUAL_KEY> + Send + Sync + 'static;
+/// # Returns
+/// Returns `Ok(())` if the event was successfully sent, or an `std::io::Error` if it failed.
+pub fn send_keyup_scancode(scan: u16) -> std::io::Result<()> {
+    send_input(&[scancode_input(scan, KEYEVENTF_KEYUP)])
+}
 
-/// Configuration for the event handler's behavior.
+/// Types `text` by sending a key-down and a key-up event per UTF-16 code unit
+/// (`KEYEVENTF_UNICODE`), all in one `SendInput` call.
 ///
-/// Used to define how to handle a modifier key after it has been pressed and released.
-/// For example, you can specify a callback to send a dummy key to prevent menu activation.
+/// The characters are delivered as `VK_PACKET` to the focused window, independent of the
+/// keyboard layout. Does nothing if `text` is empty.
 ///
-/// By default, it returns `Some(VK__none_)` to always suppress menu activation.
-pub struct Config<T = KeyboardEvent> {
-    /// A callback invoked when a key is released after being pressed.
-    pub on_released: Box<OnReleasedFn<T>>,
+/// # Returns
+/// Returns `Ok(())` if the events were successfully sent, or an `std::io::Error` if it failed.
+pub fn send_unicode(text: &str) -> std::io::Result<()> {
+    InputBatch::default().unicode(text).send()
 }
 
-impl<T> Config<T> {
-    /// Sets the callback function to be invoked when a key is released.
+/// A sequence of keyboard events injected with a single `SendInput` call.
+///
+/// The events of one batch are inserted into the input stream atomically, so no other input
+/// can come between them.
+///
+/// # Example
+/// ```no_run
+/// use prevent_alt_win_menu::event_handler::{InputBatch, KeyboardAndMouse::VK_LWIN};
+///
+/// // Open the Start menu search and type a prefix.
+/// InputBatch::default()
+///     .key_down(VK_LWIN)
+///     .key_up(VK_LWIN)
+///     .unicode("> ")
+///     .send()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Default)]
+pub struct InputBatch {
+    inputs: Vec<INPUT>,
+}
+
+impl InputBatch {
+    /// Appends a key-down event.
     ///
-    /// This method updates the `on_released` field with the provided function,
-    /// which takes a [`HoldEvent`] representing the press and release of a modifier key.
-    /// The callback should return a dummy [`VIRTUAL_KEY`] to send, or `None` if no key should be sent.
+    /// # Returns
+    /// A modified [`InputBatch`] instance (builder pattern).
+    pub fn key_down(mut self, key: VIRTUAL_KEY) -> Self {
+        self.inputs.push(keyboard_input(key, KEYBD_EVENT_FLAGS(0)));
+        self
+    }
+
+    /// Appends a key-up event.
     ///
-    /// # Arguments
-    /// - `f`: A closure or function of type `Fn(HoldEvent<T>) -> Option<VIRTUAL_KEY>`.
+    /// # Returns
+    /// A modified [`InputBatch`] instance (builder pattern).
+    pub fn key_up(mut self, key: VIRTUAL_KEY) -> Self {
+        self.inputs.push(keyboard_input(key, KEYEVENTF_KEYUP));
+        self
+    }
+
+    /// Appends a key-down and a key-up event.
     ///
     /// # Returns
-    /// A modified [`Config`] instance with the new callback set (builder pattern).
-    pub fn set_on_released<F: Fn(HoldEvent<T>) -> Option<VIRTUAL_KEY> + Send + Sync + 'static>(
-        mut self,
-        f: F,
-    ) -> Self {
-        self.on_released = Box::new(f);
+    /// A modified [`InputBatch`] instance (builder pattern).
+    pub fn key_tap(self, key: VIRTUAL_KEY) -> Self {
+        self.key_down(key).key_up(key)
+    }
+
+    /// Appends events that type `text` (see [`send_unicode`]).
+    ///
+    /// # Returns
+    /// A modified [`InputBatch`] instance (builder pattern).
+    pub fn unicode(mut self, text: &str) -> Self {
+        self.inputs.extend(text.encode_utf16().flat_map(|unit| {
+            [
+                unicode_input(unit, KEYBD_EVENT_FLAGS(0)),
+                unicode_input(unit, KEYEVENTF_KEYUP),
+            ]
+        }));
         self
     }
-}
 
-impl<T> Default for Config<T> {
-    fn default() -> Self {
-        Self {
-            on_released: Box::new(|_| Some(VK__none_)),
+    /// Returns the number of events in the batch.
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns `true` if the batch contains no events.
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Sends all events in the batch. Does nothing if the batch is empty.
+    ///
+    /// # Returns
+    /// Returns `Ok(())` if the events were successfully sent, or an `std::io::Error` if it failed.
+    pub fn send(&self) -> std::io::Result<()> {
+        if self.inputs.is_empty() {
+            return Ok(());
         }
+        send_input(&self.inputs)
     }
 }
 
-/// Sends a key-up event for the specified virtual key code.
-///
-/// This function uses the Windows `SendInput` API to emit a `KEYEVENTF_KEYUP`
-/// event for the given key. It is typically used to suppress system behavior
-/// such as menu activation after pressing modifier keys like Alt or Win.
-///
-/// # Arguments
-/// - `dummy_key`: The virtual key code for which to send a key-up event.
-///
-/// # Returns
-/// Returns `Ok(())` if the event was successfully sent, or an `std::io::Error` if it failed.
-pub fn send_keyup(dummy_key: VIRTUAL_KEY) -> std::io::Result<()> {
-    send_input(&[INPUT {
+fn send_key(key: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS) -> std::io::Result<()> {
+    send_input(&[keyboard_input(key, flags)])
+}
+
+fn keyboard_input(key: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
         r#type: INPUT_KEYBOARD,
         Anonymous: INPUT_0 {
             ki: KEYBDINPUT {
-                wVk: dummy_key,
-                dwFlags: KEYEVENTF_KEYUP,
+                wVk: key,
+                dwFlags: flags,
+                dwExtraInfo: INJECTED_INPUT_TAG,
                 ..Default::default()
             },
         },
-    }])
+    }
 }
 
-fn send_input(inputs: &[INPUT]) -> std::io::Result<()> {
-    let result = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+fn scancode_input(scan: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    let mut flags = flags | KEYEVENTF_SCANCODE;
+    if scan & 0xFF00 == 0xE000 {
+        flags |= KEYEVENTF_EXTENDEDKEY;
+    }
 
-    if result as usize != inputs.len() {
-        Err(std::io::Error::last_os_error())
-    } else {
-        #[cfg(feature = "log")]
-        log::trace!(
-            "SendInput: {:?}",
-            inputs
-                .iter()
-                .map(|i| unsafe { i.Anonymous.ki.wVk })
-                .collect::<Vec<_>>()
-        );
-        Ok(())
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wScan: scan & 0x00FF,
+                dwFlags: flags,
+                dwExtraInfo: INJECTED_INPUT_TAG,
+                ..Default::default()
+            },
+        },
+    }
+}
+
+fn unicode_input(unit: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wScan: unit,
+                dwFlags: flags | KEYEVENTF_UNICODE,
+                dwExtraInfo: INJECTED_INPUT_TAG,
+                ..Default::default()
+            },
+        },
     }
 }
 
+fn send_input(inputs: &[INPUT]) -> std::io::Result<()> {
+    input::send(inputs)
+}
+
 /// Represents a single keyboard event received via a Windows low-level keyboard hook.
 ///
 /// Internally contains the raw Windows [`KBDLLHOOKSTRUCT`] and the associated event type
 /// (e.g., key down or key up).
+///
+/// More fields may be added in future versions; use [`KeyboardEvent::new`] to construct one.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
 pub struct KeyboardEvent {
     /// The raw Windows keyboard event structure.
     pub kbd: KBDLLHOOKSTRUCT,
     /// The raw Windows keyboard event structure.
     pub wm_key_state: WmKeyState,
+    /// When the hook procedure received the event.
+    ///
+    /// Unlike `kbd.time`, this is monotonic, high-resolution and does not wrap.
+    pub timestamp: Instant,
+    /// Whether this is an auto-repeat key-down (see [`MenuTriggerEvent::is_repeat`]).
+    pub repeat: bool,
 }
 
 impl KeyboardEvent {
+    /// Creates an event received now, which is not an auto-repeat.
+    ///
+    /// Useful for synthetic events, e.g. in tests or when feeding a [`Handler`] from another
+    /// source. The remaining fields can be set afterwards.
+    pub fn new(kbd: KBDLLHOOKSTRUCT, wm_key_state: WmKeyState) -> Self {
+        Self {
+            kbd,
+            wm_key_state,
+            timestamp: Instant::now(),
+            repeat: false,
+        }
+    }
+
     /// Constructs a `KeyboardEvent` from `l_param` and `w_param` inside a Windows hook procedure.
     ///
     /// # Safety
@@ -345,9 +2328,14 @@ impl KeyboardEvent {
     pub(crate) unsafe fn from_params(l_param: LPARAM, w_param: WPARAM) -> KeyboardEvent {
         let kbd = unsafe { *(l_param.0 as *const KBDLLHOOKSTRUCT) };
         let key_state = WmKeyState::from_w_param(w_param).unwrap();
+        // Inside the hook, the asynchronous key state does not include the current event yet.
+        let repeat =
+            key_state.is_key_down() && keyboard_state::is_down(VIRTUAL_KEY(kbd.vkCode as _));
         Self {
             kbd,
             wm_key_state: key_state,
+            timestamp: Instant::now(),
+            repeat,
         }
     }
 
@@ -356,7 +2344,78 @@ impl KeyboardEvent {
         VIRTUAL_KEY(self.kbd.vkCode as _)
     }
 
-    /// Returns the duration elapsed since the given earlier event.
+    /// Returns `true` if the Alt key was down when the event was generated (`LLKHF_ALTDOWN`).
+    pub fn is_alt_down(&self) -> bool {
+        self.kbd.flags.contains(LLKHF_ALTDOWN)
+    }
+
+    /// Returns `true` if the event was injected, e.g. by `SendInput` (`LLKHF_INJECTED`).
+    pub fn is_injected(&self) -> bool {
+        self.kbd.flags.contains(LLKHF_INJECTED)
+    }
+
+    /// Returns `true` if the event was injected by this crate (see [`INJECTED_INPUT_TAG`]).
+    pub fn is_own_injection(&self) -> bool {
+        self.is_injected() && self.kbd.dwExtraInfo == INJECTED_INPUT_TAG
+    }
+
+    /// Returns `true` if the key of the event was redacted by [privacy mode](crate::privacy).
+    pub fn is_redacted(&self) -> bool {
+        self.kbd.vkCode == 0
+    }
+
+    /// Clears the key of the event if [privacy mode](crate::privacy) requires it.
+    pub(crate) fn redact_if_private(&mut self) {
+        if crate::privacy::should_redact(self.virtual_key()) {
+            self.kbd.vkCode = 0;
+            self.kbd.scanCode = 0;
+            self.kbd.flags &= !LLKHF_EXTENDED;
+        }
+    }
+
+    /// Returns `true` if the event is a liveness probe sent by [`crate::health::is_active`].
+    pub(crate) fn is_probe(&self) -> bool {
+        self.is_injected() && self.kbd.dwExtraInfo == crate::health::PROBE_INPUT_TAG
+    }
+
+    /// Returns `true` if the event is part of the tap sent by a self-test, see
+    /// [`crate::self_test`].
+    pub(crate) fn is_self_test(&self) -> bool {
+        self.is_injected() && self.kbd.dwExtraInfo == crate::self_test::SELF_TEST_INPUT_TAG
+    }
+
+    /// Returns `true` if [`HookOptions::ignore_injected`] drops the event.
+    pub(crate) fn is_ignored_injection(&self, ignore_injected: bool) -> bool {
+        ignore_injected && self.is_injected() && !self.is_self_test()
+    }
+
+    /// Returns `true` if the event was injected by a process running at a lower integrity level
+    /// (`LLKHF_LOWER_IL_INJECTED`). Such events are always [injected](Self::is_injected) as well.
+    pub fn is_lower_il_injected(&self) -> bool {
+        self.kbd.flags.contains(LLKHF_LOWER_IL_INJECTED)
+    }
+
+    /// Returns `true` if the key is an extended key, such as the right Alt or Ctrl key
+    /// (`LLKHF_EXTENDED`).
+    pub fn is_extended(&self) -> bool {
+        self.kbd.flags.contains(LLKHF_EXTENDED)
+    }
+
+    /// Returns the wall-clock time of the event, derived from the hook tick count `kbd.time`.
+    ///
+    /// The tick count is relative to system boot; this converts it by measuring its age against
+    /// the current tick count, so the result can be correlated with application logs and
+    /// external telemetry. It is only meaningful for events less than ~49.7 days old.
+    pub fn system_time(&self) -> SystemTime {
+        let age = unsafe { GetTickCount() }.wrapping_sub(self.kbd.time);
+        SystemTime::now() - Duration::from_millis(age as u64)
+    }
+
+    /// Returns the duration elapsed since the given earlier event, based on the raw
+    /// `GetTickCount`-style `kbd.time` values.
+    ///
+    /// The tick count has a resolution of about 10–16 ms and wraps every ~49.7 days.
+    /// Prefer [`KeyboardEvent::timestamp`], which [`HoldEvent::duration`] is based on.
     pub fn duration_since(&self, earlier: &Self) -> Duration {
         let millis = self.kbd.time.wrapping_sub(earlier.kbd.time);
         Duration::from_millis(millis as u64)
@@ -375,12 +2434,42 @@ impl MenuTriggerEvent for KeyboardEvent {
     fn key_state(&self) -> KeyState {
         self.wm_key_state.into()
     }
+
+    fn side(&self) -> Option<Side> {
+        match self.virtual_key() {
+            VK_LWIN | VK_LMENU => Some(Side::Left),
+            VK_RWIN | VK_RMENU => Some(Side::Right),
+            _ => None,
+        }
+    }
+
+    fn is_repeat(&self) -> bool {
+        self.repeat
+    }
+
+    fn virtual_key_code(&self) -> Option<VIRTUAL_KEY> {
+        (!self.is_redacted()).then_some(self.virtual_key())
+    }
+
+    fn scan_code(&self) -> Option<u32> {
+        (!self.is_redacted()).then_some(self.kbd.scanCode)
+    }
+
+    fn elapsed_since(&self, earlier: &Self) -> Option<Duration> {
+        Some(self.timestamp.saturating_duration_since(earlier.timestamp))
+    }
+
+    fn received_at(&self) -> Option<Instant> {
+        Some(self.timestamp)
+    }
 }
 
 impl HoldEvent<KeyboardEvent> {
     /// Returns the duration between the key press and release.
     pub fn duration(&self) -> Duration {
-        self.release.duration_since(&self.press)
+        self.release
+            .timestamp
+            .saturating_duration_since(self.press.timestamp)
     }
 }
 
@@ -436,3 +2525,307 @@ impl From<WmKeyState> for KeyState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use windows::Win32::UI::Input::KeyboardAndMouse::{VK_A, VK_F23, VK_LSHIFT, VK_TAB};
+
+    use super::*;
+    use crate::strategy::PreemptiveKeyDown;
+
+    /// Returns an event of `vk` received `ms` milliseconds after `base`.
+    fn key(base: Instant, vk: VIRTUAL_KEY, down: bool, ms: u64) -> KeyboardEvent {
+        let state = if down {
+            WmKeyState::KeyDown
+        } else {
+            WmKeyState::KeyUp
+        };
+        let mut event = KeyboardEvent::new(
+            KBDLLHOOKSTRUCT {
+                vkCode: vk.0 as u32,
+                ..Default::default()
+            },
+            state,
+        );
+        event.timestamp = base + Duration::from_millis(ms);
+        event
+    }
+
+    #[test]
+    fn on_tap_runs_for_quick_taps_only() {
+        let taps = Arc::new(Mutex::new(Vec::new()));
+        let config = Config::default()
+            .set_tap_threshold(Duration::from_millis(200))
+            .set_on_tap({
+                let taps = taps.clone();
+                move |trigger| taps.lock().unwrap().push(trigger)
+            });
+        let base = Instant::now();
+        let events = [
+            key(base, VK_LWIN, true, 0),
+            key(base, VK_LWIN, false, 100),
+            key(base, VK_LMENU, true, 1000),
+            key(base, VK_LMENU, false, 1500),
+        ];
+
+        start_event_handler(events, config).join().unwrap();
+
+        assert_eq!(*taps.lock().unwrap(), [MenuTrigger::Win]);
+    }
+
+    #[test]
+    fn holds_left_and_right_keys_separately() {
+        let holds = Arc::new(Mutex::new(Vec::new()));
+        let config = Config::default().set_on_released({
+            let holds = holds.clone();
+            move |hold: HoldEvent| {
+                holds.lock().unwrap().push((hold.side, hold.duration()));
+                None
+            }
+        });
+        let base = Instant::now();
+        let events = [
+            key(base, VK_LMENU, true, 0),
+            key(base, VK_RMENU, true, 10),
+            key(base, VK_LMENU, false, 20),
+            key(base, VK_RMENU, false, 40),
+        ];
+
+        start_event_handler(events, config).join().unwrap();
+
+        let ms = Duration::from_millis;
+        assert_eq!(
+            *holds.lock().unwrap(),
+            [(Some(Side::Left), ms(20)), (Some(Side::Right), ms(30))]
+        );
+    }
+
+    #[test]
+    fn counts_auto_repeats() {
+        let repeats = Arc::new(Mutex::new(Vec::new()));
+        let config = Config::default().set_on_released({
+            let repeats = repeats.clone();
+            move |hold: HoldEvent| {
+                repeats.lock().unwrap().push(hold.repeat_count());
+                None
+            }
+        });
+        let base = Instant::now();
+        let events = [
+            key(base, VK_LWIN, true, 0),
+            key(base, VK_LWIN, true, 500),
+            key(base, VK_LWIN, true, 530),
+            key(base, VK_LWIN, false, 560),
+            key(base, VK_LWIN, true, 1000),
+            key(base, VK_LWIN, false, 1050),
+        ];
+
+        start_event_handler(events, config).join().unwrap();
+
+        assert_eq!(*repeats.lock().unwrap(), [2, 0]);
+    }
+
+    #[test]
+    fn passes_on_interrupted_holds_only_if_handled() {
+        for handle in [false, true] {
+            let interrupted = Arc::new(Mutex::new(Vec::new()));
+            let config = Config::default()
+                .set_handle_interrupted(handle)
+                .set_on_released({
+                    let interrupted = interrupted.clone();
+                    move |hold: HoldEvent| {
+                        interrupted.lock().unwrap().push(hold.was_interrupted());
+                        None
+                    }
+                });
+            let base = Instant::now();
+            let events = [
+                key(base, VK_LMENU, true, 0),
+                key(base, VK_A, true, 10),
+                key(base, VK_A, false, 20),
+                key(base, VK_LMENU, false, 30),
+                key(base, VK_LMENU, true, 100),
+                key(base, VK_LMENU, false, 150),
+            ];
+
+            start_event_handler(events, config).join().unwrap();
+
+            let expected: &[bool] = if handle { &[true, false] } else { &[false] };
+            assert_eq!(*interrupted.lock().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn records_intervening_keys_up_to_the_limit() {
+        let keys = Arc::new(Mutex::new(Vec::new()));
+        let config = Config::default()
+            .set_handle_interrupted(true)
+            .set_intervening_keys_limit(2)
+            .set_on_released({
+                let keys = keys.clone();
+                move |hold: HoldEvent| {
+                    keys.lock().unwrap().push(hold.intervening_keys().to_vec());
+                    None
+                }
+            });
+        let base = Instant::now();
+        let events = [
+            key(base, VK_LMENU, true, 0),
+            key(base, VK_TAB, true, 10),
+            key(base, VK_TAB, false, 20),
+            key(base, VK_TAB, true, 30),
+            key(base, VK_TAB, false, 40),
+            key(base, VK_A, true, 50),
+            key(base, VK_A, false, 60),
+            key(base, VK_LMENU, false, 70),
+        ];
+
+        start_event_handler(events, config).join().unwrap();
+
+        assert_eq!(*keys.lock().unwrap(), [vec![VK_TAB, VK_TAB]]);
+    }
+
+    #[test]
+    fn rate_limit_counts_only_injections() {
+        let taps = Arc::new(AtomicUsize::new(0));
+        let config = Config::default().set_injection_rate_limit(1).set_on_tap({
+            let taps = taps.clone();
+            move |_| {
+                taps.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        let base = Instant::now();
+        let events = [
+            // Interrupted, so passed through without injecting anything.
+            key(base, VK_LMENU, true, 0),
+            key(base, VK_A, true, 10),
+            key(base, VK_A, false, 20),
+            key(base, VK_LMENU, false, 30),
+            key(base, VK_LWIN, true, 100),
+            key(base, VK_LWIN, false, 150),
+            key(base, VK_LWIN, true, 200),
+            key(base, VK_LWIN, false, 250),
+        ];
+
+        start_event_handler(events, config).join().unwrap();
+
+        // Only the first tap was suppressed, the second one exceeded the limit.
+        assert_eq!(taps.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn rate_limit_counts_pre_emptive_key_downs_once() {
+        let taps = Arc::new(AtomicUsize::new(0));
+        let config = Config::default()
+            .set_strategy(PreemptiveKeyDown::default())
+            .set_injection_rate_limit(1)
+            .set_on_tap({
+                let taps = taps.clone();
+                move |_| {
+                    taps.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        let base = Instant::now();
+        let events = [
+            key(base, VK_LWIN, true, 0),
+            key(base, VK_LWIN, false, 50),
+            key(base, VK_LWIN, true, 100),
+            key(base, VK_LWIN, false, 150),
+        ];
+
+        start_event_handler(events, config).join().unwrap();
+
+        // The first tap was completed by its release, the second one was not started.
+        assert_eq!(taps.load(Ordering::Relaxed), 1);
+    }
+
+    /// Records the calls of a strategy that injects on press, without injecting anything.
+    struct Recording(Arc<Mutex<Vec<&'static str>>>);
+
+    impl SuppressionStrategy for Recording {
+        fn on_press(&self, _press: &KeyboardEvent) -> io::Result<()> {
+            self.0.lock().unwrap().push("press");
+            Ok(())
+        }
+
+        fn injects_on_press(&self) -> bool {
+            true
+        }
+
+        fn suppress(&self, _hold: &HoldEvent, _dummy_key: VIRTUAL_KEY) -> io::Result<()> {
+            self.0.lock().unwrap().push("suppress");
+            Ok(())
+        }
+
+        fn pass_through(&self, _hold: &HoldEvent) -> io::Result<()> {
+            self.0.lock().unwrap().push("pass_through");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn releases_pre_emptive_keys_of_chords() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let config = Config::default()
+            .set_strategy(Recording(calls.clone()))
+            .set_suppress_chord(SystemChord::Copilot, true);
+        let base = Instant::now();
+        let events = [
+            key(base, VK_LWIN, true, 0),
+            key(base, VK_LSHIFT, true, 0),
+            key(base, VK_F23, true, 0),
+            key(base, VK_F23, false, 50),
+            key(base, VK_LSHIFT, false, 50),
+            key(base, VK_LWIN, false, 50),
+        ];
+
+        start_event_handler(events, config).join().unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), ["press", "pass_through"]);
+    }
+
+    #[test]
+    fn releases_pre_emptive_keys_on_foreground_change() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut handler = Handler::new(
+            Config::default()
+                .set_strategy(Recording(calls.clone()))
+                .set_reset_on_foreground_change(true),
+        );
+        let base = Instant::now();
+
+        handler.step(&key(base, VK_LMENU, true, 0));
+        // Pretend that the foreground watcher saw a change.
+        handler.foreground_changes = handler.foreground_changes.wrapping_sub(1);
+        handler.step(&key(base, VK_LMENU, false, 50));
+
+        assert_eq!(*calls.lock().unwrap(), ["press", "pass_through"]);
+    }
+
+    #[test]
+    fn forgets_chord_keys_on_reset() {
+        let chords = Arc::new(Mutex::new(Vec::new()));
+        let mut handler = Handler::new(
+            Config::default()
+                .set_reset_on_foreground_change(true)
+                .set_on_chord({
+                    let chords = chords.clone();
+                    move |chord| chords.lock().unwrap().push(chord)
+                }),
+        );
+        let base = Instant::now();
+
+        // The release of Shift is missed, e.g. on a secure desktop.
+        handler.step(&key(base, VK_LSHIFT, true, 0));
+        handler.foreground_changes = handler.foreground_changes.wrapping_sub(1);
+        handler.step(&key(base, VK_LWIN, true, 100));
+        handler.step(&key(base, VK_F23, true, 110));
+
+        assert!(chords.lock().unwrap().is_empty());
+    }
+}
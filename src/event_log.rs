@@ -0,0 +1,154 @@
+//! Reporting to the Windows Application event log (`event-log` feature).
+//!
+//! Managed deployments usually collect the event log centrally. An [`EventLogReporter`]
+//! writes the errors that need an administrator's attention there: hook registration
+//! failures, hooks re-registered by the watchdog, and injections that keep failing.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use prevent_alt_win_menu::{event_handler::Config, event_log::EventLogReporter};
+//!
+//! let reporter = Arc::new(EventLogReporter::register("MyKioskShell")?);
+//! let on_error = reporter.clone();
+//! let config = Config::default().set_on_error(move |e| on_error.report(e));
+//! if let Err(e) = prevent_alt_win_menu::start(config) {
+//!     reporter.report(&e);
+//! }
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! Windows accepts any source name, but the Event Viewer only shows the messages cleanly if
+//! the source was created once, as an administrator, e.g. during installation:
+//!
+//! ```text
+//! New-EventLog -LogName Application -Source MyKioskShell
+//! ```
+
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use windows::{
+    Win32::{
+        Foundation::HANDLE,
+        System::EventLog::{
+            DeregisterEventSource, EVENTLOG_ERROR_TYPE, EVENTLOG_WARNING_TYPE, REPORT_EVENT_TYPE,
+            RegisterEventSourceW, ReportEventW,
+        },
+    },
+    core::{HSTRING, PCWSTR},
+};
+
+use crate::error::{Error, Result};
+
+/// Injection failures within [`FAILURE_WINDOW`] after which they are considered persistent.
+const FAILURE_THRESHOLD: u32 = 5;
+const FAILURE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Event IDs written by [`EventLogReporter`].
+pub mod event_id {
+    /// The keyboard hook could not be registered.
+    pub const HOOK_REGISTRATION_FAILED: u32 = 1;
+    /// The watchdog registered a removed keyboard hook again.
+    pub const HOOK_REINSTALLED: u32 = 2;
+    /// Injections failed repeatedly within a minute.
+    pub const INJECTION_FAILURES: u32 = 3;
+}
+
+/// Writes selected [`Error`]s to the Application event log.
+pub struct EventLogReporter {
+    /// The event source handle, stored as an integer so that the reporter is `Send`.
+    source: isize,
+    /// The start of the current failure window and the failures within it.
+    failures: Mutex<Option<(Instant, u32)>>,
+}
+
+impl EventLogReporter {
+    /// Opens the event source `name` in the Application log.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::EventSourceRegistration` if the source cannot be opened.
+    pub fn register(name: &str) -> Result<Self> {
+        let source = unsafe { RegisterEventSourceW(PCWSTR::null(), &HSTRING::from(name)) }
+            .map_err(|e| Error::EventSourceRegistration(e.into()))?;
+        Ok(Self {
+            source: source.0 as isize,
+            failures: Mutex::new(None),
+        })
+    }
+
+    /// Writes `error` to the event log if it needs attention, and ignores it otherwise.
+    ///
+    /// Injection failures are only written once five of them occurred within a minute, and
+    /// then at most once per minute.
+    pub fn report(&self, error: &Error) {
+        match error {
+            Error::HookRegistrationFailed(_) | Error::HookThreadCrashed => self.write(
+                EVENTLOG_ERROR_TYPE,
+                event_id::HOOK_REGISTRATION_FAILED,
+                &format!("The keyboard hook could not be registered: {error:?}"),
+            ),
+            Error::HookReinstalled => self.write(
+                EVENTLOG_WARNING_TYPE,
+                event_id::HOOK_REINSTALLED,
+                "The keyboard hook was removed by the system and has been registered again.",
+            ),
+            Error::InjectionFailed(_) if self.failure_is_persistent() => self.write(
+                EVENTLOG_ERROR_TYPE,
+                event_id::INJECTION_FAILURES,
+                &format!(
+                    "{FAILURE_THRESHOLD} or more injections failed within a minute, \
+                     menus may not be suppressed: {error:?}"
+                ),
+            ),
+            _ => {}
+        }
+    }
+
+    /// Counts an injection failure, returning `true` when the threshold is reached.
+    fn failure_is_persistent(&self) -> bool {
+        let mut failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let (start, count) = match *failures {
+            Some((start, count)) if now.duration_since(start) < FAILURE_WINDOW => (start, count),
+            _ => (now, 0),
+        };
+        *failures = Some((start, count + 1));
+        count + 1 == FAILURE_THRESHOLD
+    }
+
+    fn write(&self, kind: REPORT_EVENT_TYPE, id: u32, message: &str) {
+        let message = HSTRING::from(message);
+        let strings = [PCWSTR(message.as_ptr())];
+        let _result = unsafe {
+            ReportEventW(
+                HANDLE(self.source as _),
+                kind,
+                0,
+                id,
+                None,
+                0,
+                Some(&strings),
+                None,
+            )
+        };
+        #[cfg(feature = "log")]
+        if let Err(e) = _result {
+            log::warn!("failed to write to the event log: {}", e);
+        }
+    }
+}
+
+impl Drop for EventLogReporter {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DeregisterEventSource(HANDLE(self.source as _));
+        }
+    }
+}
+
+// The handle of an event source may be used from any thread.
+unsafe impl Sync for EventLogReporter {}
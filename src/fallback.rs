@@ -0,0 +1,131 @@
+//! Detection of menus that open despite suppression, and what to do about them.
+//!
+//! Suppression is best-effort: some environments still open the Start menu or the menu bar.
+//! A thread started by [`start_menu_watcher`] watches [`win_event`](crate::win_event)
+//! notifications and treats a menu that opens shortly after a suppressed release as a failed
+//! suppression. Depending on the configuration, it then
+//!
+//! - closes the menu by sending `Escape` and reports it through [`Config::on_fallback`]
+//!   (see [`Config::set_auto_close`]), and/or
+//! - switches later suppressions to the next fallback strategy, reporting
+//!   `Error::SuppressionFailed` once all of them have failed (see [`Config::set_verification`]).
+//!
+//! [`Config::on_fallback`]: crate::event_handler::Config::on_fallback
+//! [`Config::set_auto_close`]: crate::event_handler::Config::set_auto_close
+//! [`Config::set_verification`]: crate::event_handler::Config::set_verification
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use windows::Win32::UI::{
+    Input::KeyboardAndMouse::VK_ESCAPE,
+    WindowsAndMessaging::{GetClassNameW, GetWindowThreadProcessId},
+};
+
+use crate::{
+    error::{Error, Result},
+    event_handler::{SharedConfig, last_injection, read_config, send_key_tap},
+    foreground::process_name,
+    win_event::{WinEvent, WinEventKind, start_win_event_hook},
+};
+
+/// Processes that host the Start menu and its search.
+const START_MENU_PROCESSES: &[&str] = &[
+    "StartMenuExperienceHost.exe",
+    "SearchHost.exe",
+    "SearchApp.exe",
+];
+
+static ESCALATION_LEVEL: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of failed suppressions detected by verification.
+///
+/// `0` means the configured strategies are in effect; `n` means the `n`-th fallback strategy
+/// is (see [`Config::add_fallback_strategy`]).
+///
+/// [`Config::add_fallback_strategy`]: crate::event_handler::Config::add_fallback_strategy
+pub fn escalation_level() -> usize {
+    ESCALATION_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Starts a thread that watches for menus opening after a suppressed release and applies
+/// [`Config::auto_close`] and [`Config::verify_window`].
+///
+/// [`crate::start`] and [`crate::start_inline`] call this automatically when either is enabled.
+///
+/// # Errors
+/// Returns an error if the WinEvent hook cannot be installed.
+///
+/// [`Config::auto_close`]: crate::event_handler::Config::auto_close
+/// [`Config::verify_window`]: crate::event_handler::Config::verify_window
+pub fn start_menu_watcher(config: SharedConfig) -> Result<thread::JoinHandle<()>> {
+    let (rx, _hook_handle) = start_win_event_hook()?;
+
+    Ok(thread::spawn(move || {
+        for event in rx {
+            if !is_menu(&event) {
+                continue;
+            }
+            let Some(since) = last_injection().map(|at| at.elapsed()) else {
+                continue;
+            };
+            let config = read_config(&config);
+
+            if config.verify_window.is_some_and(|window| since <= window) {
+                let level = ESCALATION_LEVEL.load(Ordering::Relaxed);
+                if level < config.fallback_strategies.len() {
+                    #[cfg(feature = "log")]
+                    log::warn!(
+                        "suppression failed, switching to fallback strategy {}",
+                        level
+                    );
+                    ESCALATION_LEVEL.store(level + 1, Ordering::Relaxed);
+                } else {
+                    (config.on_error)(&Error::SuppressionFailed);
+                }
+            }
+
+            if config.auto_close.is_some_and(|window| since <= window) {
+                #[cfg(feature = "log")]
+                log::warn!(
+                    "menu opened despite suppression ({:?}), closing it",
+                    event.kind
+                );
+
+                if let Err(e) = send_key_tap(VK_ESCAPE) {
+                    (config.on_error)(&Error::InjectionFailed(e));
+                }
+                if let Some(on_fallback) = &config.on_fallback {
+                    on_fallback(event);
+                }
+            }
+        }
+    }))
+}
+
+/// Returns `true` if `event` indicates that a menu bar, window menu or the Start menu opened.
+fn is_menu(event: &WinEvent) -> bool {
+    match event.kind {
+        WinEventKind::MenuStart | WinEventKind::MenuPopupStart => true,
+        WinEventKind::Foreground => is_start_menu(event),
+        WinEventKind::MenuEnd | WinEventKind::MenuPopupEnd => false,
+    }
+}
+
+fn is_start_menu(event: &WinEvent) -> bool {
+    let mut class = [0u16; 64];
+    let len = unsafe { GetClassNameW(event.hwnd(), &mut class) } as usize;
+    if String::from_utf16_lossy(&class[..len]) != "Windows.UI.Core.CoreWindow" {
+        return false;
+    }
+
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(event.hwnd(), Some(&mut pid)) };
+    process_name(pid).is_some_and(|name| {
+        START_MENU_PROCESSES
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(&name))
+    })
+}
@@ -0,0 +1,181 @@
+//! Helpers for querying the foreground window and the process that owns it.
+//!
+//! The event handler can capture a [`ForegroundWindow`] when a trigger key is pressed and when
+//! it is released (see [`Config::set_capture_foreground`]), which enables decisions such as
+//! "suppress only if focus did not change" or per-application behavior inside callbacks.
+//!
+//! [`Config::set_capture_foreground`]: crate::event_handler::Config::set_capture_foreground
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+};
+
+use windows::{
+    Win32::{
+        Foundation::{HANDLE, HWND},
+        System::Threading::{
+            OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+            QueryFullProcessImageNameW,
+        },
+        UI::{
+            Accessibility::{HWINEVENTHOOK, SetWinEventHook, UnhookWinEvent},
+            WindowsAndMessaging::{
+                DispatchMessageW, EVENT_SYSTEM_FOREGROUND, GetForegroundWindow, GetMessageW,
+                GetWindowThreadProcessId, MSG, WINEVENT_OUTOFCONTEXT,
+            },
+        },
+    },
+    core::{Owned, PWSTR},
+};
+
+use crate::{
+    activation,
+    error::{Error, Result},
+};
+
+/// A snapshot of the foreground window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForegroundWindow {
+    /// The raw window handle value. Use [`ForegroundWindow::hwnd`] to get an `HWND`.
+    pub handle: usize,
+    /// The ID of the process that owns the window.
+    pub process_id: u32,
+    /// The executable file name of the owning process (e.g. `notepad.exe`), if it could be queried.
+    pub process_name: Option<String>,
+}
+
+impl ForegroundWindow {
+    /// Captures the current foreground window.
+    ///
+    /// Returns `None` if there is no foreground window, e.g. while the desktop is switching.
+    pub fn current() -> Option<Self> {
+        Self::from_hwnd(unsafe { GetForegroundWindow() })
+    }
+
+    fn from_hwnd(hwnd: HWND) -> Option<Self> {
+        if hwnd.is_invalid() {
+            return None;
+        }
+
+        let mut pid = 0;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+
+        Some(Self {
+            handle: hwnd.0 as usize,
+            process_id: pid,
+            process_name: (pid != 0).then(|| process_name(pid)).flatten(),
+        })
+    }
+
+    /// Returns the window handle.
+    pub fn hwnd(&self) -> HWND {
+        HWND(self.handle as *mut _)
+    }
+}
+
+/// Returns the executable file name (e.g. `notepad.exe`) of the process that owns
+/// the current foreground window.
+///
+/// Returns `None` if there is no foreground window or the process cannot be queried.
+pub fn foreground_process_name() -> Option<String> {
+    ForegroundWindow::current()?.process_name
+}
+
+pub(crate) fn process_name(pid: u32) -> Option<String> {
+    let process: Owned<HANDLE> =
+        unsafe { Owned::new(OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?) };
+
+    let mut buf = [0u16; 1024];
+    let mut len = buf.len() as u32;
+    unsafe {
+        QueryFullProcessImageNameW(
+            *process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        )
+        .ok()?;
+    }
+
+    let path = String::from_utf16_lossy(&buf[..len as usize]);
+    path.rsplit('\\').next().map(str::to_owned)
+}
+
+static FOREGROUND_CHANGES: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of foreground window changes observed by the watcher started with
+/// [`start_foreground_watcher`].
+///
+/// The value only increases; compare two readings to detect a focus change in between.
+pub fn foreground_change_count() -> u64 {
+    FOREGROUND_CHANGES.load(Ordering::Relaxed)
+}
+
+/// Starts a thread that observes foreground window changes (`EVENT_SYSTEM_FOREGROUND`).
+///
+/// Each change increments [`foreground_change_count`] and is published to
+/// [`activation::subscribe`](crate::activation::subscribe) receivers. The event handler uses it to discard
+/// pending holds when focus switches (see [`Config::set_reset_on_foreground_change`]), so that
+/// a stale press is never paired with a much later release.
+///
+/// # Errors
+/// Returns `Error::WinEventHookFailed` if the WinEvent hook cannot be installed.
+///
+/// [`Config::set_reset_on_foreground_change`]: crate::event_handler::Config::set_reset_on_foreground_change
+pub fn start_foreground_watcher() -> Result<thread::JoinHandle<()>> {
+    let (result_tx, result_rx) = oneshot::channel::<Result<()>>();
+
+    let join_handle = thread::spawn(move || {
+        let hook = unsafe {
+            SetWinEventHook(
+                EVENT_SYSTEM_FOREGROUND,
+                EVENT_SYSTEM_FOREGROUND,
+                None,
+                Some(foreground_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            )
+        };
+
+        if hook.is_invalid() {
+            let _ = result_tx.send(Err(Error::WinEventHookFailed));
+            return;
+        }
+        let _ = result_tx.send(Ok(()));
+
+        #[cfg(feature = "log")]
+        log::info!("started foreground watcher");
+
+        let mut msg = MSG::default();
+        unsafe {
+            while GetMessageW(&mut msg, None, 0, 0).into() {
+                DispatchMessageW(&msg);
+            }
+            let _ = UnhookWinEvent(hook);
+        }
+    });
+
+    match result_rx.recv() {
+        Ok(Ok(())) => Ok(join_handle),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(Error::HookThreadCrashed),
+    }
+}
+
+unsafe extern "system" fn foreground_proc(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    hwnd: HWND,
+    _id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    FOREGROUND_CHANGES.fetch_add(1, Ordering::Relaxed);
+
+    if let Some(window) = ForegroundWindow::from_hwnd(hwnd) {
+        activation::publish(window);
+    }
+}
@@ -0,0 +1,262 @@
+//! Heartbeats of the keyboard hook and event handler threads.
+//!
+//! Both threads started by [`crate::start`] publish when they last ran and how many events
+//! passed between them. A long-running application can poll [`health`] (or
+//! [`JoinHandles::health`](crate::JoinHandles::health)) to notice a wedged hook or handler
+//! and restart suppression.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! let handles = prevent_alt_win_menu::start(Default::default())?;
+//! // ...
+//! let health = handles.health();
+//! if !health.is_responsive(Duration::from_secs(5)) {
+//!     eprintln!("suppression is stuck: {health:?}");
+//! }
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! [`Health`] also carries the counters to alert on when suppression degrades: dropped
+//! events, the queue depth and the lag of the handler behind the hook, with their peaks.
+//!
+//! Heartbeats only show that the threads run. [`is_active`] goes further and sends a probe
+//! event through the hook to the handler.
+
+use std::{
+    io,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use windows::Win32::{
+    System::SystemInformation::GetTickCount64,
+    UI::Input::KeyboardAndMouse::{
+        INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, VK__none_,
+    },
+};
+
+use crate::input;
+
+/// The value stored in `KEYBDINPUT.dwExtraInfo` of the probe sent by [`is_active`].
+///
+/// The probe is a key-up of `VK__none_`. The keyboard hook swallows it, so no application
+/// receives it.
+pub const PROBE_INPUT_TAG: usize = 0x5041_5750; // "PAWP"
+
+/// How often idle threads publish a heartbeat.
+pub(crate) const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Tick counts (`GetTickCount64`) of the latest occurrence, or 0 if there was none yet.
+static HOOK_HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+static HOOK_EVENT: AtomicU64 = AtomicU64::new(0);
+static HANDLER_HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+static HANDLED_EVENT: AtomicU64 = AtomicU64::new(0);
+
+static QUEUED: AtomicU64 = AtomicU64::new(0);
+static DEQUEUED: AtomicU64 = AtomicU64::new(0);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+static MAX_QUEUE_DEPTH: AtomicU64 = AtomicU64::new(0);
+// Microseconds the latest and the longest waiting event spent in the queue.
+static LAG: AtomicU64 = AtomicU64::new(0);
+static MAX_LAG: AtomicU64 = AtomicU64::new(0);
+
+static PROBES_AT_HOOK: AtomicU64 = AtomicU64::new(0);
+static PROBES_AT_HANDLER: AtomicU64 = AtomicU64::new(0);
+static PROBE_LOCK: Mutex<()> = Mutex::new(());
+
+/// A snapshot of the liveness of the keyboard hook and event handler threads.
+///
+/// Durations are measured from the time of the snapshot and are `None` if the event has not
+/// happened yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Health {
+    /// Time since the hook thread last ran its message loop. It does so at least every second.
+    pub hook_heartbeat: Option<Duration>,
+    /// Time since the hook procedure last queued a keyboard event for the handler.
+    pub last_hook_event: Option<Duration>,
+    /// Time since the event handler last waited for or took an event. It does so at least
+    /// every second unless it is stuck processing an event.
+    pub handler_heartbeat: Option<Duration>,
+    /// Time since the event handler last took an event from the queue.
+    pub last_handled_event: Option<Duration>,
+    /// The number of events queued by the hook but not yet taken by the handler.
+    pub queue_depth: u64,
+    /// The highest [`Health::queue_depth`] observed since the start or the last
+    /// [`reset_peaks`].
+    pub max_queue_depth: u64,
+    /// The number of events dropped because the queue was full.
+    pub dropped_events: u64,
+    /// How long the event last taken by the handler waited in the queue, i.e. how far the
+    /// handler is behind the hook.
+    pub lag: Option<Duration>,
+    /// The longest [`Health::lag`] observed since the start or the last [`reset_peaks`].
+    pub max_lag: Duration,
+}
+
+impl Health {
+    /// Returns `true` if both threads published a heartbeat within `timeout`.
+    pub fn is_responsive(&self, timeout: Duration) -> bool {
+        self.hook_heartbeat.is_some_and(|age| age <= timeout)
+            && self.handler_heartbeat.is_some_and(|age| age <= timeout)
+    }
+}
+
+/// Returns the current [`Health`] of the keyboard hook and event handler threads.
+pub fn health() -> Health {
+    let now = unsafe { GetTickCount64() };
+    let age = |stamp: &AtomicU64| match stamp.load(Ordering::Relaxed) {
+        0 => None,
+        at => Some(Duration::from_millis(now.saturating_sub(at))),
+    };
+
+    Health {
+        hook_heartbeat: age(&HOOK_HEARTBEAT),
+        last_hook_event: age(&HOOK_EVENT),
+        handler_heartbeat: age(&HANDLER_HEARTBEAT),
+        last_handled_event: age(&HANDLED_EVENT),
+        queue_depth: queue_depth(),
+        max_queue_depth: MAX_QUEUE_DEPTH.load(Ordering::Relaxed),
+        dropped_events: dropped_events(),
+        lag: (DEQUEUED.load(Ordering::Relaxed) > 0)
+            .then(|| Duration::from_micros(LAG.load(Ordering::Relaxed))),
+        max_lag: Duration::from_micros(MAX_LAG.load(Ordering::Relaxed)),
+    }
+}
+
+/// Resets [`Health::max_queue_depth`] and [`Health::max_lag`], e.g. at the start of each
+/// monitoring interval.
+pub fn reset_peaks() {
+    MAX_QUEUE_DEPTH.store(queue_depth(), Ordering::Relaxed);
+    MAX_LAG.store(0, Ordering::Relaxed);
+}
+
+/// Returns the number of events dropped because the queue was full.
+pub(crate) fn dropped_events() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Returns the number of events queued by the hook but not yet taken by the handler.
+pub(crate) fn queue_depth() -> u64 {
+    QUEUED
+        .load(Ordering::Relaxed)
+        .saturating_sub(DEQUEUED.load(Ordering::Relaxed))
+}
+
+fn stamp(at: &AtomicU64) {
+    at.store(unsafe { GetTickCount64() }, Ordering::Relaxed);
+}
+
+pub(crate) fn hook_heartbeat() {
+    stamp(&HOOK_HEARTBEAT);
+}
+
+/// Records an event received by the hook procedure, and whether it could be queued.
+pub(crate) fn hook_event(queued: bool) {
+    stamp(&HOOK_EVENT);
+    if queued {
+        QUEUED.fetch_add(1, Ordering::Relaxed);
+        MAX_QUEUE_DEPTH.fetch_max(queue_depth(), Ordering::Relaxed);
+    } else {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn handler_heartbeat() {
+    stamp(&HANDLER_HEARTBEAT);
+}
+
+/// Records an event taken from the queue by the handler, which the hook received at
+/// `received_at`.
+pub(crate) fn handler_event(received_at: Instant) {
+    stamp(&HANDLED_EVENT);
+    stamp(&HANDLER_HEARTBEAT);
+    DEQUEUED.fetch_add(1, Ordering::Relaxed);
+    let lag = received_at.elapsed().as_micros() as u64;
+    LAG.store(lag, Ordering::Relaxed);
+    MAX_LAG.fetch_max(lag, Ordering::Relaxed);
+}
+
+/// The result of [`is_active`].
+#[derive(Debug)]
+pub enum Liveness {
+    /// The probe passed through the hook and reached the event handler.
+    Active {
+        /// The time from injecting the probe until the handler received it.
+        round_trip: Duration,
+    },
+    /// The hook did not receive the probe: it is not installed, Windows removed it, or the
+    /// hook thread is stuck or has exited.
+    HookInactive,
+    /// The hook received the probe, but the event handler did not take it in time.
+    HandlerStalled,
+    /// The probe could not be injected, e.g. because input is blocked on the secure desktop.
+    ProbeFailed(io::Error),
+}
+
+impl Liveness {
+    /// Returns `true` for [`Liveness::Active`].
+    pub fn is_active(&self) -> bool {
+        matches!(self, Liveness::Active { .. })
+    }
+}
+
+/// Verifies that the keyboard hook is installed and delivers events to the handler.
+///
+/// Injects a probe (see [`PROBE_INPUT_TAG`]) and waits up to `timeout` for it to arrive at
+/// the event handler. This covers the hooks started by [`crate::start`] and
+/// [`crate::start_inline`]; other backends do not recognize the probe. Must not be called
+/// from the hook thread, e.g. from a callback of [`crate::start_inline`], since the probe
+/// could never be processed.
+pub fn is_active(timeout: Duration) -> Liveness {
+    let _guard = PROBE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let at_hook = PROBES_AT_HOOK.load(Ordering::Acquire);
+    let at_handler = PROBES_AT_HANDLER.load(Ordering::Acquire);
+
+    let start = Instant::now();
+    if let Err(e) = input::send(&[probe_input()]) {
+        return Liveness::ProbeFailed(e);
+    }
+
+    while start.elapsed() < timeout {
+        if PROBES_AT_HANDLER.load(Ordering::Acquire) != at_handler {
+            return Liveness::Active {
+                round_trip: start.elapsed(),
+            };
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    if PROBES_AT_HOOK.load(Ordering::Acquire) != at_hook {
+        Liveness::HandlerStalled
+    } else {
+        Liveness::HookInactive
+    }
+}
+
+fn probe_input() -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VK__none_,
+                dwFlags: KEYEVENTF_KEYUP,
+                dwExtraInfo: PROBE_INPUT_TAG,
+                ..Default::default()
+            },
+        },
+    }
+}
+
+pub(crate) fn probe_at_hook() {
+    PROBES_AT_HOOK.fetch_add(1, Ordering::Release);
+}
+
+pub(crate) fn probe_at_handler() {
+    PROBES_AT_HANDLER.fetch_add(1, Ordering::Release);
+}
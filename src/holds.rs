@@ -0,0 +1,127 @@
+//! Completed holds, delivered after the suppression decision.
+//!
+//! Applications that react to trigger keys themselves, such as a custom launcher opened by
+//! a `Win` tap, can await each hold the event handler completes instead of reimplementing
+//! hold tracking:
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::event_handler::{Config, MenuTrigger};
+//!
+//! # async fn run() -> prevent_alt_win_menu::error::Result<()> {
+//! let suppressor = prevent_alt_win_menu::start(Config::default())?;
+//! while let Some(hold) = suppressor.next_hold().await {
+//!     if hold.trigger == MenuTrigger::Win && hold.was_suppressed() {
+//!         println!("open the launcher");
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The futures only rely on [`std::task::Waker`], so any executor works.
+
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Poll, Waker},
+};
+
+use crate::event_handler::{HoldEvent, KeyboardEvent};
+
+/// How many holds are kept for a receiver that does not keep up. Older holds are discarded.
+const CAPACITY: usize = 64;
+
+struct State<T> {
+    holds: VecDeque<HoldEvent<T>>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+type Shared<T> = Arc<Mutex<State<T>>>;
+
+fn lock<T>(shared: &Shared<T>) -> MutexGuard<'_, State<T>> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Creates a connected sender and receiver.
+pub(crate) fn channel<T>() -> (HoldSender<T>, HoldReceiver<T>) {
+    let shared = Arc::new(Mutex::new(State {
+        holds: VecDeque::with_capacity(CAPACITY),
+        waker: None,
+        closed: false,
+    }));
+    (
+        HoldSender {
+            shared: shared.clone(),
+        },
+        HoldReceiver { shared },
+    )
+}
+
+/// The event handler's end of a [`HoldReceiver`].
+pub(crate) struct HoldSender<T> {
+    shared: Shared<T>,
+}
+
+impl<T: Clone> HoldSender<T> {
+    /// Delivers a copy of `hold`. Returns `false` if the receiver is gone.
+    pub(crate) fn send(&self, hold: &HoldEvent<T>) -> bool {
+        if Arc::strong_count(&self.shared) == 1 {
+            return false;
+        }
+        let mut state = lock(&self.shared);
+        if state.holds.len() == CAPACITY {
+            state.holds.pop_front();
+        }
+        state.holds.push_back(hold.clone());
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+}
+
+impl<T> Drop for HoldSender<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared);
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Receives the holds completed by an event handler, see
+/// [`Handler::subscribe_holds`](crate::event_handler::Handler::subscribe_holds) and
+/// [`JoinHandles::next_hold`](crate::JoinHandles::next_hold).
+///
+/// Only one task should wait on a receiver at a time.
+pub struct HoldReceiver<T = KeyboardEvent> {
+    shared: Shared<T>,
+}
+
+impl<T> HoldReceiver<T> {
+    /// Waits for the next completed hold.
+    ///
+    /// Returns `None` once the event handler has exited and all holds have been received.
+    pub async fn next_hold(&self) -> Option<HoldEvent<T>> {
+        poll_fn(|cx| {
+            let mut state = lock(&self.shared);
+            match state.holds.pop_front() {
+                Some(hold) => Poll::Ready(Some(hold)),
+                None if state.closed => Poll::Ready(None),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Returns the next completed hold if there is one, without waiting.
+    pub fn try_next_hold(&self) -> Option<HoldEvent<T>> {
+        lock(&self.shared).holds.pop_front()
+    }
+}
@@ -0,0 +1,137 @@
+//! Several hooks on a single message-loop thread.
+//!
+//! [`crate::keyboard_hook`], [`crate::mouse_hook`] and [`crate::win_event`] each spawn their
+//! own thread with its own message loop. A [`HookManager`] registers the hooks it is
+//! configured with on one thread instead, and sends their events through one channel as
+//! [`HookMessage`]s, tagged with the hook they come from.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::hook_manager::{HookManager, HookMessage};
+//!
+//! let (rx, hooks) = HookManager::new().with_mouse().with_win_events().start()?;
+//! for message in rx {
+//!     match message {
+//!         HookMessage::Keyboard(event) => println!("key {:?}", event.kbd.vkCode),
+//!         HookMessage::Mouse(event) => println!("mouse {:?}", event.message),
+//!         HookMessage::WinEvent(event) => println!("system {:?}", event.kind),
+//!     }
+//! }
+//! hooks.shutdown().ok();
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! Unlike [`crate::keyboard_hook::start_keyboard_hook`], keyboard events are sent through an
+//! unbounded channel from inside the hook procedure, as mouse events are.
+
+use std::sync::mpsc;
+
+use crate::{
+    error::Result,
+    event_handler::KeyboardEvent,
+    keyboard_hook::{self, HookOptions, HookThread},
+    mouse_hook::{self, MouseEvent},
+    win_event::{self, WinEvent},
+};
+
+/// An event from one of the hooks of a [`HookManager`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookMessage {
+    /// An event of the low-level keyboard hook.
+    Keyboard(KeyboardEvent),
+    /// An event of the low-level mouse hook, if [`HookManager::with_mouse`] is set.
+    Mouse(MouseEvent),
+    /// A menu or foreground event, if [`HookManager::with_win_events`] is set.
+    WinEvent(WinEvent),
+}
+
+/// Builder for a thread hosting the keyboard hook together with other hooks.
+#[derive(Debug, Clone, Default)]
+pub struct HookManager {
+    options: HookOptions,
+    mouse: bool,
+    win_events: bool,
+}
+
+impl HookManager {
+    /// Creates a manager hosting only the keyboard hook, with default [`HookOptions`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the options applied inside the keyboard hook.
+    ///
+    /// # Returns
+    /// A modified [`HookManager`] instance (builder pattern).
+    pub fn with_keyboard_options(mut self, options: HookOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Also hosts the low-level mouse hook. Button presses count towards
+    /// [`mouse_hook::click_count`].
+    ///
+    /// # Returns
+    /// A modified [`HookManager`] instance (builder pattern).
+    pub fn with_mouse(mut self) -> Self {
+        self.mouse = true;
+        self
+    }
+
+    /// Also hosts the WinEvent hook for menu and foreground events.
+    ///
+    /// # Returns
+    /// A modified [`HookManager`] instance (builder pattern).
+    pub fn with_win_events(mut self) -> Self {
+        self.win_events = true;
+        self
+    }
+
+    /// Registers the hooks on a new thread.
+    ///
+    /// Returns the receiver of all events, which ends once the thread has shut down, and the
+    /// [`HookThread`], whose [`HookThread::shutdown`] removes all hooks at once.
+    ///
+    /// # Errors
+    /// - Returns `Error::HookRegistrationFailed` if the keyboard or mouse hook fails to register.
+    /// - Returns `Error::WinEventHookFailed` if the WinEvent hook cannot be installed.
+    /// - Returns `Error::HookThreadCrashed` if the hook thread terminated unexpectedly.
+    pub fn start(self) -> Result<(mpsc::Receiver<HookMessage>, HookThread)> {
+        let (tx, rx) = mpsc::channel();
+        let ignore_injected = self.options.ignore_injected;
+
+        let keyboard_tx = tx.clone();
+        let keyboard = Box::new(move |event: KeyboardEvent| {
+            // Filtered here, since there is no `KeyboardEvents` to do it.
+            if event.is_probe()
+                || event.is_own_injection()
+                || (ignore_injected && event.is_injected())
+            {
+                return;
+            }
+            let _ = keyboard_tx.send(HookMessage::Keyboard(event));
+        });
+
+        let (mouse, win_events) = (self.mouse, self.win_events);
+        let install = move || {
+            let mouse_hook = mouse
+                .then(|| {
+                    let tx = tx.clone();
+                    mouse_hook::install_mouse_hook(Some(Box::new(move |event| {
+                        let _ = tx.send(HookMessage::Mouse(event));
+                    })))
+                })
+                .transpose()?;
+            let win_event_hook = win_events
+                .then(|| {
+                    win_event::install_win_event_hook(Box::new(move |event| {
+                        let _ = tx.send(HookMessage::WinEvent(event));
+                    }))
+                })
+                .transpose()?;
+            Ok((mouse_hook, win_event_hook))
+        };
+
+        let hook_thread = keyboard_hook::start_callback_hook(self.options, keyboard, install)?;
+        Ok((rx, hook_thread))
+    }
+}
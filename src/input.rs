@@ -0,0 +1,171 @@
+//! The injection side of suppression, abstracted behind [`InputSender`].
+//!
+//! Every event this crate injects, from dummy keys to [`InputBatch`], goes through the
+//! process-wide sender. By default it is [`SendInputSender`], which calls `SendInput`.
+//! Replace it with [`set_input_sender`] to capture what would have been injected in tests,
+//! or to route input to another sink.
+//!
+//! ```
+//! use prevent_alt_win_menu::event_handler::{KeyboardAndMouse::VK_F24, send_key_tap};
+//! use prevent_alt_win_menu::input::{RecordingSender, set_input_sender};
+//!
+//! let recorder = RecordingSender::default();
+//! set_input_sender(recorder.clone());
+//!
+//! send_key_tap(VK_F24)?;
+//! assert_eq!(recorder.inputs().len(), 2);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [`InputBatch`]: crate::event_handler::InputBatch
+
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+};
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{INPUT, SendInput};
+
+/// A sink for injected input.
+pub trait InputSender: Send + Sync {
+    /// Injects `inputs` atomically, in order.
+    fn send(&self, inputs: &[INPUT]) -> io::Result<()>;
+}
+
+/// Injects input with `SendInput`. This is the default sender.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SendInputSender;
+
+impl InputSender for SendInputSender {
+    fn send(&self, inputs: &[INPUT]) -> io::Result<()> {
+        let result = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+
+        if result as usize != inputs.len() {
+            Err(io::Error::last_os_error())
+        } else {
+            #[cfg(feature = "log")]
+            log::trace!("SendInput: {:?}", VirtualKeys(inputs));
+            Ok(())
+        }
+    }
+}
+
+/// Formats the virtual keys of `INPUT`s without collecting them first.
+#[cfg(feature = "log")]
+struct VirtualKeys<'a>(&'a [INPUT]);
+
+#[cfg(feature = "log")]
+impl std::fmt::Debug for VirtualKeys<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|i| unsafe { i.Anonymous.ki.wVk }))
+            .finish()
+    }
+}
+
+/// Records input instead of injecting it.
+///
+/// Clones share the same record, so keep one clone to inspect what was sent after passing
+/// another to [`set_input_sender`].
+#[derive(Clone, Default)]
+pub struct RecordingSender {
+    inputs: Arc<Mutex<Vec<INPUT>>>,
+}
+
+impl RecordingSender {
+    /// Returns all inputs recorded so far.
+    pub fn inputs(&self) -> Vec<INPUT> {
+        self.inputs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Discards all recorded inputs.
+    pub fn clear(&self) {
+        self.inputs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+impl InputSender for RecordingSender {
+    fn send(&self, inputs: &[INPUT]) -> io::Result<()> {
+        self.inputs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend_from_slice(inputs);
+        Ok(())
+    }
+}
+
+static INPUT_SENDER: RwLock<Option<Arc<dyn InputSender>>> = RwLock::new(None);
+
+/// Replaces the process-wide [`InputSender`].
+pub fn set_input_sender(sender: impl InputSender + 'static) {
+    *INPUT_SENDER.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(sender));
+}
+
+/// Restores the default [`SendInputSender`].
+pub fn reset_input_sender() {
+    *INPUT_SENDER.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+thread_local! {
+    static DEFERRING: Cell<bool> = const { Cell::new(false) };
+    /// Input deferred on this thread. Keeps its capacity between batches.
+    static DEFERRED: RefCell<Vec<INPUT>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Sends `inputs` through the process-wide sender, or appends them to the deferred batch
+/// while [`defer`] is in effect on this thread.
+pub(crate) fn send(inputs: &[INPUT]) -> io::Result<()> {
+    if DEFERRING.get() {
+        DEFERRED.with_borrow_mut(|deferred| deferred.extend_from_slice(inputs));
+        return Ok(());
+    }
+    send_now(inputs)
+}
+
+/// Defers input sent on this thread until the next [`flush`], so that it is injected with
+/// a single call.
+pub(crate) fn defer() {
+    DEFERRING.set(true);
+}
+
+/// Returns whether input sent on this thread is currently deferred.
+pub(crate) fn is_deferring() -> bool {
+    DEFERRING.get()
+}
+
+/// Stops deferring and injects the input deferred on this thread, if any.
+pub(crate) fn flush() -> io::Result<()> {
+    DEFERRING.set(false);
+    DEFERRED.with_borrow_mut(|deferred| {
+        if deferred.is_empty() {
+            return Ok(());
+        }
+        #[cfg(feature = "log")]
+        log::trace!("flushing {} deferred inputs", deferred.len());
+        let result = send_now(deferred);
+        deferred.clear();
+        result
+    })
+}
+
+fn send_now(inputs: &[INPUT]) -> io::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("inject", inputs = inputs.len()).entered();
+
+    let sender = INPUT_SENDER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+
+    match sender {
+        Some(sender) => sender.send(inputs),
+        None => SendInputSender.send(inputs),
+    }
+}
@@ -0,0 +1,321 @@
+//! Event source and sink backed by the [Interception](https://github.com/oblitum/Interception)
+//! kernel driver.
+//!
+//! Some applications, notably games protected by anti-cheat software, read keyboard input
+//! below the low-level hook layer, so neither `WH_KEYBOARD_LL` events nor `SendInput`
+//! injections reach them. With the Interception driver installed, this module captures
+//! keystrokes at the driver level and injects dummy keys the same way.
+//!
+//! Captured strokes are forwarded to the system unchanged and delivered as
+//! [`InterceptionEvent`]s, which implement [`MenuTriggerEvent`] and can be passed to
+//! [`start_event_handler`](crate::event_handler::start_event_handler). Suppression is done
+//! with the [`InterceptionKeyUp`] strategy.
+//!
+//! Requires the `interception` feature. The driver must be installed, and `interception.lib`
+//! must be found by the linker (`interception.dll` at runtime).
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::event_handler::{Config, start_event_handler};
+//! use prevent_alt_win_menu::interception::{InterceptionKeyUp, start_interception};
+//!
+//! let (rx, interception, _) = start_interception()?;
+//! let config = Config::default().set_strategy(InterceptionKeyUp::new(interception));
+//! start_event_handler(rx, config);
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+
+use std::{
+    ffi::c_void,
+    io,
+    sync::{
+        Arc,
+        atomic::{AtomicI32, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    MAPVK_VSC_TO_VK_EX, MapVirtualKeyW, VIRTUAL_KEY, VK_LMENU, VK_LWIN, VK_MENU, VK_RMENU, VK_RWIN,
+};
+
+use crate::{
+    error::{Error, Result},
+    event_handler::{HoldEvent, KeyState, MenuTrigger, MenuTriggerEvent, Side},
+    strategy::SuppressionStrategy,
+};
+
+/// The key-up bit of [`KeyStroke::state`].
+pub const KEY_UP: u16 = 0x01;
+/// The `E0` prefix bit of [`KeyStroke::state`], set for extended keys.
+pub const KEY_E0: u16 = 0x02;
+/// The `E1` prefix bit of [`KeyStroke::state`].
+pub const KEY_E1: u16 = 0x04;
+
+const FILTER_KEY_ALL: u16 = 0xFFFF;
+const MAX_KEYBOARD: i32 = 10;
+
+/// A keystroke as reported by the Interception driver (`InterceptionKeyStroke`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyStroke {
+    /// The scan code, without prefix.
+    pub code: u16,
+    /// A combination of [`KEY_UP`], [`KEY_E0`] and [`KEY_E1`].
+    pub state: u16,
+    /// Device-specific extra information.
+    pub information: u32,
+}
+
+#[link(name = "interception")]
+unsafe extern "C" {
+    fn interception_create_context() -> *mut c_void;
+    fn interception_destroy_context(context: *mut c_void);
+    fn interception_set_filter(
+        context: *mut c_void,
+        predicate: unsafe extern "C" fn(device: i32) -> i32,
+        filter: u16,
+    );
+    fn interception_wait(context: *mut c_void) -> i32;
+    fn interception_receive(
+        context: *mut c_void,
+        device: i32,
+        stroke: *mut KeyStroke,
+        nstroke: u32,
+    ) -> i32;
+    fn interception_send(
+        context: *mut c_void,
+        device: i32,
+        stroke: *const KeyStroke,
+        nstroke: u32,
+    ) -> i32;
+}
+
+unsafe extern "C" fn is_keyboard(device: i32) -> i32 {
+    (1..=MAX_KEYBOARD).contains(&device) as i32
+}
+
+struct Context(*mut c_void);
+
+// SAFETY: The driver context is a set of device handles; the library allows waiting and
+// receiving on one thread while sending from another.
+unsafe impl Send for Context {}
+unsafe impl Sync for Context {}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe { interception_destroy_context(self.0) };
+    }
+}
+
+/// A handle to the Interception driver used to inject keystrokes.
+///
+/// Keystrokes are sent to the keyboard device that produced the most recent event.
+#[derive(Clone)]
+pub struct Interception {
+    context: Arc<Context>,
+    last_device: Arc<AtomicI32>,
+}
+
+impl std::fmt::Debug for Interception {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interception")
+            .field("last_device", &self.last_device.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Interception {
+    /// Sends a key-up stroke of the given scan code. An `0xE0` high byte sets [`KEY_E0`].
+    ///
+    /// # Errors
+    /// Fails if no keyboard event has been received yet, so there is no device to send to,
+    /// or if the driver rejects the stroke.
+    pub fn send_keyup_scancode(&self, scan: u16) -> io::Result<()> {
+        let mut state = KEY_UP;
+        if scan & 0xFF00 == 0xE000 {
+            state |= KEY_E0;
+        }
+        self.send(&KeyStroke {
+            code: scan & 0x00FF,
+            state,
+            information: 0,
+        })
+    }
+
+    /// Sends a keystroke to the most recently active keyboard.
+    pub fn send(&self, stroke: &KeyStroke) -> io::Result<()> {
+        let device = self.last_device.load(Ordering::Relaxed);
+        if device == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "no keyboard device seen yet",
+            ));
+        }
+
+        let sent = unsafe { interception_send(self.context.0, device, stroke, 1) };
+        if sent == 1 {
+            Ok(())
+        } else {
+            Err(io::Error::other("interception_send failed"))
+        }
+    }
+}
+
+/// A keyboard event captured by the Interception driver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InterceptionEvent {
+    /// The keyboard device that produced the stroke.
+    pub device: i32,
+    /// The raw stroke.
+    pub stroke: KeyStroke,
+    /// When the stroke was received.
+    pub timestamp: Instant,
+}
+
+impl InterceptionEvent {
+    /// Returns the scan code with the `0xE0` prefix in the high byte for extended keys.
+    pub fn extended_scan_code(&self) -> u16 {
+        if self.stroke.state & KEY_E0 != 0 {
+            0xE000 | self.stroke.code
+        } else {
+            self.stroke.code
+        }
+    }
+}
+
+impl MenuTriggerEvent for InterceptionEvent {
+    fn menu_trigger(&self) -> Option<MenuTrigger> {
+        match self.virtual_key_code()? {
+            VK_LWIN | VK_RWIN => Some(MenuTrigger::Win),
+            VK_MENU | VK_LMENU | VK_RMENU => Some(MenuTrigger::Alt),
+            _ => None,
+        }
+    }
+
+    fn key_state(&self) -> KeyState {
+        if self.stroke.state & KEY_UP != 0 {
+            KeyState::Up
+        } else {
+            KeyState::Down
+        }
+    }
+
+    fn side(&self) -> Option<Side> {
+        match self.virtual_key_code()? {
+            VK_LWIN | VK_LMENU => Some(Side::Left),
+            VK_RWIN | VK_RMENU => Some(Side::Right),
+            _ => None,
+        }
+    }
+
+    fn virtual_key_code(&self) -> Option<VIRTUAL_KEY> {
+        let vk = unsafe { MapVirtualKeyW(self.extended_scan_code() as u32, MAPVK_VSC_TO_VK_EX) };
+        (vk != 0).then_some(VIRTUAL_KEY(vk as u16))
+    }
+
+    fn scan_code(&self) -> Option<u32> {
+        Some(self.stroke.code as u32)
+    }
+
+    fn elapsed_since(&self, earlier: &Self) -> Option<Duration> {
+        Some(self.timestamp.saturating_duration_since(earlier.timestamp))
+    }
+
+    fn received_at(&self) -> Option<Instant> {
+        Some(self.timestamp)
+    }
+}
+
+/// Captures all keyboard input through the Interception driver on a background thread.
+///
+/// Each stroke is forwarded to the system immediately and then sent through the returned
+/// channel. The returned [`Interception`] handle injects keystrokes, e.g. via
+/// [`InterceptionKeyUp`].
+///
+/// # Errors
+/// Returns `Error::InterceptionUnavailable` if the driver context cannot be created,
+/// typically because the driver is not installed.
+pub fn start_interception() -> Result<(
+    mpsc::Receiver<InterceptionEvent>,
+    Interception,
+    thread::JoinHandle<()>,
+)> {
+    let context = unsafe { interception_create_context() };
+    if context.is_null() {
+        return Err(Error::InterceptionUnavailable);
+    }
+
+    let interception = Interception {
+        context: Arc::new(Context(context)),
+        last_device: Arc::new(AtomicI32::new(0)),
+    };
+    unsafe { interception_set_filter(context, is_keyboard, FILTER_KEY_ALL) };
+
+    let (tx, rx) = mpsc::channel();
+    let source = interception.clone();
+
+    let join_handle = thread::spawn(move || {
+        #[cfg(feature = "log")]
+        log::info!("started interception");
+
+        loop {
+            let device = unsafe { interception_wait(source.context.0) };
+            let mut stroke = KeyStroke::default();
+            if unsafe { interception_receive(source.context.0, device, &mut stroke, 1) } <= 0 {
+                break;
+            }
+
+            unsafe { interception_send(source.context.0, device, &stroke, 1) };
+            source.last_device.store(device, Ordering::Relaxed);
+
+            let mut event = InterceptionEvent {
+                device,
+                stroke,
+                timestamp: Instant::now(),
+            };
+            if crate::privacy::is_enabled()
+                && event
+                    .virtual_key_code()
+                    .is_none_or(|vk| !crate::privacy::is_exposed(vk))
+            {
+                event.stroke.code = 0;
+                event.stroke.state &= !(KEY_E0 | KEY_E1);
+            }
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok((rx, interception, join_handle))
+}
+
+/// Sends a key-up stroke of a scan code through the Interception driver.
+///
+/// The counterpart of [`ScanCodeKeyUp`](crate::strategy::ScanCodeKeyUp) for applications
+/// that ignore `SendInput`. The `dummy_key` chosen by `on_released` is ignored.
+#[derive(Debug, Clone)]
+pub struct InterceptionKeyUp {
+    /// The driver handle returned by [`start_interception`].
+    pub interception: Interception,
+    /// The scan code to release. Defaults to `0x76` (`F24`).
+    pub scan: u16,
+}
+
+impl InterceptionKeyUp {
+    /// Creates the strategy with the default scan code.
+    pub fn new(interception: Interception) -> Self {
+        Self {
+            interception,
+            scan: 0x76,
+        }
+    }
+}
+
+impl<T> SuppressionStrategy<T> for InterceptionKeyUp {
+    fn suppress(&self, _hold: &HoldEvent<T>, _dummy_key: VIRTUAL_KEY) -> io::Result<()> {
+        self.interception.send_keyup_scancode(self.scan)
+    }
+}
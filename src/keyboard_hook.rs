@@ -1,127 +1,1012 @@
 //! Low-level module for starting a global keyboard hook on Windows.
 //!
 //! This module registers a system-wide low-level keyboard hook (`WH_KEYBOARD_LL`)
-//! and sends captured events as [`KeyboardEvent`]s through a channel.
+//! and delivers captured events as [`KeyboardEvent`]s through [`KeyboardEvents`].
+//!
+//! The hook procedure only decides whether to block an event and copies it into a
+//! pre-allocated queue, so that it returns well within the system's hook timeout even under
+//! load. Filtering, such as [`HookOptions::ignore_injected`], happens on the receiving side.
 //!
 //! In most cases, it is recommended to use the higher-level API [`crate::start`].
 //! Use this module directly only if you need custom keyboard event handling
 //! or fine-grained control over the hook behavior.
 //!
 //! # Public API
-//! - [`start_keyboard_hook`] — Starts the global keyboard hook and returns a receiver and thread handle.
-use std::{cell::OnceCell, sync::mpsc, thread};
+//! - [`start_keyboard_hook`] — Starts the global keyboard hook and returns its events and thread handle.
+//! - [`start_keyboard_hook_with`] — Same as above, with [`HookOptions`] applied inside the hook.
+//! - [`start_inline_hook`] — Handles events inside the hook procedure, without a handler thread.
+//! - [`start_keyboard_hook_callback`] — Passes events to a callback inside the hook procedure.
+//! - [`attach_to_current_thread`] — Registers the hook on the caller's own message loop.
+//! - [`hook_reinstall_count`] — How often the watchdog re-registered a removed hook.
+//! - [`is_paused`] — Whether a hook was paused with [`HookCommand::Pause`].
+//! - [`HookThread`] — Handle of the hook thread, used to shut it down or send it a [`HookCommand`].
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
 use windows::{
     Win32::{
-        Foundation::{LPARAM, LRESULT, WPARAM},
-        System::LibraryLoader::GetModuleHandleW,
-        UI::WindowsAndMessaging::{
-            CallNextHookEx, DispatchMessageW, GetMessageW, HC_ACTION, HHOOK, HOOKPROC, MSG,
-            SetWindowsHookExW, TranslateMessage, WH_KEYBOARD_LL,
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+        System::{
+            LibraryLoader::GetModuleHandleW,
+            Power::{RegisterSuspendResumeNotification, UnregisterSuspendResumeNotification},
+            Threading::GetCurrentThreadId,
+        },
+        UI::{
+            Input::KeyboardAndMouse::GetAsyncKeyState,
+            WindowsAndMessaging::{
+                CallNextHookEx, DEVICE_NOTIFY_WINDOW_HANDLE, DefWindowProcW, DestroyWindow,
+                DispatchMessageW, GetMessageW, HC_ACTION, HHOOK, HOOKPROC, MSG,
+                PBT_APMRESUMEAUTOMATIC, PM_REMOVE, PeekMessageW, PostMessageW, PostThreadMessageW,
+                SetTimer, SetWindowsHookExW, TranslateMessage, WH_KEYBOARD_LL, WINDOWS_HOOK_ID,
+                WM_APP, WM_POWERBROADCAST, WM_TIMER,
+            },
         },
     },
-    core::Owned,
+    core::{Owned, w},
 };
 
 use crate::{
     error::{Error, Result},
-    event_handler::KeyboardEvent,
+    event_handler::{
+        Config, Handler,
+        KeyboardAndMouse::{VK_F10, VK_SHIFT, VK_SPACE},
+        KeyboardEvent, MenuTrigger, MenuTriggerEvent, SharedConfig, read_config,
+    },
+    health, keyboard_state,
+    queue::{self, Consumer, PopError, Producer},
+    self_test,
+    sound::{self, Sound},
+    stats,
+    window::create_message_window,
 };
 
 thread_local! {
-    static GLOBAL_SENDER: OnceCell<mpsc::Sender<KeyboardEvent>> = const { OnceCell::new() };
+    static HOOK_STATE: RefCell<Option<HookState>> = const { RefCell::new(None) };
+}
+
+/// The number of events the hook procedure has received.
+static HOOK_EVENTS: AtomicU64 = AtomicU64::new(0);
+static HOOK_REINSTALLS: AtomicU64 = AtomicU64::new(0);
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Returns how often the watchdog re-registered a keyboard hook that Windows had removed
+/// (see [`HookOptions::watchdog`]).
+///
+/// The value only increases; compare two readings to detect a re-registration in between.
+pub fn hook_reinstall_count() -> u64 {
+    HOOK_REINSTALLS.load(Ordering::Relaxed)
+}
+
+/// Returns `true` while a keyboard hook of this process is paused with
+/// [`HookCommand::Pause`].
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+struct HookState {
+    sink: EventSink,
+    options: HookOptions,
+    paused: bool,
+    subscribers: RefCell<Vec<mpsc::SyncSender<KeyboardEvent>>>,
+}
+
+impl HookState {
+    fn set_paused(&mut self, paused: bool) {
+        if self.paused != paused
+            && let Some(sound) = self.options.toggle_sound
+        {
+            sound::play(sound);
+        }
+        self.paused = paused;
+        PAUSED.store(paused, Ordering::Relaxed);
+    }
+}
+
+/// How many events a subscriber (see [`HookThread::subscribe`]) may fall behind before
+/// further events are dropped for it.
+const SUBSCRIBER_CAPACITY: usize = 1024;
+
+/// Where the hook procedure delivers events that are not blocked.
+enum EventSink {
+    /// Queue events for a separate event handler thread.
+    Queue(Producer<KeyboardEvent>),
+    /// Handle events directly inside the hook procedure.
+    Inline(InlineHandler),
+    /// Pass events to a callback, see [`crate::hook_manager`].
+    Callback(Box<dyn Fn(KeyboardEvent) + Send>),
+}
+
+impl EventSink {
+    fn deliver(&self, event: KeyboardEvent) {
+        match self {
+            EventSink::Queue(queue) => health::hook_event(queue.push(event)),
+            EventSink::Inline(inline) => inline.handle(&event),
+            EventSink::Callback(callback) => callback(event),
+        }
+    }
+}
+
+/// The events captured by a keyboard hook started with [`start_keyboard_hook`].
+///
+/// Iterating blocks until the next event arrives, and ends when the hook thread exits.
+/// Events the hook should not deliver, such as this crate's own injections, are skipped
+/// here rather than inside the hook procedure.
+///
+/// There is a single receiver per hook, so `KeyboardEvents` can be moved to another thread
+/// but not shared between threads:
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<prevent_alt_win_menu::keyboard_hook::KeyboardEvents>();
+/// ```
+pub struct KeyboardEvents {
+    queue: Consumer<KeyboardEvent>,
+    ignore_injected: bool,
+}
+
+impl KeyboardEvents {
+    /// Waits for the next event.
+    ///
+    /// Returns `None` once the hook thread has exited and all events have been received.
+    pub fn recv(&self) -> Option<KeyboardEvent> {
+        health::handler_heartbeat();
+        loop {
+            match self.queue.pop_timeout(health::HEARTBEAT_INTERVAL) {
+                Ok(event) => {
+                    health::handler_event(event.timestamp);
+                    if self.accepts(&event) {
+                        return Some(event);
+                    }
+                }
+                Err(PopError::Timeout) => health::handler_heartbeat(),
+                Err(PopError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Returns the next event if one is available, without waiting.
+    pub fn try_recv(&self) -> Option<KeyboardEvent> {
+        loop {
+            let event = self.queue.try_pop()?;
+            health::handler_event(event.timestamp);
+            if self.accepts(&event) {
+                return Some(event);
+            }
+        }
+    }
+
+    /// Returns an iterator over the events available right now, without waiting.
+    ///
+    /// Intended for loops that poll once per iteration, such as a game's frame loop.
+    pub fn try_iter(&self) -> impl Iterator<Item = KeyboardEvent> + '_ {
+        std::iter::from_fn(|| self.try_recv())
+    }
+
+    /// Returns the number of events lost because they were not received fast enough.
+    ///
+    /// The hook never waits for the receiver; once about a thousand events are pending,
+    /// further events are dropped.
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped()
+    }
+
+    /// Returns the underlying channel, so that events can be received in a
+    /// `crossbeam_channel::select!` together with other channels.
+    ///
+    /// Events taken from the channel directly have not been filtered yet; pass each one to
+    /// [`KeyboardEvents::accept`] before handling it.
+    #[cfg(feature = "crossbeam")]
+    pub fn receiver(&self) -> &crossbeam_channel::Receiver<KeyboardEvent> {
+        self.queue.receiver()
+    }
+
+    /// Returns the underlying channel, which can be received from synchronously or awaited.
+    ///
+    /// Events taken from the channel directly have not been filtered yet; pass each one to
+    /// [`KeyboardEvents::accept`] before handling it, or use [`KeyboardEvents::recv_async`].
+    #[cfg(feature = "flume")]
+    pub fn receiver(&self) -> &flume::Receiver<KeyboardEvent> {
+        self.queue.receiver()
+    }
+
+    /// Waits asynchronously for the next event.
+    ///
+    /// Returns `None` once the hook thread has exited and all events have been received.
+    /// Takes `&mut self` so that the future is `Send`, although `KeyboardEvents` is not `Sync`.
+    #[cfg(feature = "flume")]
+    pub async fn recv_async(&mut self) -> Option<KeyboardEvent> {
+        loop {
+            let event = self.queue.receiver().recv_async().await.ok()?;
+            health::handler_event(event.timestamp);
+            if self.accepts(&event) {
+                return Some(event);
+            }
+        }
+    }
+
+    /// Returns `event` if it passes the filters that [`KeyboardEvents::recv`] applies, such as
+    /// [`HookOptions::ignore_injected`].
+    #[cfg(any(feature = "crossbeam", feature = "flume"))]
+    pub fn accept(&self, event: KeyboardEvent) -> Option<KeyboardEvent> {
+        health::handler_event(event.timestamp);
+        self.accepts(&event).then_some(event)
+    }
+
+    fn accepts(&self, event: &KeyboardEvent) -> bool {
+        if event.is_probe() {
+            health::probe_at_handler();
+            return false;
+        }
+        !(event.is_own_injection() || event.is_ignored_injection(self.ignore_injected))
+    }
+}
+
+impl Iterator for KeyboardEvents {
+    type Item = KeyboardEvent;
+
+    fn next(&mut self) -> Option<KeyboardEvent> {
+        self.recv()
+    }
+}
+
+struct InlineHandler {
+    handler: RefCell<Handler>,
+    budget: Duration,
+    exceeded: Cell<bool>,
+    ignore_injected: bool,
+}
+
+impl InlineHandler {
+    fn handle(&self, event: &KeyboardEvent) {
+        if event.is_probe() {
+            health::probe_at_handler();
+            return;
+        }
+        if self.exceeded.get()
+            || event.is_own_injection()
+            || event.is_ignored_injection(self.ignore_injected)
+        {
+            return;
+        }
+        // The hook may be re-entered while the handler injects input.
+        let Ok(mut handler) = self.handler.try_borrow_mut() else {
+            return;
+        };
+
+        let start = Instant::now();
+        handler.handle_keyboard_event(event);
+        let elapsed = start.elapsed();
+        stats::record_latency(event.timestamp);
+
+        if elapsed > self.budget {
+            #[cfg(feature = "log")]
+            log::error!("inline handler took {:?}, disabling it", elapsed);
+            self.exceeded.set(true);
+            handler.report(&Error::InlineBudgetExceeded(elapsed));
+        }
+    }
+}
+
+/// Options applied synchronously inside the hook procedure.
+///
+/// Unlike [`Config`](crate::event_handler::Config) callbacks, which run on the event handler
+/// thread after the event has already been passed on, these options decide whether an event
+/// is blocked (swallowed) before any application sees it. Blocked events are not sent
+/// to [`KeyboardEvents`] either.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookOptions {
+    /// Block the Space key while Alt is held, which prevents the window (system) menu
+    /// opened by `Alt+Space`.
+    pub block_alt_space: bool,
+    /// Block the F10 key while Shift is held, which prevents the context menu
+    /// opened by `Shift+F10`.
+    pub block_shift_f10: bool,
+    /// Do not send injected events (`LLKHF_INJECTED`) to the event handler.
+    ///
+    /// Synthetic input from automation tools then neither triggers nor resets suppression.
+    /// Injected events are still passed on to other applications.
+    pub ignore_injected: bool,
+    /// Triggers whose keys are swallowed entirely, both key-down and key-up.
+    ///
+    /// This is a blocking mode for kiosk machines: the menu can never open and no input is
+    /// injected. Because a key-up can only be swallowed safely if its key-down was swallowed
+    /// too (otherwise the key would stay logically held), combinations such as `Win+E` or
+    /// `Alt+Tab` are lost as well. Triggers are recognized by
+    /// [`MenuTriggerEvent::menu_trigger`]; [`Config::triggers`] is not consulted.
+    ///
+    /// [`Config::triggers`]: crate::event_handler::Config::triggers
+    pub block_triggers: HashSet<MenuTrigger>,
+    /// The time budget for a single event handled inside the hook by [`start_inline_hook`].
+    ///
+    /// Windows silently removes a low-level hook whose procedure exceeds the system's
+    /// `LowLevelHooksTimeout`. To stay well below it, the inline handler is disabled (events
+    /// are passed on unprocessed) after the first event that takes longer than this, and
+    /// `Error::InlineBudgetExceeded` is reported through `on_error`. Defaults to 10 ms.
+    pub inline_budget: Duration,
+    /// The interval at which a watchdog checks that the hook is still installed.
+    ///
+    /// Windows removes a low-level hook without notice once it times out. The watchdog
+    /// compares the keyboard state (`GetAsyncKeyState`) between checks: if keys changed but
+    /// the hook procedure received no events, the hook is re-registered, which is logged and
+    /// reported as `Error::HookReinstalled` through `on_error`. Disabled by default.
+    pub watchdog: Option<Duration>,
+    /// A sound played when the hook is paused or resumed with [`HookCommand::Pause`] and
+    /// [`HookCommand::Resume`]. Disabled by default.
+    pub toggle_sound: Option<Sound>,
+}
+
+impl Default for HookOptions {
+    fn default() -> Self {
+        Self {
+            block_alt_space: false,
+            block_shift_f10: false,
+            ignore_injected: false,
+            block_triggers: HashSet::new(),
+            inline_budget: Duration::from_millis(10),
+            watchdog: None,
+            toggle_sound: None,
+        }
+    }
+}
+
+impl HookOptions {
+    /// Returns `true` if `event` should be blocked.
+    fn should_block(&self, event: &KeyboardEvent) -> bool {
+        if let Some(trigger) = event.menu_trigger()
+            && self.block_triggers.contains(&trigger)
+        {
+            // A key held since before the hook was installed has already been seen as down,
+            // so its release must pass.
+            return event.is_key_down() || !keyboard_state::is_down(event.virtual_key());
+        }
+
+        match event.virtual_key() {
+            VK_SPACE => self.block_alt_space && event.is_alt_down(),
+            VK_F10 => self.block_shift_f10 && keyboard_state::is_down(VK_SHIFT),
+            _ => false,
+        }
+    }
 }
 
 /// Starts a global keyboard hook and spawns a thread to handle incoming events.
 ///
 /// This function registers a low-level Windows keyboard hook that captures all
-/// keyboard input events system-wide and sends them through a channel.
+/// keyboard input events system-wide and queues them for the caller.
 ///
-/// The hook is run on a background thread. The function returns a `Receiver`
+/// The hook is run on a background thread. The function returns [`KeyboardEvents`]
 /// for incoming `KeyboardEvent`s and the `JoinHandle` for the background thread.
 ///
 /// # Returns
 /// - `Ok((rx, handle))`:
-///   - `rx`: A receiver that delivers captured keyboard events.
-///   - `handle`: A join handle for the background thread running the hook loop.
+///   - `rx`: Delivers captured keyboard events.
+///   - `handle`: A [`HookThread`] for the background thread running the hook loop.
 ///
 /// # Errors
 /// - Returns `Error::HookRegistrationFailed` if the keyboard hook fails to register.
 /// - Returns `Error::HookThreadCrashed` if the hook thread terminated unexpectedly.
 ///
 /// # Note
-/// - Unhooking is not currently implemented. The hook will be released automatically when the process exits.
-pub fn start_keyboard_hook() -> Result<(mpsc::Receiver<KeyboardEvent>, thread::JoinHandle<()>)> {
-    let (tx, rx) = mpsc::channel::<KeyboardEvent>();
+/// - The hook stays registered until [`HookThread::shutdown`] is called or the process exits.
+pub fn start_keyboard_hook() -> Result<(KeyboardEvents, HookThread)> {
+    start_keyboard_hook_with(HookOptions::default())
+}
+
+/// Starts a global keyboard hook with the given [`HookOptions`].
+///
+/// See [`start_keyboard_hook`] for details on the return value and errors.
+pub fn start_keyboard_hook_with(options: HookOptions) -> Result<(KeyboardEvents, HookThread)> {
+    let (producer, consumer) = queue::queue();
+    let events = KeyboardEvents {
+        queue: consumer,
+        ignore_injected: options.ignore_injected,
+    };
+
+    let hook_thread = spawn_hook_thread(HookState {
+        sink: EventSink::Queue(producer),
+        options,
+        paused: false,
+        subscribers: Default::default(),
+    })?;
+
+    Ok((events, hook_thread))
+}
+
+/// Handle of a thread running a global keyboard hook, returned by [`start_keyboard_hook`]
+/// and [`start_inline_hook`].
+///
+/// The thread owns a message-only window, which receives power notifications and the
+/// shutdown request of [`HookThread::shutdown`]. Dropping the handle detaches the thread, as
+/// with [`thread::JoinHandle`].
+#[derive(Debug)]
+pub struct HookThread {
+    join_handle: thread::JoinHandle<()>,
+    thread_id: u32,
+    // `HWND` is not `Send`; the handle is only passed to `PostMessageW`.
+    window: Option<isize>,
+}
+
+impl HookThread {
+    /// Returns the Win32 ID of the hook thread, e.g. for `PostThreadMessageW`.
+    pub fn thread_id(&self) -> u32 {
+        self.thread_id
+    }
 
-    let (result_tx, result_rx) = oneshot::channel::<Result<()>>();
+    /// Returns the message-only window of the hook thread, if it could be created.
+    pub fn window(&self) -> Option<HWND> {
+        self.window.map(|hwnd| HWND(hwnd as *mut _))
+    }
+
+    /// Returns `true` if the hook thread has terminated.
+    pub fn is_finished(&self) -> bool {
+        self.join_handle.is_finished()
+    }
+
+    /// Unregisters the hook and waits for the hook thread to terminate.
+    ///
+    /// Afterwards, the [`KeyboardEvents`] of the hook end, which in turn stops an event
+    /// handler started with them.
+    ///
+    /// # Errors
+    /// Returns the panic payload if the hook thread panicked, as [`thread::JoinHandle::join`].
+    pub fn shutdown(self) -> thread::Result<()> {
+        #[cfg(feature = "log")]
+        log::info!("shutting down keyboard hook thread");
+        // Posting fails only if the thread has already terminated.
+        let _ = unsafe {
+            match self.window() {
+                Some(hwnd) => PostMessageW(Some(hwnd), WM_SHUTDOWN, WPARAM(0), LPARAM(0)),
+                None => PostThreadMessageW(self.thread_id, WM_SHUTDOWN, WPARAM(0), LPARAM(0)),
+            }
+        };
+        self.join_handle.join()
+    }
+
+    /// Sends a [`HookCommand`] to the hook thread, which applies it between two events.
+    ///
+    /// # Errors
+    /// Returns `Error::HookCommandFailed` if the command cannot be posted, e.g. because the
+    /// hook thread has terminated.
+    pub fn send(&self, command: HookCommand) -> Result<()> {
+        send_command(self.thread_id, command)
+    }
+
+    /// Returns a new, independent receiver of the events this hook delivers.
+    ///
+    /// Every subscriber gets a copy of each event, alongside the regular consumer such as
+    /// the suppression handler, e.g. to drive an application's own shortcut engine. Events
+    /// are filtered like [`KeyboardEvents`] does, and redacted in privacy mode.
+    ///
+    /// A subscriber that falls more than about a thousand events behind misses events
+    /// until it catches up. Dropping the receiver unsubscribes.
+    ///
+    /// # Errors
+    /// Same as [`HookThread::send`].
+    pub fn subscribe(&self) -> Result<mpsc::Receiver<KeyboardEvent>> {
+        let (tx, rx) = mpsc::sync_channel(SUBSCRIBER_CAPACITY);
+        self.send(HookCommand::Subscribe(tx))?;
+        Ok(rx)
+    }
+
+    /// Waits for the hook thread to terminate, without asking it to.
+    pub fn join(self) -> thread::Result<()> {
+        self.join_handle.join()
+    }
+
+    /// Returns the underlying [`thread::JoinHandle`], giving up [`HookThread::shutdown`].
+    pub fn into_join_handle(self) -> thread::JoinHandle<()> {
+        self.join_handle
+    }
+}
+
+/// A keyboard hook registered on the calling thread by [`attach_to_current_thread`].
+///
+/// The hook is removed when this value is dropped, after which the [`KeyboardEvents`] end.
+pub struct AttachedHook {
+    _hook: Owned<HHOOK>,
+}
+
+impl Drop for AttachedHook {
+    fn drop(&mut self) {
+        HOOK_STATE.with(|s| s.borrow_mut().take());
+    }
+}
+
+/// Registers the global keyboard hook on the calling thread instead of spawning a hook thread.
+///
+/// The hook procedure runs whenever the calling thread retrieves messages, so the thread must
+/// already pump messages, like the GUI thread of a winit or Win32 application, and must not
+/// block for long. Events are delivered as with [`start_keyboard_hook`]; pass them to
+/// [`start_event_handler`](crate::event_handler::start_event_handler). The returned
+/// [`AttachedHook`] must stay on the calling thread and be kept alive as long as the hook
+/// should stay registered.
+///
+/// # Errors
+/// Returns `Error::HookRegistrationFailed` if the hook fails to register, or if a keyboard
+/// hook of this module is already registered on the calling thread.
+pub fn attach_to_current_thread() -> Result<(KeyboardEvents, AttachedHook)> {
+    attach_to_current_thread_with(HookOptions::default())
+}
+
+/// Registers the global keyboard hook on the calling thread with the given [`HookOptions`].
+///
+/// See [`attach_to_current_thread`]. [`HookOptions::watchdog`] is ignored, since the message
+/// loop belongs to the caller.
+pub fn attach_to_current_thread_with(
+    options: HookOptions,
+) -> Result<(KeyboardEvents, AttachedHook)> {
+    let already_attached = HOOK_STATE.with(|s| s.borrow().is_some());
+    if already_attached {
+        return Err(Error::HookRegistrationFailed(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "a keyboard hook is already registered on this thread",
+        )));
+    }
+
+    let (producer, consumer) = queue::queue();
+    let events = KeyboardEvents {
+        queue: consumer,
+        ignore_injected: options.ignore_injected,
+    };
+    HOOK_STATE.with(|s| {
+        *s.borrow_mut() = Some(HookState {
+            sink: EventSink::Queue(producer),
+            options,
+            paused: false,
+            subscribers: Default::default(),
+        })
+    });
+
+    match unsafe { register_hook(WH_KEYBOARD_LL, Some(low_level_keyboard_proc)) } {
+        Ok(hook) => {
+            #[cfg(feature = "log")]
+            log::info!("registered keyboard hook on the current thread");
+            Ok((events, AttachedHook { _hook: hook }))
+        }
+        Err(e) => {
+            HOOK_STATE.with(|s| s.borrow_mut().take());
+            Err(Error::HookRegistrationFailed(e))
+        }
+    }
+}
+
+/// Starts a global keyboard hook that handles events directly inside the hook procedure.
+///
+/// There is no queue and no event handler thread: `config` is evaluated synchronously for
+/// every event, which minimizes latency. In exchange, all callbacks must be fast, since the
+/// whole system's keyboard input waits for them. See [`HookOptions::inline_budget`] for how
+/// slow callbacks are handled.
+///
+/// # Errors
+/// Same as [`start_keyboard_hook`].
+pub fn start_inline_hook(config: Config) -> Result<HookThread> {
+    start_inline_hook_shared(Arc::new(RwLock::new(config)))
+}
+
+/// Like [`start_inline_hook`], but reads the configuration from a [`SharedConfig`].
+pub(crate) fn start_inline_hook_shared(config: SharedConfig) -> Result<HookThread> {
+    let options = read_config(&config).hook.clone();
+    let budget = options.inline_budget;
+    let ignore_injected = options.ignore_injected;
+
+    let hook_thread = spawn_hook_thread(HookState {
+        sink: EventSink::Inline(InlineHandler {
+            handler: RefCell::new(Handler::with_shared(config.clone())),
+            budget,
+            exceeded: Cell::new(false),
+            ignore_injected,
+        }),
+        options,
+        paused: false,
+        subscribers: Default::default(),
+    })?;
+    if let Some(observer) = &read_config(&config).observer {
+        observer.on_hook_registered(hook_thread.thread_id());
+    }
+    Ok(hook_thread)
+}
+
+/// Starts a global keyboard hook that passes every event directly to `callback`.
+///
+/// `callback` runs on the hook thread, inside the hook procedure: no queue, no event handler
+/// thread. The whole system's keyboard input waits until it returns, and Windows removes
+/// hooks that take longer than its hook timeout (about 300 ms by default, less on recent
+/// versions). Keep it short and non-blocking, e.g. update state or forward the event.
+///
+/// Probes and this crate's own injections are filtered out, as by [`KeyboardEvents`].
+///
+/// # Errors
+/// Same as [`start_keyboard_hook`].
+pub fn start_keyboard_hook_callback(
+    options: HookOptions,
+    callback: impl Fn(KeyboardEvent) + Send + 'static,
+) -> Result<HookThread> {
+    let ignore_injected = options.ignore_injected;
+    let callback = Box::new(move |event: KeyboardEvent| {
+        if event.is_probe() {
+            health::probe_at_handler();
+            return;
+        }
+        if event.is_own_injection() || event.is_ignored_injection(ignore_injected) {
+            return;
+        }
+        callback(event);
+    });
+    start_callback_hook(options, callback, || Ok(()))
+}
+
+/// Posts `command` to the hook thread with the Win32 ID `thread_id`, see [`HookThread::send`].
+pub(crate) fn send_command(thread_id: u32, command: HookCommand) -> Result<()> {
+    let command = Box::into_raw(Box::new(command));
+    let posted = unsafe {
+        PostThreadMessageW(
+            thread_id,
+            WM_HOOK_COMMAND,
+            WPARAM(0),
+            LPARAM(command as isize),
+        )
+    };
+    posted.map_err(|e| {
+        // The message was not queued, so the command is still owned here.
+        drop(unsafe { Box::from_raw(command) });
+        Error::HookCommandFailed(e.into())
+    })
+}
+
+/// A command for a running hook thread, sent with [`HookThread::send`].
+#[derive(Debug, Clone)]
+pub enum HookCommand {
+    /// Passes all events on without blocking or delivering them. The hook stays registered.
+    Pause,
+    /// Undoes [`HookCommand::Pause`].
+    Resume,
+    /// Registers the hook again, moving it to the front of the hook chain.
+    Reinstall,
+    /// Replaces the options applied inside the hook procedure.
+    ///
+    /// [`HookOptions::ignore_injected`], [`HookOptions::inline_budget`] and
+    /// [`HookOptions::watchdog`] keep the values given at start.
+    SetOptions(HookOptions),
+    /// Sends a copy of every delivered event to the channel, see [`HookThread::subscribe`].
+    ///
+    /// The hook never waits: events that do not fit into a full channel are dropped for it.
+    /// Use a bounded channel, since sending on an unbounded one allocates inside the hook
+    /// procedure.
+    Subscribe(mpsc::SyncSender<KeyboardEvent>),
+}
+
+/// Starts a hook thread whose keyboard events are passed to `callback`, and which also hosts
+/// the hooks registered by `install`. They stay registered as long as the value returned by
+/// `install` lives, which is until the thread shuts down.
+pub(crate) fn start_callback_hook<G: 'static>(
+    options: HookOptions,
+    callback: Box<dyn Fn(KeyboardEvent) + Send>,
+    install: impl FnOnce() -> Result<G> + Send + 'static,
+) -> Result<HookThread> {
+    spawn_hook_thread_with(
+        HookState {
+            sink: EventSink::Callback(callback),
+            options,
+            paused: false,
+            subscribers: Default::default(),
+        },
+        install,
+    )
+}
+
+fn spawn_hook_thread(state: HookState) -> Result<HookThread> {
+    spawn_hook_thread_with(state, || Ok(()))
+}
+
+fn spawn_hook_thread_with<G: 'static>(
+    state: HookState,
+    install: impl FnOnce() -> Result<G> + Send + 'static,
+) -> Result<HookThread> {
+    let (result_tx, result_rx) = oneshot::channel::<Result<(u32, Option<isize>)>>();
 
     let join_handle = thread::spawn(move || {
-        GLOBAL_SENDER.with(|g| g.set(tx)).unwrap();
+        let watchdog_interval = state.options.watchdog;
+        HOOK_STATE.with(|s| *s.borrow_mut() = Some(state));
 
-        let hook_result = unsafe { register_keyboard_hook(Some(low_level_keyboard_proc)) };
+        #[cfg(feature = "tracing")]
+        let registration = tracing::info_span!("register_keyboard_hook").entered();
+        let hook_result = unsafe { register_hook(WH_KEYBOARD_LL, Some(low_level_keyboard_proc)) };
 
-        let _hook_handle = match hook_result {
+        let mut hook_handle = match hook_result {
             Err(e) => {
                 #[cfg(feature = "log")]
                 log::error!("Failed to register keyboard hook: {}", e);
+                #[cfg(feature = "tracing")]
+                tracing::error!(error = %e, "failed to register the keyboard hook");
                 let _ = result_tx.send(Err(Error::HookRegistrationFailed(e)));
                 return;
             }
-            Ok(handle) => {
-                let _ = result_tx.send(Ok(()));
-                handle
+            Ok(handle) => handle,
+        };
+        let other_hooks = match install() {
+            Ok(hooks) => hooks,
+            Err(e) => {
+                let _ = result_tx.send(Err(e));
+                return;
             }
         };
 
+        // The window receives power notifications and shutdown requests.
+        let window = create_message_window(w!("PreventAltWinMenuHook"), Some(hook_window_proc));
+        let thread_id = unsafe { GetCurrentThreadId() };
+        let _ = result_tx.send(Ok((thread_id, window.map(|hwnd| hwnd.0 as isize))));
+        #[cfg(feature = "etw")]
+        crate::etw::hook_registered(thread_id);
+        #[cfg(feature = "tracing")]
+        {
+            tracing::info!(thread_id, "registered keyboard hook");
+            drop(registration);
+        }
+
         #[cfg(feature = "log")]
         log::info!("registered keybord hook");
 
+        health::hook_heartbeat();
+        let heartbeat_timer =
+            unsafe { SetTimer(None, 0, health::HEARTBEAT_INTERVAL.as_millis() as u32, None) };
+        // Hooks are known to stop working after sleep, so they are re-registered on resume.
+        // The registration lives as long as the window.
+        let power_notification = window.and_then(|hwnd| unsafe {
+            RegisterSuspendResumeNotification(hwnd.into(), DEVICE_NOTIFY_WINDOW_HANDLE).ok()
+        });
+
+        let mut watchdog = watchdog_interval.map(|interval| {
+            let timer = unsafe { SetTimer(None, 0, interval.as_millis() as u32, None) };
+            (timer, Watchdog::new())
+        });
+
         let mut msg = MSG::default();
         unsafe {
             while GetMessageW(&mut msg, None, 0, 0).into() {
+                if msg.message == WM_TIMER {
+                    if msg.wParam.0 == heartbeat_timer {
+                        health::hook_heartbeat();
+                    } else if let Some((timer, watchdog)) = &mut watchdog
+                        && msg.wParam.0 == *timer
+                        && watchdog.hook_is_dead()
+                    {
+                        #[cfg(feature = "log")]
+                        log::warn!("keyboard hook stopped receiving events, registering it again");
+                        if reinstall_hook(&mut hook_handle) {
+                            HOOK_REINSTALLS.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    continue;
+                }
+                if msg.message == WM_RESUMED {
+                    #[cfg(feature = "log")]
+                    log::info!("resumed from sleep, registering keyboard hook again");
+                    reinstall_hook(&mut hook_handle);
+                    continue;
+                }
+                if msg.message == WM_SHUTDOWN {
+                    break;
+                }
+                if msg.message == WM_HOOK_COMMAND {
+                    let command = Box::from_raw(msg.lParam.0 as *mut HookCommand);
+                    apply_command(*command, &mut hook_handle);
+                    continue;
+                }
                 let _ = TranslateMessage(&msg);
                 DispatchMessageW(&msg);
             }
+            // Commands still queued are never applied, but their boxes must be freed.
+            while PeekMessageW(&mut msg, None, WM_HOOK_COMMAND, WM_HOOK_COMMAND, PM_REMOVE).into() {
+                drop(Box::from_raw(msg.lParam.0 as *mut HookCommand));
+            }
+        }
+
+        if let Some(notification) = power_notification {
+            unsafe {
+                let _ = UnregisterSuspendResumeNotification(notification);
+            }
+        }
+        // Unhook before the state goes away, then close the queue so the consumer ends.
+        drop(other_hooks);
+        drop(hook_handle);
+        #[cfg(feature = "etw")]
+        crate::etw::hook_unregistered(thread_id);
+        if let Some(hwnd) = window {
+            unsafe {
+                let _ = DestroyWindow(hwnd);
+            }
         }
+        HOOK_STATE.with(|s| s.borrow_mut().take());
+
+        #[cfg(feature = "log")]
+        log::info!("unregistered keyboard hook");
     });
 
     match result_rx.recv() {
-        Ok(Ok(_)) => Ok((rx, join_handle)),
+        Ok(Ok((thread_id, window))) => Ok(HookThread {
+            join_handle,
+            thread_id,
+            window,
+        }),
         Ok(Err(e)) => Err(e),
         Err(_) => Err(Error::HookThreadCrashed),
     }
 }
 
+fn apply_command(command: HookCommand, hook_handle: &mut Owned<HHOOK>) {
+    #[cfg(feature = "log")]
+    log::info!("keyboard hook command: {:?}", command);
+
+    if let HookCommand::Reinstall = command {
+        reinstall_hook(hook_handle);
+        return;
+    }
+    HOOK_STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let Some(state) = state.as_mut() else {
+            return;
+        };
+        match command {
+            HookCommand::Pause => state.set_paused(true),
+            HookCommand::Resume => state.set_paused(false),
+            HookCommand::SetOptions(options) => {
+                state.options = HookOptions {
+                    ignore_injected: state.options.ignore_injected,
+                    inline_budget: state.options.inline_budget,
+                    watchdog: state.options.watchdog,
+                    ..options
+                }
+            }
+            HookCommand::Subscribe(tx) => state.subscribers.get_mut().push(tx),
+            HookCommand::Reinstall => {}
+        }
+    });
+}
+
+/// Cross-checks the keyboard state against the events received by the hook.
+struct Watchdog {
+    events: u64,
+    /// A hash of the keyboard state, which is all that is kept of it.
+    keys: u64,
+}
+
+impl Watchdog {
+    fn new() -> Self {
+        Self {
+            events: HOOK_EVENTS.load(Ordering::Relaxed),
+            keys: Self::keys(),
+        }
+    }
+
+    /// Returns `true` if keys were pressed or released since the last check, but the hook
+    /// procedure was not called.
+    fn hook_is_dead(&mut self) -> bool {
+        let events = HOOK_EVENTS.load(Ordering::Relaxed);
+        let keys = Self::keys();
+        let dead = events == self.events && keys != self.keys;
+
+        self.events = events;
+        self.keys = keys;
+        dead
+    }
+
+    fn keys() -> u64 {
+        let mut hasher = DefaultHasher::new();
+        // Skip the mouse buttons (0x01-0x06), which never reach a keyboard hook.
+        for vk in 0x08..256 {
+            (unsafe { GetAsyncKeyState(vk) } < 0).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// Replaces `hook_handle` with a newly registered hook. Returns `false` on failure, in which
+/// case the old hook is kept.
+fn reinstall_hook(hook_handle: &mut Owned<HHOOK>) -> bool {
+    let result = unsafe { register_hook(WH_KEYBOARD_LL, Some(low_level_keyboard_proc)) };
+    #[cfg(feature = "etw")]
+    crate::etw::hook_reinstalled(result.is_ok());
+    match result {
+        Ok(handle) => {
+            *hook_handle = handle;
+            true
+        }
+        Err(_e) => {
+            #[cfg(feature = "log")]
+            log::error!("Failed to register keyboard hook again: {}", _e);
+            false
+        }
+    }
+}
+
+/// Posted to the hook thread when the system resumed from sleep or hibernation.
+const WM_RESUMED: u32 = WM_APP + 1;
+/// Posted to the hook thread by [`HookThread::shutdown`].
+const WM_SHUTDOWN: u32 = WM_APP + 2;
+/// Posted to the hook thread by [`HookThread::send`], with a boxed [`HookCommand`] in `lParam`.
+const WM_HOOK_COMMAND: u32 = WM_APP + 3;
+
+unsafe extern "system" fn hook_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    if msg == WM_POWERBROADCAST && w_param.0 as u32 == PBT_APMRESUMEAUTOMATIC {
+        unsafe {
+            let _ = PostMessageW(Some(hwnd), WM_RESUMED, WPARAM(0), LPARAM(0));
+        }
+        return LRESULT(1);
+    }
+    unsafe { DefWindowProcW(hwnd, msg, w_param, l_param) }
+}
+
 unsafe extern "system" fn low_level_keyboard_proc(
     n_code: i32,
     w_param: WPARAM,
     l_param: LPARAM,
 ) -> LRESULT {
     if n_code == HC_ACTION as i32 {
-        let event = unsafe { KeyboardEvent::from_params(l_param, w_param) };
+        HOOK_EVENTS.fetch_add(1, Ordering::Relaxed);
+        let mut event = unsafe { KeyboardEvent::from_params(l_param, w_param) };
 
-        GLOBAL_SENDER.with(|s| {
-            let sender = s.get().unwrap();
-            if let Err(_e) = sender.send(event) {
-                #[cfg(feature = "log")]
-                log::error!("{}", _e);
+        // Keep this path minimal: no allocation, locking or logging.
+        let blocked = HOOK_STATE.with(|s| {
+            let state = s.borrow();
+            let Some(state) = state.as_ref() else {
+                return false;
+            };
+            if state.paused {
+                return false;
             }
-        })
+            // Recognized before blocking, which must not swallow this crate's own checks.
+            if event.is_probe() {
+                health::probe_at_hook();
+            } else if event.is_self_test() {
+                self_test::at_hook();
+            } else if state.options.should_block(&event) {
+                return true;
+            }
+            event.redact_if_private();
+
+            if !event.is_probe()
+                && !event.is_own_injection()
+                && !event.is_ignored_injection(state.options.ignore_injected)
+                && let Ok(mut subscribers) = state.subscribers.try_borrow_mut()
+            {
+                // The channels are pre-allocated, so sending does not allocate.
+                subscribers.retain(|tx| {
+                    !matches!(tx.try_send(event), Err(mpsc::TrySendError::Disconnected(_)))
+                });
+            }
+            state.sink.deliver(event);
+            // Probes are not passed on to applications.
+            event.is_probe()
+        });
+
+        if blocked {
+            return LRESULT(1);
+        }
     }
 
     unsafe { CallNextHookEx(None, n_code, w_param, l_param) }
 }
 
-unsafe fn register_keyboard_hook(f: HOOKPROC) -> std::io::Result<Owned<HHOOK>> {
-    let keyboard_hook = unsafe {
-        SetWindowsHookExW(
-            WH_KEYBOARD_LL,
-            f,
-            Some(GetModuleHandleW(None).unwrap().into()),
-            0,
-        )
-    }?;
+pub(crate) unsafe fn register_hook(
+    id: WINDOWS_HOOK_ID,
+    f: HOOKPROC,
+) -> std::io::Result<Owned<HHOOK>> {
+    let hook =
+        unsafe { SetWindowsHookExW(id, f, Some(GetModuleHandleW(None).unwrap().into()), 0) }?;
 
-    Ok(unsafe { Owned::new(keyboard_hook) })
+    Ok(unsafe { Owned::new(hook) })
 }
@@ -0,0 +1,100 @@
+//! Typed queries of the current keyboard state.
+//!
+//! These wrap `GetAsyncKeyState` and `GetKeyboardState` so that callbacks and host applications
+//! can ask "is Shift held?" without declaring the Windows calls themselves.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::keyboard_state;
+//! use prevent_alt_win_menu::event_handler::KeyboardAndMouse::VK_CAPITAL;
+//!
+//! if keyboard_state::modifiers().ctrl {
+//!     println!("Ctrl is held");
+//! }
+//! let caps_lock = keyboard_state::KeyboardState::current().is_some_and(|s| s.is_toggled(VK_CAPITAL));
+//! ```
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, GetKeyboardState, VIRTUAL_KEY, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN,
+    VK_SHIFT,
+};
+
+/// Returns `true` if `vk` is physically held right now.
+///
+/// Uses `GetAsyncKeyState`, which reflects the system-wide state. Inside a low-level hook,
+/// it reflects all events before the current one.
+pub fn is_down(vk: VIRTUAL_KEY) -> bool {
+    unsafe { GetAsyncKeyState(vk.0 as i32) as u16 & 0x8000 != 0 }
+}
+
+/// The modifier keys held at a point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    /// Either Shift key is held.
+    pub shift: bool,
+    /// Either Ctrl key is held.
+    pub ctrl: bool,
+    /// Either Alt key is held.
+    pub alt: bool,
+    /// Either Windows key is held.
+    pub win: bool,
+}
+
+impl Modifiers {
+    /// Returns `true` if no modifier is held.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Returns the modifier keys currently held, based on [`is_down`].
+pub fn modifiers() -> Modifiers {
+    Modifiers {
+        shift: is_down(VK_SHIFT),
+        ctrl: is_down(VK_CONTROL),
+        alt: is_down(VK_MENU),
+        win: is_down(VK_LWIN) || is_down(VK_RWIN),
+    }
+}
+
+/// A snapshot of all 256 virtual keys, as returned by `GetKeyboardState`.
+///
+/// Unlike [`is_down`], this is the state of the calling thread's message queue, which only
+/// advances as the thread processes keyboard messages. It also carries toggle states such as
+/// Caps Lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyboardState([u8; 256]);
+
+impl KeyboardState {
+    /// Captures the keyboard state of the calling thread.
+    ///
+    /// Returns `None` if `GetKeyboardState` fails.
+    pub fn current() -> Option<Self> {
+        let mut keys = [0u8; 256];
+        unsafe { GetKeyboardState(&mut keys) }.ok()?;
+        Some(Self(keys))
+    }
+
+    /// Returns `true` if `vk` was held when the snapshot was taken.
+    pub fn is_down(&self, vk: VIRTUAL_KEY) -> bool {
+        self.get(vk) & 0x80 != 0
+    }
+
+    /// Returns `true` if `vk` was toggled on (e.g. Caps Lock) when the snapshot was taken.
+    pub fn is_toggled(&self, vk: VIRTUAL_KEY) -> bool {
+        self.get(vk) & 0x01 != 0
+    }
+
+    /// Returns the modifier keys held when the snapshot was taken.
+    pub fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            shift: self.is_down(VK_SHIFT),
+            ctrl: self.is_down(VK_CONTROL),
+            alt: self.is_down(VK_MENU),
+            win: self.is_down(VK_LWIN) || self.is_down(VK_RWIN),
+        }
+    }
+
+    fn get(&self, vk: VIRTUAL_KEY) -> u8 {
+        self.0.get(vk.0 as usize).copied().unwrap_or(0)
+    }
+}
@@ -1,13 +1,80 @@
 #![doc = include_str!("../README.md")]
 
+#[cfg(all(feature = "crossbeam", feature = "flume"))]
+compile_error!("the `crossbeam` and `flume` features are mutually exclusive");
+
+pub mod activation;
+pub mod adapters;
+#[cfg(feature = "tokio")]
+pub mod async_hook;
+pub mod audit;
+pub mod backend;
+pub mod chord;
+#[cfg(feature = "debug-ui")]
+pub mod debug_ui;
+#[cfg(feature = "detect")]
+pub mod detect;
+pub mod diagnostics;
+pub mod elevation;
 pub mod error;
+#[cfg(feature = "etw")]
+pub mod etw;
 pub mod event_handler;
+#[cfg(feature = "event-log")]
+pub mod event_log;
+pub mod fallback;
+pub mod foreground;
+pub mod health;
+pub mod holds;
+pub mod hook_manager;
+pub mod input;
+#[cfg(feature = "interception")]
+pub mod interception;
 pub mod keyboard_hook;
+pub mod keyboard_state;
+pub mod middleware;
+pub mod mouse_hook;
+pub mod observer;
+#[cfg(feature = "overlay")]
+pub mod overlay;
+pub mod privacy;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+pub mod pump;
+mod queue;
+pub mod record;
+#[cfg(feature = "json")]
+pub mod recording;
+#[cfg(feature = "json")]
+pub mod replay;
+pub mod rules;
+pub mod schedule;
+pub mod self_test;
+pub mod session;
+pub mod sound;
+#[cfg(feature = "serde")]
+pub mod spec;
+pub mod stats;
+pub mod strategy;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod subclass;
+pub mod tee;
+pub mod thread_hook;
+#[cfg(feature = "toast")]
+pub mod toast;
+#[cfg(feature = "watch")]
+pub mod watch;
+pub mod win_event;
+mod window;
 
-use std::thread;
+use std::{
+    sync::{Arc, RwLock},
+    thread,
+};
 
 use error::Result;
-use event_handler::Config;
+use event_handler::{Config, RemoteSessionMode, SharedConfig, read_config};
 
 /// Starts keyboard hook and event handler threads to suppress the Alt or Windows menu.
 ///
@@ -22,18 +89,168 @@ use event_handler::Config;
 ///
 /// Returns an error if the keyboard hook cannot be registered or the hook thread fails to initialize.
 pub fn start(config: Config) -> Result<JoinHandles> {
-    let (rx, hook_handle) = keyboard_hook::start_keyboard_hook()?;
-    let handler_handle = event_handler::start_event_handler(rx, config);
+    start_shared(Arc::new(RwLock::new(config)))
+}
+
+fn start_shared(config: SharedConfig) -> Result<JoinHandles> {
+    let current = read_config(&config);
+    let capabilities = check_capabilities();
+
+    let foreground_watcher = current
+        .reset_on_foreground_change
+        .then(foreground::start_foreground_watcher)
+        .transpose()?;
+    let mouse_hook = current
+        .track_mouse_clicks
+        .then(mouse_hook::start_mouse_hook)
+        .transpose()?;
+    let session_watcher = (current.pause_on_lock
+        || current.remote_session != RemoteSessionMode::Suppress)
+        .then(session::start_session_watcher)
+        .transpose()?;
+    let menu_watcher = (current.auto_close.is_some() || current.verify_window.is_some())
+        .then(|| fallback::start_menu_watcher(config.clone()))
+        .transpose()?;
+    let hook_options = current.hook.clone();
+    drop(current);
+
+    let (rx, hook_handle) = keyboard_hook::start_keyboard_hook_with(hook_options)?;
+    if let Some(observer) = &read_config(&config).observer {
+        observer.on_hook_registered(hook_handle.thread_id());
+    }
+    let mut handler = event_handler::Handler::with_shared(config.clone());
+    let holds = handler.subscribe_holds();
+    let handler_handle = event_handler::spawn_hook_handler(rx, handler);
 
     Ok(JoinHandles {
         keyboard_hook: hook_handle,
         event_handler: handler_handle,
+        holds,
+        foreground_watcher,
+        mouse_hook,
+        session_watcher,
+        menu_watcher,
+        capabilities,
+        config,
     })
 }
 
+/// Detects the [`Capabilities`](elevation::Capabilities) of the process and logs a warning
+/// if suppression will not cover elevated windows.
+///
+/// This is the normal case for unelevated applications, so it is not reported as an error.
+fn check_capabilities() -> elevation::Capabilities {
+    let capabilities = elevation::Capabilities::current();
+    #[cfg(feature = "log")]
+    if !capabilities.covers_elevated_windows() {
+        log::warn!("not elevated and no uiAccess, menus of elevated windows are not suppressed");
+    }
+    capabilities
+}
+
+/// Starts the event handler with events from the given [`backend::HookBackend`].
+///
+/// This is the generic form of [`start`], which uses [`backend::LowLevelHook`]. Options
+/// that need their own threads, such as [`Config::set_reset_on_foreground_change`], are
+/// not handled here.
+///
+/// Returns the backend's handle together with the handle of the event handler thread.
+///
+/// # Errors
+///
+/// Returns an error if the backend fails to start.
+pub fn start_with_backend<B: backend::HookBackend>(
+    backend: B,
+    config: Config<B::Event>,
+) -> Result<(B::Handle, thread::JoinHandle<()>)> {
+    let (events, handle) = backend.start()?;
+    let handler_handle = event_handler::start_event_handler(events, config);
+    Ok((handle, handler_handle))
+}
+
+/// Starts suppression with the event handling done inside the keyboard hook.
+///
+/// Unlike [`start`], no event handler thread is spawned and events are not passed through a
+/// queue; the decision is made synchronously in the hook procedure, which minimizes latency.
+/// Use this only if all callbacks in `config` are guaranteed to be fast: the handler is
+/// disabled once a single event exceeds [`Config::set_inline_budget`].
+///
+/// Returns the handle of the keyboard hook thread. Auxiliary threads, such as the foreground
+/// watcher, are started as in [`start`] but detached.
+///
+/// # Errors
+///
+/// Returns an error if the keyboard hook cannot be registered or the hook thread fails to initialize.
+pub fn start_inline(config: Config) -> Result<keyboard_hook::HookThread> {
+    let config = Arc::new(RwLock::new(config));
+    start_detached_watchers(&config)?;
+    keyboard_hook::start_inline_hook_shared(config)
+}
+
+/// Starts a keyboard hook that passes every event directly to `callback`, with no queue
+/// and no event handler thread.
+///
+/// This is the minimal-footprint integration: a single thread, and no suppression logic of
+/// this crate. `callback` runs inside the hook procedure, so it must return quickly; see
+/// [`keyboard_hook::start_keyboard_hook_callback`] for the constraints and for passing
+/// [`keyboard_hook::HookOptions`].
+///
+/// # Errors
+///
+/// Returns an error if the keyboard hook cannot be registered or the hook thread fails to initialize.
+pub fn start_with_callback<F: Fn(event_handler::KeyboardEvent) + Send + 'static>(
+    callback: F,
+) -> Result<keyboard_hook::HookThread> {
+    keyboard_hook::start_keyboard_hook_callback(Default::default(), callback)
+}
+
+/// Checks the capabilities and starts the auxiliary threads `config` needs, detached.
+fn start_detached_watchers(config: &SharedConfig) -> Result<()> {
+    check_capabilities();
+    let current = read_config(config);
+    if current.reset_on_foreground_change {
+        foreground::start_foreground_watcher()?;
+    }
+    if current.track_mouse_clicks {
+        mouse_hook::start_mouse_hook()?;
+    }
+    if current.pause_on_lock || current.remote_session != RemoteSessionMode::Suppress {
+        session::start_session_watcher()?;
+    }
+    if current.auto_close.is_some() || current.verify_window.is_some() {
+        fallback::start_menu_watcher(config.clone())?;
+    }
+    Ok(())
+}
+
+/// Starts suppression with a configuration file that is reloaded whenever it changes.
+///
+/// The file is parsed with [`Config::from_path`]. Afterwards, a watcher thread applies
+/// changes to the running handler; parse errors are reported through the active
+/// configuration's `on_error` callback and the previous configuration is kept.
+///
+/// Returns the usual [`JoinHandles`] together with the handle of the watcher thread.
+///
+/// # Errors
+///
+/// Returns an error if the initial configuration cannot be loaded, the file cannot be watched,
+/// or the keyboard hook cannot be registered.
+#[cfg(feature = "watch")]
+pub fn start_with_config_file(
+    path: impl AsRef<std::path::Path>,
+) -> Result<(JoinHandles, thread::JoinHandle<()>)> {
+    let path = path.as_ref();
+    let config = Arc::new(RwLock::new(Config::from_path(path)?));
+    let handles = start_shared(config.clone())?;
+    // Watching the hook thread lets changed hook options be applied, too.
+    let watcher_handle = watch::watch(path, config, Some(handles.keyboard_hook.thread_id()))?;
+
+    Ok((handles, watcher_handle))
+}
+
 /// Pair of thread handles for the keyboard hook and event handler.
 ///
-/// These are standard [`std::thread::JoinHandle`]s representing background threads
+/// These are [`std::thread::JoinHandle`]s representing background threads
 /// that suppress the system menu triggered by Alt or Windows key releases.
 ///
 /// In typical usage, you do not need to hold on to this struct:
@@ -42,9 +259,110 @@ pub fn start(config: Config) -> Result<JoinHandles> {
 /// However, if you want to explicitly wait for their termination or check for errors,
 /// you can keep and `join()` them as needed.
 pub struct JoinHandles {
-    /// Thread that runs the Windows low-level keyboard hook.
-    pub keyboard_hook: thread::JoinHandle<()>,
+    /// Thread that runs the Windows low-level keyboard hook. Shutting it down also ends the
+    /// event handler thread.
+    pub keyboard_hook: keyboard_hook::HookThread,
 
     /// Thread that processes keyboard events and performs suppression.
     pub event_handler: thread::JoinHandle<()>,
+
+    /// The holds completed by the event handler, see [`JoinHandles::next_hold`].
+    pub holds: holds::HoldReceiver,
+
+    /// Thread that observes foreground window changes, if
+    /// [`Config::set_reset_on_foreground_change`] is enabled.
+    pub foreground_watcher: Option<thread::JoinHandle<()>>,
+
+    /// Thread that runs the low-level mouse hook, if [`Config::set_track_mouse_clicks`]
+    /// is enabled.
+    pub mouse_hook: Option<thread::JoinHandle<()>>,
+
+    /// Thread that observes workstation locks, the secure desktop and Remote Desktop
+    /// connections, if [`Config::set_pause_on_lock`] or [`Config::set_remote_session_mode`]
+    /// is enabled.
+    pub session_watcher: Option<thread::JoinHandle<()>>,
+
+    /// Thread that watches for menus opened despite suppression, if
+    /// [`Config::set_auto_close`] or [`Config::set_verification`] is enabled.
+    pub menu_watcher: Option<thread::JoinHandle<()>>,
+
+    /// What the process can inject input into, detected at start.
+    pub capabilities: elevation::Capabilities,
+
+    config: SharedConfig,
+}
+
+impl JoinHandles {
+    /// Returns the heartbeats of the keyboard hook and event handler threads.
+    ///
+    /// See [`health::health`].
+    pub fn health(&self) -> health::Health {
+        health::health()
+    }
+
+    /// Verifies that the keyboard hook still delivers events to the handler.
+    ///
+    /// See [`health::is_active`].
+    pub fn is_active(&self, timeout: std::time::Duration) -> health::Liveness {
+        health::is_active(timeout)
+    }
+
+    /// Returns how long events take from the keyboard hook until the handler is done.
+    ///
+    /// See [`stats::latency`].
+    pub fn latency(&self) -> stats::LatencyStats {
+        stats::latency()
+    }
+
+    /// Returns the counters of suppressions, pass-throughs, failures and handled events.
+    ///
+    /// Requires [`Config::set_collect_stats`]; see [`stats::counters`].
+    pub fn stats(&self) -> stats::Stats {
+        stats::counters()
+    }
+
+    /// Resets the counters returned by [`JoinHandles::stats`].
+    pub fn reset_stats(&self) {
+        stats::reset_counters()
+    }
+
+    /// Returns the most recent hold the event handler decided to suppress.
+    ///
+    /// See [`event_handler::last_suppression`].
+    pub fn last_suppression(&self) -> Option<event_handler::LastSuppression> {
+        event_handler::last_suppression()
+    }
+
+    /// Injects a marked `Win` tap and reports whether it was suppressed, waiting up to
+    /// `timeout` for each stage.
+    ///
+    /// See [`self_test`].
+    ///
+    /// # Errors
+    /// Returns `Error::InjectionFailed` if the tap cannot be injected, e.g. because input
+    /// is blocked on the secure desktop.
+    pub fn self_test(&self, timeout: std::time::Duration) -> Result<self_test::SelfTestReport> {
+        self_test::run(timeout)
+    }
+
+    /// Returns a snapshot of the internal state to attach to bug reports.
+    ///
+    /// See [`diagnostics::StateDump`]. Scanning for conflicting tools takes a few
+    /// milliseconds.
+    pub fn diagnostics(&self) -> diagnostics::StateDump {
+        diagnostics::StateDump::capture(
+            &read_config(&self.config),
+            self.capabilities,
+            !self.keyboard_hook.is_finished(),
+            !self.event_handler.is_finished(),
+        )
+    }
+
+    /// Waits for the next hold of a trigger key completed by the event handler, after the
+    /// suppression decision (see [`event_handler::HoldEvent::was_suppressed`]).
+    ///
+    /// Returns `None` once the event handler has exited. See [`holds`].
+    pub async fn next_hold(&self) -> Option<event_handler::HoldEvent> {
+        self.holds.next_hold().await
+    }
 }
@@ -0,0 +1,85 @@
+//! A chain of stages every event passes through before suppression.
+//!
+//! Each [`EventMiddleware`] receives the event and a [`Next`] handle to the rest of the
+//! chain. It can inspect or log the event and pass it on, drop it by not calling
+//! [`Next::run`], replace it with a remapped event, or pass on several. The last stage is
+//! always the suppression logic itself.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{
+//!     event_handler::{Config, KeyboardEvent},
+//!     middleware::Next,
+//! };
+//!
+//! let config = Config::default()
+//!     // Log every event.
+//!     .add_middleware(|event: KeyboardEvent, mut next: Next<'_>| {
+//!         println!("{:?} {:?}", event.kbd.vkCode, event.wm_key_state);
+//!         next.run(event);
+//!     })
+//!     // Let injected events bypass suppression.
+//!     .add_middleware(|event: KeyboardEvent, mut next: Next<'_>| {
+//!         if !event.is_injected() {
+//!             next.run(event);
+//!         }
+//!     });
+//! prevent_alt_win_menu::start(config)?;
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! Middleware run synchronously for every key event, so they delay suppression by the time
+//! they take.
+
+use std::sync::{Mutex, PoisonError};
+
+use crate::event_handler::{Config, Handler, KeyboardEvent};
+
+/// A stage of the event processing chain, added with
+/// [`Config::add_middleware`](crate::event_handler::Config::add_middleware).
+///
+/// Implemented for closures taking the event and [`Next`].
+pub trait EventMiddleware<T = KeyboardEvent>: Send {
+    /// Processes `event`, passing it (or other events) on with `next`.
+    fn handle(&mut self, event: T, next: Next<'_, T>);
+}
+
+impl<T, F: FnMut(T, Next<'_, T>) + Send> EventMiddleware<T> for F {
+    fn handle(&mut self, event: T, next: Next<'_, T>) {
+        self(event, next)
+    }
+}
+
+/// The rest of the middleware chain after the current stage.
+pub struct Next<'a, T = KeyboardEvent> {
+    chain: &'a [Mutex<Box<dyn EventMiddleware<T>>>],
+    config: &'a Config<T>,
+    handler: &'a mut Handler<T>,
+}
+
+impl<'a, T: crate::event_handler::MenuTriggerEvent + Clone> Next<'a, T> {
+    pub(crate) fn new(config: &'a Config<T>, handler: &'a mut Handler<T>) -> Self {
+        Self {
+            chain: &config.middleware,
+            config,
+            handler,
+        }
+    }
+
+    /// Passes `event` to the next stage. May be called several times to emit several events.
+    pub fn run(&mut self, event: T) {
+        match self.chain.split_first() {
+            Some((middleware, rest)) => {
+                let next = Next {
+                    chain: rest,
+                    config: self.config,
+                    handler: &mut *self.handler,
+                };
+                middleware
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .handle(event, next);
+            }
+            None => self.handler.suppress_stage(&event, self.config),
+        }
+    }
+}
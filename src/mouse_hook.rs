@@ -0,0 +1,258 @@
+//! Low-level mouse hook used to notice mouse clicks during a hold.
+//!
+//! Clicking while Alt or Win is held (e.g. `Alt+click` to move a window in some window managers)
+//! does not open the menu, just like pressing another key does not. The hook registered here
+//! (`WH_MOUSE_LL`) counts button presses, and the event handler compares the count at press and
+//! release time to mark such holds as interrupted (see [`Config::set_track_mouse_clicks`]).
+//!
+//! [`start_mouse_event_hook`] additionally delivers every mouse event as a [`MouseEvent`]
+//! through a channel, mirroring [`crate::keyboard_hook`], for applications that coordinate
+//! menu suppression with mouse gestures.
+//!
+//! Mouse events are never blocked.
+//!
+//! [`Config::set_track_mouse_clicks`]: crate::event_handler::Config::set_track_mouse_clicks
+
+use std::{
+    cell::OnceCell,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Instant,
+};
+
+use windows::{
+    Win32::{
+        Foundation::{LPARAM, LRESULT, WPARAM},
+        UI::WindowsAndMessaging::{
+            CallNextHookEx, DispatchMessageW, GetMessageW, HC_ACTION, HHOOK, LLMHF_INJECTED, MSG,
+            MSLLHOOKSTRUCT, TranslateMessage, WH_MOUSE_LL, WM_LBUTTONDOWN, WM_LBUTTONUP,
+            WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL,
+            WM_RBUTTONDOWN, WM_RBUTTONUP, WM_XBUTTONDOWN, WM_XBUTTONUP,
+        },
+    },
+    core::Owned,
+};
+
+use crate::{
+    error::{Error, Result},
+    keyboard_hook::register_hook,
+};
+
+/// Receives the events of a mouse hook on its thread.
+pub(crate) type MouseSink = Box<dyn Fn(MouseEvent) + Send>;
+
+thread_local! {
+    static MOUSE_SINK: OnceCell<Option<MouseSink>> = const { OnceCell::new() };
+}
+
+static MOUSE_CLICKS: AtomicU64 = AtomicU64::new(0);
+
+/// The kind of a [`MouseEvent`], taken from the window message passed to the hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseMessage {
+    /// `WM_MOUSEMOVE`
+    Move,
+    /// `WM_LBUTTONDOWN`
+    LeftDown,
+    /// `WM_LBUTTONUP`
+    LeftUp,
+    /// `WM_RBUTTONDOWN`
+    RightDown,
+    /// `WM_RBUTTONUP`
+    RightUp,
+    /// `WM_MBUTTONDOWN`
+    MiddleDown,
+    /// `WM_MBUTTONUP`
+    MiddleUp,
+    /// `WM_XBUTTONDOWN`; see [`MouseEvent::x_button`].
+    XDown,
+    /// `WM_XBUTTONUP`; see [`MouseEvent::x_button`].
+    XUp,
+    /// `WM_MOUSEWHEEL`; see [`MouseEvent::wheel_delta`].
+    Wheel,
+    /// `WM_MOUSEHWHEEL`; see [`MouseEvent::wheel_delta`].
+    HWheel,
+}
+
+impl MouseMessage {
+    fn from_w_param(w_param: WPARAM) -> Option<Self> {
+        Some(match w_param.0 as u32 {
+            WM_MOUSEMOVE => Self::Move,
+            WM_LBUTTONDOWN => Self::LeftDown,
+            WM_LBUTTONUP => Self::LeftUp,
+            WM_RBUTTONDOWN => Self::RightDown,
+            WM_RBUTTONUP => Self::RightUp,
+            WM_MBUTTONDOWN => Self::MiddleDown,
+            WM_MBUTTONUP => Self::MiddleUp,
+            WM_XBUTTONDOWN => Self::XDown,
+            WM_XBUTTONUP => Self::XUp,
+            WM_MOUSEWHEEL => Self::Wheel,
+            WM_MOUSEHWHEEL => Self::HWheel,
+            _ => return None,
+        })
+    }
+
+    /// Returns `true` for button presses, which count as clicks.
+    pub fn is_button_down(&self) -> bool {
+        matches!(
+            self,
+            Self::LeftDown | Self::RightDown | Self::MiddleDown | Self::XDown
+        )
+    }
+}
+
+/// A single mouse event received via the low-level mouse hook.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MouseEvent {
+    /// The raw Windows mouse event structure.
+    pub msll: MSLLHOOKSTRUCT,
+    /// The kind of event.
+    pub message: MouseMessage,
+    /// When the hook procedure received the event.
+    pub timestamp: Instant,
+}
+
+impl MouseEvent {
+    /// Returns the cursor position in per-monitor-aware screen coordinates.
+    pub fn position(&self) -> (i32, i32) {
+        (self.msll.pt.x, self.msll.pt.y)
+    }
+
+    /// Returns the wheel rotation for [`MouseMessage::Wheel`] and [`MouseMessage::HWheel`],
+    /// in multiples of `WHEEL_DELTA` (120).
+    pub fn wheel_delta(&self) -> Option<i16> {
+        matches!(self.message, MouseMessage::Wheel | MouseMessage::HWheel)
+            .then_some((self.msll.mouseData >> 16) as i16)
+    }
+
+    /// Returns which X button (`1` or `2`) was pressed or released.
+    pub fn x_button(&self) -> Option<u16> {
+        matches!(self.message, MouseMessage::XDown | MouseMessage::XUp)
+            .then_some((self.msll.mouseData >> 16) as u16)
+    }
+
+    /// Returns `true` if the event was injected (`LLMHF_INJECTED`).
+    pub fn is_injected(&self) -> bool {
+        self.msll.flags & LLMHF_INJECTED != 0
+    }
+}
+
+/// Returns the number of mouse button presses observed by the hook started with
+/// [`start_mouse_hook`].
+///
+/// The value only increases; compare two readings to detect a click in between.
+pub fn click_count() -> u64 {
+    MOUSE_CLICKS.load(Ordering::Relaxed)
+}
+
+/// Starts a global low-level mouse hook on a background thread.
+///
+/// Each button press (left, right, middle or X button) increments [`click_count`].
+///
+/// # Errors
+/// - Returns `Error::HookRegistrationFailed` if the mouse hook fails to register.
+/// - Returns `Error::HookThreadCrashed` if the hook thread terminated unexpectedly.
+pub fn start_mouse_hook() -> Result<thread::JoinHandle<()>> {
+    spawn_hook_thread(None)
+}
+
+/// Starts a global low-level mouse hook that sends every mouse event through a channel.
+///
+/// Like [`start_mouse_hook`], button presses also increment [`click_count`].
+///
+/// # Errors
+/// Same as [`start_mouse_hook`].
+pub fn start_mouse_event_hook() -> Result<(mpsc::Receiver<MouseEvent>, thread::JoinHandle<()>)> {
+    let (tx, rx) = mpsc::channel();
+    let join_handle = spawn_hook_thread(Some(Box::new(move |event| {
+        if let Err(_e) = tx.send(event) {
+            #[cfg(feature = "log")]
+            log::error!("{}", _e);
+        }
+    })))?;
+    Ok((rx, join_handle))
+}
+
+/// Registers the mouse hook on the calling thread, which must pump messages, passing events
+/// to `sink` if given. Only one sink can be set per thread.
+pub(crate) fn install_mouse_hook(sink: Option<MouseSink>) -> Result<Owned<HHOOK>> {
+    if MOUSE_SINK.with(|s| s.set(sink)).is_err() {
+        return Err(Error::HookRegistrationFailed(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "a mouse hook is already registered on this thread",
+        )));
+    }
+
+    match unsafe { register_hook(WH_MOUSE_LL, Some(low_level_mouse_proc)) } {
+        Ok(handle) => {
+            #[cfg(feature = "log")]
+            log::info!("registered mouse hook");
+            Ok(handle)
+        }
+        Err(e) => {
+            #[cfg(feature = "log")]
+            log::error!("Failed to register mouse hook: {}", e);
+            Err(Error::HookRegistrationFailed(e))
+        }
+    }
+}
+
+fn spawn_hook_thread(sink: Option<MouseSink>) -> Result<thread::JoinHandle<()>> {
+    let (result_tx, result_rx) = oneshot::channel::<Result<()>>();
+
+    let join_handle = thread::spawn(move || {
+        let _hook_handle = match install_mouse_hook(sink) {
+            Err(e) => {
+                let _ = result_tx.send(Err(e));
+                return;
+            }
+            Ok(handle) => {
+                let _ = result_tx.send(Ok(()));
+                handle
+            }
+        };
+
+        let mut msg = MSG::default();
+        unsafe {
+            while GetMessageW(&mut msg, None, 0, 0).into() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+    });
+
+    match result_rx.recv() {
+        Ok(Ok(())) => Ok(join_handle),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(Error::HookThreadCrashed),
+    }
+}
+
+unsafe extern "system" fn low_level_mouse_proc(
+    n_code: i32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    if n_code == HC_ACTION as i32
+        && let Some(message) = MouseMessage::from_w_param(w_param)
+    {
+        if message.is_button_down() {
+            MOUSE_CLICKS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        MOUSE_SINK.with(|s| {
+            if let Some(Some(sink)) = s.get() {
+                sink(MouseEvent {
+                    msll: unsafe { *(l_param.0 as *const MSLLHOOKSTRUCT) },
+                    message,
+                    timestamp: Instant::now(),
+                });
+            }
+        });
+    }
+
+    unsafe { CallNextHookEx(None, n_code, w_param, l_param) }
+}
@@ -0,0 +1,77 @@
+//! Notifications about the lifecycle and decisions of suppression.
+//!
+//! An [`Observer`] set with [`Config::set_observer`] receives structured events, so that an
+//! application can route them to its own telemetry without enabling the `log` feature:
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{
+//!     event_handler::{Config, HoldEvent},
+//!     observer::{Observer, Outcome},
+//! };
+//!
+//! struct Telemetry;
+//!
+//! impl Observer for Telemetry {
+//!     fn on_suppressed(&self, hold: &HoldEvent, outcome: Outcome) {
+//!         println!("{} released: {:?}", hold.trigger, outcome);
+//!     }
+//! }
+//!
+//! prevent_alt_win_menu::start(Config::default().set_observer(Telemetry))?;
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! All methods have empty default implementations. Except for
+//! [`Observer::on_hook_registered`], they are called on the event handler thread (or inside
+//! the hook with [`crate::start_inline`]), so they should return quickly. Errors are still
+//! reported through [`Config::set_on_error`].
+//!
+//! [`Config::set_observer`]: crate::event_handler::Config::set_observer
+//! [`Config::set_on_error`]: crate::event_handler::Config::set_on_error
+
+use crate::{
+    event_handler::{HoldEvent, KeyboardEvent},
+    record::SuppressionRecord,
+};
+
+/// What the event handler did with a completed hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Outcome {
+    /// The menu was suppressed.
+    Suppressed,
+    /// The release was passed through, e.g. because the hold was interrupted, outside the
+    /// schedule or excluded by a rule.
+    PassedThrough,
+    /// Suppression was skipped because of
+    /// [`Config::set_injection_rate_limit`](crate::event_handler::Config::set_injection_rate_limit).
+    RateLimited,
+    /// Injecting the suppression input failed.
+    Failed,
+}
+
+/// Receives lifecycle and suppression events of the event handler.
+pub trait Observer<T = KeyboardEvent>: Send + Sync {
+    /// The keyboard hook was registered on the thread `thread_id`.
+    ///
+    /// Called by [`crate::start`] and [`crate::start_inline`] on the starting thread.
+    fn on_hook_registered(&self, _thread_id: u32) {}
+
+    /// The watchdog registered the keyboard hook again after Windows removed it.
+    ///
+    /// Noticed with the first event after the re-registration.
+    fn on_hook_reinstalled(&self) {}
+
+    /// A hold of a trigger key was completed and decided.
+    fn on_suppressed(&self, _hold: &HoldEvent<T>, _outcome: Outcome) {}
+
+    /// The record of a completed hold, right after [`Observer::on_suppressed`].
+    ///
+    /// See [`crate::record`].
+    fn on_record(&self, _record: &SuppressionRecord) {}
+
+    /// `count` events were dropped because the queue to the handler was full.
+    ///
+    /// Noticed with the next event the handler receives.
+    fn on_dropped_events(&self, _count: u64) {}
+}
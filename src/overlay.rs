@@ -0,0 +1,253 @@
+//! An on-screen indicator of suppression (`overlay` feature).
+//!
+//! An [`Overlay`] is a small square in a corner of the primary monitor that stays on top of
+//! other windows and lets clicks pass through. It is green while suppression is active, gray
+//! while it is paused (see [`keyboard_hook::is_paused`] and [`session::is_paused`]) and
+//! flashes red whenever a menu is suppressed, e.g. for streamers demonstrating that the
+//! `Win` key is blocked.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::overlay::{Corner, Overlay};
+//!
+//! let _handles = prevent_alt_win_menu::start(Default::default())?;
+//! let overlay = Overlay::start(Corner::BottomRight)?;
+//! // ...
+//! overlay.set_visible(false);
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+
+use std::{cell::Cell, thread, time::Duration};
+
+use windows::{
+    Win32::{
+        Foundation::{COLORREF, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Graphics::Gdi::{
+            BeginPaint, CreateSolidBrush, DeleteObject, EndPaint, FillRect, InvalidateRect,
+            PAINTSTRUCT,
+        },
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetClientRect,
+            GetMessageW, GetSystemMetrics, LWA_ALPHA, MSG, PostMessageW, PostQuitMessage,
+            RegisterClassW, SM_CXSCREEN, SM_CYSCREEN, SW_HIDE, SW_SHOWNOACTIVATE,
+            SetLayeredWindowAttributes, SetTimer, ShowWindow, WM_APP, WM_CLOSE, WM_DESTROY,
+            WM_PAINT, WM_TIMER, WNDCLASSW, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW,
+            WS_EX_TOPMOST, WS_EX_TRANSPARENT, WS_POPUP,
+        },
+    },
+    core::w,
+};
+
+use crate::{
+    error::{Error, Result},
+    event_handler::last_suppression,
+    keyboard_hook,
+    observer::Outcome,
+    session,
+};
+
+/// The edge length of the indicator, in pixels.
+const SIZE: i32 = 16;
+/// The distance of the indicator from the screen edges, in pixels.
+const MARGIN: i32 = 12;
+/// How often the state is checked.
+const POLL_INTERVAL_MS: u32 = 50;
+/// How long the indicator stays red after a suppression.
+const FLASH_DURATION: Duration = Duration::from_millis(400);
+
+// Colors as `0x00BBGGRR`.
+const ACTIVE: COLORREF = COLORREF(0x0050_AF4C);
+const PAUSED: COLORREF = COLORREF(0x009E_9E9E);
+const FLASH: COLORREF = COLORREF(0x0036_43F4);
+
+/// Shows or hides the overlay, with `WPARAM(1)` or `WPARAM(0)`.
+const WM_SET_VISIBLE: u32 = WM_APP + 1;
+
+thread_local! {
+    static COLOR: Cell<COLORREF> = const { Cell::new(ACTIVE) };
+}
+
+/// The corner of the primary monitor the [`Overlay`] is shown in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Corner {
+    /// The top left corner.
+    TopLeft,
+    /// The top right corner. This is the default.
+    #[default]
+    TopRight,
+    /// The bottom left corner.
+    BottomLeft,
+    /// The bottom right corner.
+    BottomRight,
+}
+
+/// A running overlay indicator. See the [module docs](self).
+#[derive(Debug)]
+pub struct Overlay {
+    join_handle: thread::JoinHandle<()>,
+    // `HWND` is not `Send`; the handle is only passed to `PostMessageW`.
+    window: isize,
+}
+
+impl Overlay {
+    /// Shows the indicator in `corner` on a new thread.
+    ///
+    /// # Errors
+    /// - Returns `Error::OverlayFailed` if the window cannot be created.
+    /// - Returns `Error::HookThreadCrashed` if the thread terminated unexpectedly.
+    pub fn start(corner: Corner) -> Result<Self> {
+        let (result_tx, result_rx) = oneshot::channel::<Result<isize>>();
+
+        let join_handle = thread::spawn(move || {
+            let hwnd = match create_window(corner) {
+                Ok(hwnd) => hwnd,
+                Err(e) => {
+                    let _ = result_tx.send(Err(Error::OverlayFailed(e.into())));
+                    return;
+                }
+            };
+            unsafe { SetTimer(Some(hwnd), 1, POLL_INTERVAL_MS, None) };
+            let _ = result_tx.send(Ok(hwnd.0 as isize));
+
+            let mut msg = MSG::default();
+            unsafe {
+                while GetMessageW(&mut msg, None, 0, 0).into() {
+                    DispatchMessageW(&msg);
+                }
+            }
+        });
+
+        match result_rx.recv() {
+            Ok(Ok(window)) => Ok(Self {
+                join_handle,
+                window,
+            }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(Error::HookThreadCrashed),
+        }
+    }
+
+    /// Shows or hides the indicator. It keeps tracking the state while hidden.
+    pub fn set_visible(&self, visible: bool) {
+        // Posting fails only if the thread has already terminated.
+        let _ = unsafe {
+            PostMessageW(
+                Some(self.hwnd()),
+                WM_SET_VISIBLE,
+                WPARAM(visible as usize),
+                LPARAM(0),
+            )
+        };
+    }
+
+    /// Closes the indicator and waits for its thread to terminate.
+    ///
+    /// # Errors
+    /// Returns the panic payload if the thread panicked, as [`thread::JoinHandle::join`].
+    pub fn close(self) -> thread::Result<()> {
+        let _ = unsafe { PostMessageW(Some(self.hwnd()), WM_CLOSE, WPARAM(0), LPARAM(0)) };
+        self.join_handle.join()
+    }
+
+    fn hwnd(&self) -> HWND {
+        HWND(self.window as *mut _)
+    }
+}
+
+fn create_window(corner: Corner) -> windows::core::Result<HWND> {
+    let instance = unsafe { GetModuleHandleW(None) }?;
+    let class_name = w!("PreventAltWinMenuOverlay");
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(window_proc),
+        hInstance: instance.into(),
+        lpszClassName: class_name,
+        ..Default::default()
+    };
+    // Registering fails harmlessly if an earlier overlay already registered the class.
+    unsafe { RegisterClassW(&class) };
+
+    let (width, height) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    let x = match corner {
+        Corner::TopLeft | Corner::BottomLeft => MARGIN,
+        Corner::TopRight | Corner::BottomRight => width - MARGIN - SIZE,
+    };
+    let y = match corner {
+        Corner::TopLeft | Corner::TopRight => MARGIN,
+        Corner::BottomLeft | Corner::BottomRight => height - MARGIN - SIZE,
+    };
+
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_LAYERED | WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_TRANSPARENT | WS_EX_NOACTIVATE,
+            class_name,
+            None,
+            WS_POPUP,
+            x,
+            y,
+            SIZE,
+            SIZE,
+            None,
+            None,
+            Some(instance.into()),
+            None,
+        )
+    }?;
+    unsafe {
+        SetLayeredWindowAttributes(hwnd, COLORREF(0), 200, LWA_ALPHA)?;
+        let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    }
+    Ok(hwnd)
+}
+
+/// Returns the color for the current state.
+fn current_color() -> COLORREF {
+    let flashing = last_suppression()
+        .is_some_and(|last| last.action == Outcome::Suppressed && last.elapsed() < FLASH_DURATION);
+    if flashing {
+        FLASH
+    } else if keyboard_hook::is_paused() || session::is_paused() {
+        PAUSED
+    } else {
+        ACTIVE
+    }
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: u32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_TIMER => {
+            let color = current_color();
+            if COLOR.replace(color) != color {
+                let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+            }
+        }
+        WM_PAINT => unsafe {
+            let mut paint = PAINTSTRUCT::default();
+            let hdc = BeginPaint(hwnd, &mut paint);
+            let mut rect = RECT::default();
+            let _ = GetClientRect(hwnd, &mut rect);
+            let brush = CreateSolidBrush(COLOR.get());
+            FillRect(hdc, &rect, brush);
+            let _ = DeleteObject(brush.into());
+            let _ = EndPaint(hwnd, &paint);
+        },
+        WM_SET_VISIBLE => {
+            let show = if w_param.0 != 0 {
+                SW_SHOWNOACTIVATE
+            } else {
+                SW_HIDE
+            };
+            let _ = unsafe { ShowWindow(hwnd, show) };
+        }
+        WM_CLOSE => {
+            let _ = unsafe { DestroyWindow(hwnd) };
+        }
+        WM_DESTROY => unsafe { PostQuitMessage(0) },
+        _ => return unsafe { DefWindowProcW(hwnd, msg, w_param, l_param) },
+    }
+    LRESULT(0)
+}
@@ -0,0 +1,70 @@
+//! Privacy mode, which keeps keystrokes other than modifiers out of the crate.
+//!
+//! A global keyboard hook sees every key the user types. Suppression only needs to know
+//! which modifiers are pressed and *that* another key was pressed in between, not which one.
+//! While privacy mode is enabled, the hooks of this crate ([`crate::keyboard_hook`],
+//! [`crate::thread_hook`], [`crate::hook_manager`] and, with the `interception` feature, the
+//! Interception backend) redact every other key right after receiving it: the key code and
+//! scan code are cleared before the event is queued, passed to a callback, stored in a
+//! [`HoldEvent`](crate::event_handler::HoldEvent) or logged. Decisions that must be made on
+//! the real key inside the hook, such as [`HookOptions::block_alt_space`], still work.
+//!
+//! Privacy mode is enabled by default with the `privacy` feature, and can be switched at
+//! runtime with [`set_enabled`].
+//!
+//! Keys that are passed through unchanged are Shift, Ctrl, Alt and Win (see [`is_exposed`]),
+//! and `F23`, which Windows sends as part of the Copilot key. Custom
+//! [`Config::set_triggers`](crate::event_handler::Config::set_triggers) on other keys do not
+//! work in privacy mode.
+//!
+//! [`HookOptions::block_alt_space`]: crate::keyboard_hook::HookOptions::block_alt_space
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    VIRTUAL_KEY, VK_CONTROL, VK_F23, VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_MENU,
+    VK_RCONTROL, VK_RMENU, VK_RSHIFT, VK_RWIN, VK_SHIFT,
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(cfg!(feature = "privacy"));
+
+/// Returns `true` while privacy mode is enabled.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Enables or disables privacy mode for all hooks of this process.
+///
+/// Events received before the change are not affected.
+pub fn set_enabled(enabled: bool) {
+    #[cfg(feature = "log")]
+    log::info!(
+        "privacy mode {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns `true` if `vk` is passed through in privacy mode.
+pub fn is_exposed(vk: VIRTUAL_KEY) -> bool {
+    matches!(
+        vk,
+        VK_SHIFT
+            | VK_LSHIFT
+            | VK_RSHIFT
+            | VK_CONTROL
+            | VK_LCONTROL
+            | VK_RCONTROL
+            | VK_MENU
+            | VK_LMENU
+            | VK_RMENU
+            | VK_LWIN
+            | VK_RWIN
+            | VK_F23
+    )
+}
+
+/// Returns `true` if an event with key `vk` must be redacted right now.
+pub(crate) fn should_redact(vk: VIRTUAL_KEY) -> bool {
+    is_enabled() && !is_exposed(vk)
+}
@@ -0,0 +1,231 @@
+//! A minimal Prometheus exporter for the statistics of this process (`prometheus` feature).
+//!
+//! [`start_exporter`] serves [`render`] at `http://127.0.0.1:<port>/metrics`, for scraping
+//! long-running kiosk or server deployments:
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{event_handler::Config, prometheus};
+//!
+//! prevent_alt_win_menu::start(Config::default().set_collect_stats(true))?;
+//! prometheus::start_exporter(9464)?;
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! The exporter only listens on the loopback interface. Counters stay zero unless
+//! [`Config::set_collect_stats`](crate::event_handler::Config::set_collect_stats) is
+//! enabled.
+
+use std::{
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    thread,
+};
+
+use crate::{
+    error::{Error, Result},
+    event_handler::MenuTrigger,
+    health, stats,
+};
+
+/// Serves the metrics on `127.0.0.1:port` from a new thread.
+///
+/// Pass port `0` to let the system choose one. Returns the bound address and the handle of
+/// the server thread, which runs until the process exits.
+///
+/// # Errors
+///
+/// Returns `Error::ExporterBind` if the port cannot be bound.
+pub fn start_exporter(port: u16) -> Result<(SocketAddr, thread::JoinHandle<()>)> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).map_err(Error::ExporterBind)?;
+    let addr = listener.local_addr().map_err(Error::ExporterBind)?;
+
+    #[cfg(feature = "log")]
+    log::info!("serving Prometheus metrics on http://{}/metrics", addr);
+
+    let handle = thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            if let Err(_e) = serve(stream) {
+                #[cfg(feature = "log")]
+                log::warn!("failed to serve metrics: {}", _e);
+            }
+        }
+    });
+    Ok((addr, handle))
+}
+
+fn serve(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", render())
+    } else {
+        ("404 Not Found", String::new())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Renders the statistics of this process in the Prometheus text format.
+pub fn render() -> String {
+    let mut out = String::new();
+    let counters = stats::counters();
+
+    metric(&mut out, "paw_events_total", "counter", "Events handled.");
+    sample(&mut out, "paw_events_total", "", counters.events);
+
+    metric(
+        &mut out,
+        "paw_suppressed_total",
+        "counter",
+        "Menus suppressed.",
+    );
+    for trigger in [MenuTrigger::Win, MenuTrigger::Alt] {
+        let labels = format!("trigger=\"{}\"", label(trigger));
+        sample(
+            &mut out,
+            "paw_suppressed_total",
+            &labels,
+            counters.suppressions_of(trigger),
+        );
+    }
+
+    metric(
+        &mut out,
+        "paw_passed_through_total",
+        "counter",
+        "Releases not suppressed.",
+    );
+    sample(
+        &mut out,
+        "paw_passed_through_total",
+        "",
+        counters.pass_throughs,
+    );
+
+    metric(
+        &mut out,
+        "paw_injection_errors_total",
+        "counter",
+        "Failed injections.",
+    );
+    sample(
+        &mut out,
+        "paw_injection_errors_total",
+        "",
+        counters.injection_failures,
+    );
+
+    metric(
+        &mut out,
+        "paw_rate_limited_total",
+        "counter",
+        "Suppressions skipped by the rate limit.",
+    );
+    sample(
+        &mut out,
+        "paw_rate_limited_total",
+        "",
+        counters.rate_limited,
+    );
+
+    let holds = stats::hold_durations();
+    metric(
+        &mut out,
+        "paw_hold_duration_seconds",
+        "histogram",
+        "Uninterrupted hold durations.",
+    );
+    for trigger in [MenuTrigger::Win, MenuTrigger::Alt] {
+        let mut cumulative = 0;
+        for (bound, count) in holds.buckets(trigger) {
+            cumulative += count;
+            let le = bound.map_or("+Inf".to_owned(), |b| b.as_secs_f64().to_string());
+            let labels = format!("trigger=\"{}\",le=\"{le}\"", label(trigger));
+            sample(
+                &mut out,
+                "paw_hold_duration_seconds_bucket",
+                &labels,
+                cumulative,
+            );
+        }
+        let labels = format!("trigger=\"{}\"", label(trigger));
+        sample(
+            &mut out,
+            "paw_hold_duration_seconds_count",
+            &labels,
+            cumulative,
+        );
+    }
+
+    let latency = stats::latency();
+    metric(
+        &mut out,
+        "paw_latency_seconds",
+        "summary",
+        "Time from the hook to the handler.",
+    );
+    for (quantile, value) in [
+        ("0.5", latency.p50),
+        ("0.9", latency.p90),
+        ("0.99", latency.p99),
+    ] {
+        let labels = format!("quantile=\"{quantile}\"");
+        sample(
+            &mut out,
+            "paw_latency_seconds",
+            &labels,
+            value.as_secs_f64(),
+        );
+    }
+    sample(&mut out, "paw_latency_seconds_count", "", latency.count);
+
+    let health = health::health();
+    metric(
+        &mut out,
+        "paw_queue_depth",
+        "gauge",
+        "Events waiting for the handler.",
+    );
+    sample(&mut out, "paw_queue_depth", "", health.queue_depth);
+    metric(
+        &mut out,
+        "paw_dropped_events_total",
+        "counter",
+        "Events dropped by a full queue.",
+    );
+    sample(
+        &mut out,
+        "paw_dropped_events_total",
+        "",
+        health.dropped_events,
+    );
+
+    out
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{name} {value}");
+    } else {
+        let _ = writeln!(out, "{name}{{{labels}}} {value}");
+    }
+}
+
+fn label(trigger: MenuTrigger) -> &'static str {
+    match trigger {
+        MenuTrigger::Win => "win",
+        MenuTrigger::Alt => "alt",
+    }
+}
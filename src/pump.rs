@@ -0,0 +1,108 @@
+//! Suppression driven by the caller's own loop instead of a handler thread.
+//!
+//! Game engines and other applications with a frame loop often want all work to happen on
+//! their main thread. An [`EventPump`] owns the keyboard hook and a [`Handler`]; calling
+//! [`EventPump::poll`] once per frame handles every event received since the last call.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{event_handler::Config, pump::EventPump};
+//!
+//! let mut pump = EventPump::start(Config::default())?;
+//! loop {
+//!     pump.poll();
+//!     // update and render the frame ...
+//! #   break;
+//! }
+//! pump.shutdown().ok();
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! To inspect or filter events before they are handled, take them with
+//! [`EventPump::try_iter`] and pass them to [`EventPump::handler`] yourself.
+//!
+//! The hook itself still runs on its own thread, since it must answer Windows without
+//! waiting for the next frame. Events queue up in between; if a frame takes longer than the
+//! time between a key release and the menu opening, suppression comes too late. Poll at
+//! least every few milliseconds, or use [`crate::start`] instead.
+
+use std::{
+    sync::{Arc, RwLock},
+    thread,
+};
+
+use crate::{
+    error::Result,
+    event_handler::{Config, Handler, KeyboardEvent},
+    keyboard_hook::{self, HookThread, KeyboardEvents},
+};
+
+/// A keyboard hook whose events are handled when the owner polls.
+pub struct EventPump {
+    events: KeyboardEvents,
+    handler: Handler,
+    hook: HookThread,
+}
+
+impl EventPump {
+    /// Installs the keyboard hook and prepares a [`Handler`] for `config`.
+    ///
+    /// Auxiliary threads, such as the foreground watcher, are started as in
+    /// [`crate::start_inline`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the keyboard hook cannot be registered or an auxiliary thread
+    /// fails to start.
+    pub fn start(config: Config) -> Result<Self> {
+        let hook_options = config.hook.clone();
+        let config = Arc::new(RwLock::new(config));
+        crate::start_detached_watchers(&config)?;
+        let (events, hook) = keyboard_hook::start_keyboard_hook_with(hook_options)?;
+        Ok(Self {
+            events,
+            handler: Handler::with_shared(config),
+            hook,
+        })
+    }
+
+    /// Handles all events received since the last call, without waiting.
+    ///
+    /// Returns the number of events handled.
+    pub fn poll(&mut self) -> usize {
+        let mut handled = 0;
+        let mut next = self.events.try_recv();
+        while let Some(event) = next {
+            next = self.events.try_recv();
+            self.handler.step_queued(&event, next.is_some());
+            handled += 1;
+        }
+        handled
+    }
+
+    /// Returns an iterator over the events available right now, without handling them.
+    ///
+    /// Pass every event to [`Handler::step`] of [`EventPump::handler`] in order, otherwise
+    /// holds are not tracked correctly.
+    pub fn try_iter(&self) -> impl Iterator<Item = KeyboardEvent> + '_ {
+        self.events.try_iter()
+    }
+
+    /// Returns the handler that [`EventPump::poll`] feeds.
+    pub fn handler(&mut self) -> &mut Handler {
+        &mut self.handler
+    }
+
+    /// Returns the handle of the keyboard hook thread.
+    pub fn hook_thread(&self) -> &HookThread {
+        &self.hook
+    }
+
+    /// Removes the keyboard hook and waits for its thread to exit.
+    ///
+    /// # Errors
+    ///
+    /// Returns the panic payload if the hook thread panicked.
+    pub fn shutdown(self) -> thread::Result<()> {
+        self.hook.shutdown()
+    }
+}
@@ -0,0 +1,373 @@
+//! A bounded single-producer, single-consumer queue that never blocks the producer.
+//!
+//! The keyboard hook procedure must return quickly, or Windows removes the hook. Pushing into
+//! this queue is a copy into a pre-allocated slot and two atomic operations: no allocation,
+//! no lock and no logging. If the consumer falls behind and the queue is full, the event is
+//! dropped and counted instead.
+//!
+//! With the `crossbeam` or `flume` feature, a bounded channel of that crate takes the place
+//! of the ring buffer, so that applications can receive events in a `select!` loop or await
+//! them.
+
+/// Why `Consumer::pop_timeout` returned no value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PopError {
+    /// The queue stayed empty until the timeout.
+    Timeout,
+    /// The producer is gone and the queue is drained.
+    Closed,
+}
+
+#[cfg(any(feature = "crossbeam", feature = "flume"))]
+pub(crate) use channel::{Consumer, Producer, queue};
+#[cfg(not(any(feature = "crossbeam", feature = "flume")))]
+pub(crate) use ring::{Consumer, Producer, queue};
+
+#[cfg(all(test, any(feature = "crossbeam", feature = "flume")))]
+use channel::CAPACITY;
+#[cfg(all(test, not(any(feature = "crossbeam", feature = "flume"))))]
+use ring::CAPACITY;
+
+#[cfg(not(any(feature = "crossbeam", feature = "flume")))]
+mod ring {
+    use std::{
+        cell::{Cell, UnsafeCell},
+        marker::PhantomData,
+        mem::MaybeUninit,
+        sync::{
+            Arc, OnceLock,
+            atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        },
+        thread::{self, Thread},
+        time::{Duration, Instant},
+    };
+
+    use super::PopError;
+
+    /// The number of slots. Must be a power of two.
+    pub(super) const CAPACITY: usize = 1024;
+
+    /// How long the consumer parks before checking the queue again, in case it was never
+    /// registered for wake-ups (e.g. it moved to another thread).
+    const PARK_TIMEOUT: Duration = Duration::from_millis(50);
+
+    struct Shared<T> {
+        slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+        /// The index of the next slot to read. Only written by the consumer.
+        head: AtomicUsize,
+        /// The index of the next slot to write. Only written by the producer.
+        tail: AtomicUsize,
+        closed: AtomicBool,
+        dropped: AtomicU64,
+        consumer: OnceLock<Thread>,
+    }
+
+    // Each slot is accessed by at most one side at a time, as arbitrated by `head` and `tail`.
+    unsafe impl<T: Send> Sync for Shared<T> {}
+
+    /// Creates a queue and returns its two ends.
+    pub(crate) fn queue<T: Copy + Send>() -> (Producer<T>, Consumer<T>) {
+        let shared = Arc::new(Shared {
+            slots: (0..CAPACITY)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            dropped: AtomicU64::new(0),
+            consumer: OnceLock::new(),
+        });
+
+        (
+            Producer {
+                shared: shared.clone(),
+            },
+            Consumer {
+                shared,
+                _not_sync: PhantomData,
+            },
+        )
+    }
+
+    /// The sending end, used by the hook procedure.
+    pub(crate) struct Producer<T> {
+        shared: Arc<Shared<T>>,
+    }
+
+    impl<T: Copy + Send> Producer<T> {
+        /// Appends `value`, or drops it if the queue is full.
+        ///
+        /// Returns `false` if `value` was dropped.
+        pub(crate) fn push(&self, value: T) -> bool {
+            let shared = &*self.shared;
+            let tail = shared.tail.load(Ordering::Relaxed);
+            let head = shared.head.load(Ordering::Acquire);
+
+            if tail.wrapping_sub(head) == CAPACITY {
+                shared.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+
+            unsafe { (*shared.slots[tail % CAPACITY].get()).write(value) };
+            shared.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+            if let Some(consumer) = shared.consumer.get() {
+                consumer.unpark();
+            }
+            true
+        }
+    }
+
+    impl<T> Drop for Producer<T> {
+        fn drop(&mut self) {
+            self.shared.closed.store(true, Ordering::Release);
+            if let Some(consumer) = self.shared.consumer.get() {
+                consumer.unpark();
+            }
+        }
+    }
+
+    /// The receiving end.
+    ///
+    /// Not `Sync`: a single consumer may move `head`, so two threads popping through a
+    /// shared reference would read the same slot and race with the producer.
+    pub(crate) struct Consumer<T> {
+        shared: Arc<Shared<T>>,
+        _not_sync: PhantomData<Cell<()>>,
+    }
+
+    impl<T: Copy + Send> Consumer<T> {
+        /// Removes the oldest value, if any.
+        pub(crate) fn try_pop(&self) -> Option<T> {
+            let shared = &*self.shared;
+            let head = shared.head.load(Ordering::Relaxed);
+            let tail = shared.tail.load(Ordering::Acquire);
+
+            if head == tail {
+                return None;
+            }
+
+            let value = unsafe { (*shared.slots[head % CAPACITY].get()).assume_init_read() };
+            shared.head.store(head.wrapping_add(1), Ordering::Release);
+            Some(value)
+        }
+
+        /// Removes the oldest value, waiting up to `timeout` for one if the queue is empty.
+        pub(crate) fn pop_timeout(&self, timeout: Duration) -> Result<T, PopError> {
+            let _ = self.shared.consumer.set(thread::current());
+            let deadline = Instant::now() + timeout;
+
+            loop {
+                if let Some(value) = self.try_pop() {
+                    return Ok(value);
+                }
+                if self.shared.closed.load(Ordering::Acquire) {
+                    // The producer may have pushed right before closing.
+                    return self.try_pop().ok_or(PopError::Closed);
+                }
+
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(PopError::Timeout);
+                }
+                thread::park_timeout(remaining.min(PARK_TIMEOUT));
+            }
+        }
+
+        /// Returns the number of values dropped because the queue was full.
+        pub(crate) fn dropped(&self) -> u64 {
+            self.shared.dropped.load(Ordering::Relaxed)
+        }
+    }
+}
+
+#[cfg(any(feature = "crossbeam", feature = "flume"))]
+mod channel {
+    use std::{
+        cell::Cell,
+        marker::PhantomData,
+        sync::{
+            Arc,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::Duration,
+    };
+
+    #[cfg(feature = "crossbeam")]
+    use crossbeam_channel as imp;
+    #[cfg(feature = "flume")]
+    use flume as imp;
+    use imp::{Receiver, RecvTimeoutError, Sender};
+
+    use super::PopError;
+
+    /// The number of events the channel holds.
+    pub(super) const CAPACITY: usize = 1024;
+
+    /// Creates a queue and returns its two ends.
+    pub(crate) fn queue<T: Copy + Send>() -> (Producer<T>, Consumer<T>) {
+        // A bounded channel pre-allocates its slots, so sending does not allocate.
+        let (tx, rx) = imp::bounded(CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+        (
+            Producer {
+                tx,
+                dropped: dropped.clone(),
+            },
+            Consumer {
+                rx,
+                dropped,
+                _not_sync: PhantomData,
+            },
+        )
+    }
+
+    /// The sending end, used by the hook procedure.
+    pub(crate) struct Producer<T> {
+        tx: Sender<T>,
+        dropped: Arc<AtomicU64>,
+    }
+
+    impl<T: Copy + Send> Producer<T> {
+        /// Appends `value`, or drops it if the queue is full.
+        ///
+        /// Returns `false` if `value` was dropped.
+        pub(crate) fn push(&self, value: T) -> bool {
+            let pushed = self.tx.try_send(value).is_ok();
+            if !pushed {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            pushed
+        }
+    }
+
+    /// The receiving end.
+    ///
+    /// Not `Sync`, like the ring buffer's consumer, so that the types built on it do not
+    /// change with the enabled features.
+    pub(crate) struct Consumer<T> {
+        rx: Receiver<T>,
+        dropped: Arc<AtomicU64>,
+        _not_sync: PhantomData<Cell<()>>,
+    }
+
+    impl<T: Copy + Send> Consumer<T> {
+        /// Removes the oldest value, if any.
+        pub(crate) fn try_pop(&self) -> Option<T> {
+            self.rx.try_recv().ok()
+        }
+
+        /// Removes the oldest value, waiting up to `timeout` for one if the queue is empty.
+        pub(crate) fn pop_timeout(&self, timeout: Duration) -> Result<T, PopError> {
+            self.rx.recv_timeout(timeout).map_err(|e| match e {
+                RecvTimeoutError::Timeout => PopError::Timeout,
+                RecvTimeoutError::Disconnected => PopError::Closed,
+            })
+        }
+
+        /// Returns the number of values dropped because the queue was full.
+        pub(crate) fn dropped(&self) -> u64 {
+            self.dropped.load(Ordering::Relaxed)
+        }
+
+        /// Returns the underlying receiver.
+        pub(crate) fn receiver(&self) -> &Receiver<T> {
+            &self.rx
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn pops_in_push_order() {
+        let (tx, rx) = queue::<u32>();
+        for i in 0..10 {
+            assert!(tx.push(i));
+        }
+        for i in 0..10 {
+            assert_eq!(rx.try_pop(), Some(i));
+        }
+        assert_eq!(rx.try_pop(), None);
+    }
+
+    #[test]
+    fn drops_and_counts_when_full() {
+        let (tx, rx) = queue::<usize>();
+        for i in 0..CAPACITY {
+            assert!(tx.push(i));
+        }
+        assert!(!tx.push(CAPACITY));
+        assert!(!tx.push(CAPACITY + 1));
+        assert_eq!(rx.dropped(), 2);
+
+        // Popping frees a slot; the dropped values are gone for good.
+        assert_eq!(rx.try_pop(), Some(0));
+        assert!(tx.push(CAPACITY + 2));
+        let rest: Vec<_> = std::iter::from_fn(|| rx.try_pop()).collect();
+        assert_eq!(rest.len(), CAPACITY);
+        assert_eq!(rest.first(), Some(&1));
+        assert_eq!(rest.last(), Some(&(CAPACITY + 2)));
+        assert_eq!(rx.dropped(), 2);
+    }
+
+    #[test]
+    fn keeps_order_across_wrap_around() {
+        let (tx, rx) = queue::<usize>();
+        let mut next_push = 0;
+        let mut next_pop = 0;
+        // Uneven batches move the indices across the end of the slots several times.
+        for batch in [CAPACITY - 3, 700, CAPACITY, 5, CAPACITY - 1, 999] {
+            for _ in 0..batch {
+                assert!(tx.push(next_push));
+                next_push += 1;
+            }
+            while let Some(value) = rx.try_pop() {
+                assert_eq!(value, next_pop);
+                next_pop += 1;
+            }
+        }
+        assert_eq!(next_pop, next_push);
+        assert_eq!(rx.dropped(), 0);
+    }
+
+    #[test]
+    fn fills_to_capacity_after_wrap_around() {
+        let (tx, rx) = queue::<usize>();
+        for i in 0..CAPACITY / 2 + 1 {
+            assert!(tx.push(i));
+            assert_eq!(rx.try_pop(), Some(i));
+        }
+        for i in 0..CAPACITY {
+            assert!(tx.push(i));
+        }
+        assert!(!tx.push(CAPACITY));
+        for i in 0..CAPACITY {
+            assert_eq!(rx.try_pop(), Some(i));
+        }
+        assert_eq!(rx.try_pop(), None);
+        assert_eq!(rx.dropped(), 1);
+    }
+
+    #[test]
+    fn pop_timeout_reports_timeout_and_close() {
+        let (tx, rx) = queue::<u32>();
+        assert_eq!(
+            rx.pop_timeout(Duration::from_millis(10)),
+            Err(PopError::Timeout)
+        );
+
+        assert!(tx.push(7));
+        drop(tx);
+        // Values pushed before the producer went away are still delivered.
+        assert_eq!(rx.pop_timeout(Duration::from_millis(10)), Ok(7));
+        assert_eq!(
+            rx.pop_timeout(Duration::from_millis(10)),
+            Err(PopError::Closed)
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Typed records of every suppression decision, for auditing and history views.
+//!
+//! A [`SuppressionRecord`] is delivered to [`Observer::on_record`] for each completed hold.
+//! An [`mpsc::Sender`] implements [`Observer`] by forwarding the records, so a channel is
+//! enough to collect them:
+//!
+//! ```no_run
+//! use std::sync::mpsc;
+//!
+//! use prevent_alt_win_menu::event_handler::Config;
+//!
+//! let (tx, rx) = mpsc::channel();
+//! let _handles = prevent_alt_win_menu::start(Config::default().set_observer(tx))?;
+//! for record in rx {
+//!     println!("{:?} {} after {:?} ms", record.action, record.trigger, record.hold_ms);
+//! }
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+
+use std::{sync::mpsc, time::SystemTime};
+
+use crate::{
+    event_handler::{HoldEvent, MenuTrigger, MenuTriggerEvent},
+    foreground,
+    observer::{Observer, Outcome},
+};
+
+/// The decision of the event handler on a completed hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuppressionRecord {
+    /// When the decision was made.
+    pub timestamp: SystemTime,
+    /// The trigger that was held.
+    pub trigger: MenuTrigger,
+    /// How long the trigger was held, in milliseconds, if the events carry timestamps.
+    pub hold_ms: Option<u64>,
+    /// What the event handler did.
+    pub action: Outcome,
+    /// The executable file name of the foreground process at the release, if it could be
+    /// queried.
+    pub foreground_process: Option<String>,
+}
+
+impl SuppressionRecord {
+    /// Creates the record of `hold`, decided with `action`.
+    ///
+    /// The foreground process is taken from [`HoldEvent::foreground_at_release`] if it was
+    /// captured, and queried otherwise.
+    pub(crate) fn new<T: MenuTriggerEvent>(hold: &HoldEvent<T>, action: Outcome) -> Self {
+        let foreground_process = match hold.foreground_at_release() {
+            Some(window) => window.process_name.clone(),
+            None => foreground::foreground_process_name(),
+        };
+        Self {
+            timestamp: SystemTime::now(),
+            trigger: hold.trigger,
+            hold_ms: hold
+                .release
+                .elapsed_since(&hold.press)
+                .map(|held| held.as_millis() as u64),
+            action,
+            foreground_process,
+        }
+    }
+}
+
+impl<T> Observer<T> for mpsc::Sender<SuppressionRecord> {
+    fn on_record(&self, record: &SuppressionRecord) {
+        let _ = self.send(record.clone());
+    }
+}
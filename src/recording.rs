@@ -0,0 +1,171 @@
+//! Recording of trigger events and decisions to a JSON-lines file (`json` feature).
+//!
+//! To analyze a misbehaving machine offline, or to turn it into a reproducible fixture, set
+//! a [`Recorder`] with [`Config::set_recorder`]. The event handler then appends one
+//! [`RecordedLine`] per line:
+//!
+//! ```text
+//! {"kind":"start","unix_ms":1760500000000,"version":"0.2.2"}
+//! {"kind":"event","t_us":0,"trigger":"Win","side":"Left","down":true,"repeat":false,"vk":91,"scan_code":91}
+//! {"kind":"event","t_us":180250,"trigger":"Win","side":"Left","down":false,"repeat":false,"vk":91,"scan_code":91}
+//! {"kind":"decision","t_us":180400,"trigger":"Win","held_ms":180,"interrupted":false,"action":"Suppressed"}
+//! ```
+//!
+//! Events of trigger keys are recorded, as well as every other key while a trigger is held,
+//! since they interrupt the hold. In [privacy mode](crate::privacy), the codes of other keys
+//! are already redacted.
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{event_handler::Config, recording::Recorder};
+//!
+//! let config = Config::default().set_recorder(Recorder::create("alt-win.jsonl")?);
+//! let _handles = prevent_alt_win_menu::start(config)?;
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Mutex, PoisonError},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::{Error, Result},
+    event_handler::{HoldEvent, MenuTrigger, MenuTriggerEvent, Side},
+    observer::Outcome,
+};
+
+/// A line of a recording.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedLine {
+    /// Written when the recording starts.
+    Start {
+        /// The wall-clock time of the start, in milliseconds since the Unix epoch.
+        unix_ms: u64,
+        /// The version of this crate.
+        version: String,
+    },
+    /// A keyboard event received by the event handler.
+    Event(RecordedEvent),
+    /// The decision on a completed hold.
+    Decision(RecordedDecision),
+}
+
+/// A keyboard event in a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RecordedEvent {
+    /// Microseconds since the start of the recording, when the hook received the event.
+    pub t_us: u64,
+    /// The trigger of the key, if it is one.
+    pub trigger: Option<MenuTrigger>,
+    /// Whether the left or right key was used, if known.
+    pub side: Option<Side>,
+    /// `true` for a key-down, `false` for a key-up.
+    pub down: bool,
+    /// Whether this is an auto-repeat key-down.
+    pub repeat: bool,
+    /// The virtual key code, if the event carries one.
+    pub vk: Option<u16>,
+    /// The hardware scan code, if the event carries one.
+    pub scan_code: Option<u32>,
+}
+
+/// The decision on a completed hold in a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RecordedDecision {
+    /// Microseconds since the start of the recording, when the decision was made.
+    pub t_us: u64,
+    /// The trigger that was held.
+    pub trigger: MenuTrigger,
+    /// How long the trigger was held, in milliseconds, if the events carry timestamps.
+    pub held_ms: Option<u64>,
+    /// Whether another key was pressed during the hold.
+    pub interrupted: bool,
+    /// What the event handler did.
+    pub action: Outcome,
+}
+
+/// Appends events and decisions to a JSON-lines file. See the [module docs](self).
+#[derive(Debug)]
+pub struct Recorder {
+    file: Mutex<BufWriter<File>>,
+    started_at: Instant,
+}
+
+impl Recorder {
+    /// Opens `path` for appending, creating it if needed, and writes a
+    /// [`RecordedLine::Start`].
+    ///
+    /// # Errors
+    /// Returns `Error::Recording` if the file cannot be opened or written.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(Error::Recording)?;
+        let recorder = Self {
+            file: Mutex::new(BufWriter::new(file)),
+            started_at: Instant::now(),
+        };
+        recorder
+            .write(&RecordedLine::Start {
+                unix_ms: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_millis() as u64),
+                version: env!("CARGO_PKG_VERSION").to_owned(),
+            })
+            .map_err(Error::Recording)?;
+        Ok(recorder)
+    }
+
+    /// Appends `event`.
+    pub(crate) fn record_event<T: MenuTriggerEvent>(
+        &self,
+        event: &T,
+        trigger: Option<MenuTrigger>,
+    ) -> io::Result<()> {
+        self.write(&RecordedLine::Event(RecordedEvent {
+            t_us: self.since_start(event.received_at().unwrap_or_else(Instant::now)),
+            trigger,
+            side: event.side(),
+            down: event.is_key_down(),
+            repeat: event.is_repeat(),
+            vk: event.virtual_key_code().map(|vk| vk.0),
+            scan_code: event.scan_code(),
+        }))
+    }
+
+    /// Appends the decision on `hold`.
+    pub(crate) fn record_decision<T: MenuTriggerEvent>(
+        &self,
+        hold: &HoldEvent<T>,
+        action: Outcome,
+    ) -> io::Result<()> {
+        self.write(&RecordedLine::Decision(RecordedDecision {
+            t_us: self.since_start(Instant::now()),
+            trigger: hold.trigger,
+            held_ms: hold
+                .release
+                .elapsed_since(&hold.press)
+                .map(|held| held.as_millis() as u64),
+            interrupted: hold.was_interrupted(),
+            action,
+        }))
+    }
+
+    fn since_start(&self, at: Instant) -> u64 {
+        at.saturating_duration_since(self.started_at).as_micros() as u64
+    }
+
+    fn write(&self, line: &RecordedLine) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        serde_json::to_writer(&mut *file, line)?;
+        file.write_all(b"\n")?;
+        // Flushed per line, so that the recording survives a crash of the application.
+        file.flush()
+    }
+}
@@ -0,0 +1,206 @@
+//! Replay of recorded traces through the event handler (`json` feature).
+//!
+//! [`run`] reads a file written by a [`Recorder`](crate::recording::Recorder), feeds its
+//! events through [`start_event_handler`] and returns the decisions taken. A user-reported
+//! misbehavior can thus be reproduced deterministically, and a configuration compared
+//! against the decisions that were recorded:
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{event_handler::Config, replay};
+//!
+//! for decision in replay::run("alt-win.jsonl", Config::default())? {
+//!     println!("{} after {:?} ms: {:?}", decision.trigger, decision.held_ms, decision.action);
+//! }
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! Nothing is injected: the strategies of the configuration are replaced by one that does
+//! nothing. Events are replayed as fast as possible with their recorded timestamps, so hold
+//! durations are exact, but options measured in wall-clock time, such as
+//! [`Config::set_cooldown`] and [`Config::set_injection_rate_limit`], and rules on the
+//! foreground window see the time and window of the replay.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    panic,
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY;
+
+use crate::{
+    error::{Error, Result},
+    event_handler::{
+        Config, HoldEvent, KeyState, MenuTrigger, MenuTriggerEvent, Side, start_event_handler,
+    },
+    observer::{Observer, Outcome},
+    record::SuppressionRecord,
+    recording::{RecordedDecision, RecordedEvent, RecordedLine},
+    strategy::SuppressionStrategy,
+};
+
+/// A recorded event, as passed to the event handler during [`run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayEvent {
+    /// The event as recorded. Its `t_us` is continued across the sessions of the file.
+    pub recorded: RecordedEvent,
+}
+
+impl MenuTriggerEvent for ReplayEvent {
+    fn menu_trigger(&self) -> Option<MenuTrigger> {
+        self.recorded.trigger
+    }
+
+    fn key_state(&self) -> KeyState {
+        if self.recorded.down {
+            KeyState::Down
+        } else {
+            KeyState::Up
+        }
+    }
+
+    fn side(&self) -> Option<Side> {
+        self.recorded.side
+    }
+
+    fn is_repeat(&self) -> bool {
+        self.recorded.repeat
+    }
+
+    fn virtual_key_code(&self) -> Option<VIRTUAL_KEY> {
+        self.recorded.vk.map(VIRTUAL_KEY)
+    }
+
+    fn scan_code(&self) -> Option<u32> {
+        self.recorded.scan_code
+    }
+
+    fn elapsed_since(&self, earlier: &Self) -> Option<Duration> {
+        Some(Duration::from_micros(
+            self.recorded.t_us.saturating_sub(earlier.recorded.t_us),
+        ))
+    }
+}
+
+/// Replays the trace at `path` with `config` and returns the decisions taken, in order.
+///
+/// The `t_us` of each decision is that of the release that completed the hold. An
+/// [`Observer`] set in `config` is still notified.
+///
+/// # Errors
+/// Returns `Error::Recording` if the file cannot be read or a line is not a
+/// [`RecordedLine`].
+pub fn run(
+    path: impl AsRef<Path>,
+    mut config: Config<ReplayEvent>,
+) -> Result<Vec<RecordedDecision>> {
+    let events = read_events(path.as_ref()).map_err(Error::Recording)?;
+
+    let decisions = Arc::new(Mutex::new(Vec::new()));
+    config.observer = Some(Box::new(Capture {
+        inner: config.observer.take(),
+        decisions: decisions.clone(),
+    }));
+    config.strategy = Box::new(NoInjection);
+    config.trigger_strategies.clear();
+    config.fallback_strategies.clear();
+
+    if let Err(payload) = start_event_handler(events, config).join() {
+        panic::resume_unwind(payload);
+    }
+    Ok(std::mem::take(
+        &mut *decisions.lock().unwrap_or_else(PoisonError::into_inner),
+    ))
+}
+
+/// Reads the events of all sessions in the file, continuing `t_us` across sessions.
+fn read_events(path: &Path) -> io::Result<Vec<ReplayEvent>> {
+    let mut events = Vec::new();
+    let (mut offset, mut last) = (0, 0);
+
+    for (number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parsed = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {e}", number + 1),
+            )
+        })?;
+        match parsed {
+            RecordedLine::Start { .. } => offset = last,
+            RecordedLine::Event(mut recorded) => {
+                recorded.t_us += offset;
+                last = recorded.t_us;
+                events.push(ReplayEvent { recorded });
+            }
+            RecordedLine::Decision(_) => {}
+        }
+    }
+    Ok(events)
+}
+
+/// Collects the decisions of [`run`] and forwards everything to the configured observer.
+struct Capture {
+    inner: Option<Box<dyn Observer<ReplayEvent>>>,
+    decisions: Arc<Mutex<Vec<RecordedDecision>>>,
+}
+
+impl Observer<ReplayEvent> for Capture {
+    fn on_hook_registered(&self, thread_id: u32) {
+        if let Some(inner) = &self.inner {
+            inner.on_hook_registered(thread_id);
+        }
+    }
+
+    fn on_hook_reinstalled(&self) {
+        if let Some(inner) = &self.inner {
+            inner.on_hook_reinstalled();
+        }
+    }
+
+    fn on_suppressed(&self, hold: &HoldEvent<ReplayEvent>, outcome: Outcome) {
+        self.decisions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(RecordedDecision {
+                t_us: hold.release.recorded.t_us,
+                trigger: hold.trigger,
+                held_ms: hold
+                    .release
+                    .elapsed_since(&hold.press)
+                    .map(|held| held.as_millis() as u64),
+                interrupted: hold.was_interrupted(),
+                action: outcome,
+            });
+        if let Some(inner) = &self.inner {
+            inner.on_suppressed(hold, outcome);
+        }
+    }
+
+    fn on_record(&self, record: &SuppressionRecord) {
+        if let Some(inner) = &self.inner {
+            inner.on_record(record);
+        }
+    }
+
+    fn on_dropped_events(&self, count: u64) {
+        if let Some(inner) = &self.inner {
+            inner.on_dropped_events(count);
+        }
+    }
+}
+
+/// A strategy that injects nothing, so that a replay does not type into the desktop.
+struct NoInjection;
+
+impl SuppressionStrategy<ReplayEvent> for NoInjection {
+    fn suppress(&self, _hold: &HoldEvent<ReplayEvent>, _dummy_key: VIRTUAL_KEY) -> io::Result<()> {
+        Ok(())
+    }
+}
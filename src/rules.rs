@@ -0,0 +1,172 @@
+//! Declarative rules for deciding whether to suppress a menu.
+//!
+//! A [`RuleSet`] is an ordered list of [`Rule`]s. When a trigger key is released, the first rule
+//! whose conditions all hold decides the [`Action`]; if none matches, [`RuleSet::default_action`]
+//! is used. This covers most use cases without writing an `on_released` closure, and rule
+//! evaluation can be tested deterministically with [`RuleSet::evaluate_with`].
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use prevent_alt_win_menu::event_handler::{Config, MenuTrigger};
+//! use prevent_alt_win_menu::rules::{Action, Rule, RuleSet};
+//!
+//! let rules = RuleSet::new(vec![
+//!     // Let quick Alt taps open the menu bar.
+//!     Rule {
+//!         trigger: Some(MenuTrigger::Alt),
+//!         max_hold: Some(Duration::from_millis(300)),
+//!         action: Action::PassThrough,
+//!         ..Default::default()
+//!     },
+//!     // Never interfere with this application.
+//!     Rule {
+//!         process: Some("game.exe".to_owned()),
+//!         action: Action::PassThrough,
+//!         ..Default::default()
+//!     },
+//! ]);
+//!
+//! let config = Config::default().set_rules(rules);
+//! ```
+
+use std::time::Duration;
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{VIRTUAL_KEY, VK__none_};
+
+use crate::{
+    activation,
+    event_handler::{Config, HoldEvent, MenuTrigger, Side},
+    foreground::foreground_process_name,
+};
+
+/// What to do when a rule matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Suppress the menu by sending the given dummy key.
+    Suppress(VIRTUAL_KEY),
+    /// Let the menu appear.
+    PassThrough,
+}
+
+impl Action {
+    /// Returns the dummy key to send, or `None` for [`Action::PassThrough`].
+    pub fn dummy_key(&self) -> Option<VIRTUAL_KEY> {
+        match self {
+            Action::Suppress(key) => Some(*key),
+            Action::PassThrough => None,
+        }
+    }
+}
+
+impl Default for Action {
+    fn default() -> Self {
+        Action::Suppress(VK__none_)
+    }
+}
+
+/// A single suppression rule.
+///
+/// All conditions that are set (`Some`) must hold for the rule to match.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rule {
+    /// Matches only releases of this trigger.
+    pub trigger: Option<MenuTrigger>,
+    /// Matches only releases of the left or right key.
+    pub side: Option<Side>,
+    /// Matches only if the key was held for at least this long.
+    pub min_hold: Option<Duration>,
+    /// Matches only if the key was held for at most this long.
+    pub max_hold: Option<Duration>,
+    /// Matches only if the foreground process has this executable name (case-insensitive),
+    /// e.g. `"notepad.exe"`.
+    pub process: Option<String>,
+    /// The action taken when the rule matches.
+    pub action: Action,
+}
+
+impl Rule {
+    /// Returns `true` if the rule matches the given facts.
+    pub fn matches(
+        &self,
+        trigger: MenuTrigger,
+        side: Option<Side>,
+        held: Duration,
+        process: Option<&str>,
+    ) -> bool {
+        self.trigger.is_none_or(|t| t == trigger)
+            && self.side.is_none_or(|s| side == Some(s))
+            && self.min_hold.is_none_or(|min| held >= min)
+            && self.max_hold.is_none_or(|max| held <= max)
+            && self
+                .process
+                .as_deref()
+                .is_none_or(|p| process.is_some_and(|name| name.eq_ignore_ascii_case(p)))
+    }
+}
+
+/// An ordered list of [`Rule`]s with a fallback action.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleSet {
+    /// Rules evaluated in order; the first match wins.
+    pub rules: Vec<Rule>,
+    /// The action taken when no rule matches. Suppresses with `VK__none_` by default.
+    pub default_action: Action,
+}
+
+impl RuleSet {
+    /// Creates a rule set with the default fallback action.
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            rules,
+            default_action: Action::default(),
+        }
+    }
+
+    /// Evaluates the rules against explicitly given facts.
+    ///
+    /// This function is pure and does not query any system state.
+    pub fn evaluate_with(
+        &self,
+        trigger: MenuTrigger,
+        side: Option<Side>,
+        held: Duration,
+        process: Option<&str>,
+    ) -> Action {
+        self.rules
+            .iter()
+            .find(|r| r.matches(trigger, side, held, process))
+            .map_or(self.default_action, |r| r.action)
+    }
+
+    /// Evaluates the rules for a completed hold.
+    ///
+    /// The foreground process is taken from [`HoldEvent::foreground_at_release`] if it was
+    /// captured, then from the latest [`activation::current`] if the foreground watcher is
+    /// running, and otherwise queried, but only if some rule refers to it.
+    pub fn evaluate(&self, hold: &HoldEvent) -> Action {
+        let process = if self.rules.iter().any(|r| r.process.is_some()) {
+            match hold.foreground_at_release() {
+                Some(window) => window.process_name.clone(),
+                None => match activation::current() {
+                    Some(activation) => activation.window.process_name,
+                    None => foreground_process_name(),
+                },
+            }
+        } else {
+            None
+        };
+
+        self.evaluate_with(hold.trigger, hold.side, hold.duration(), process.as_deref())
+    }
+}
+
+impl Config {
+    /// Sets `on_released` to evaluate the given [`RuleSet`].
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance with the new callback set (builder pattern).
+    pub fn set_rules(self, rules: RuleSet) -> Self {
+        self.set_on_released(move |hold| rules.evaluate(&hold).dummy_key())
+    }
+}
@@ -0,0 +1,206 @@
+//! Time- and flag-based activation of suppression.
+//!
+//! A [`Schedule`] restricts suppression to certain times of the day and/or to periods
+//! in which an application-controlled flag is set. Attach it with [`Config::set_schedule`].
+//!
+//! ```
+//! use std::sync::{Arc, atomic::AtomicBool};
+//!
+//! use prevent_alt_win_menu::event_handler::Config;
+//! use prevent_alt_win_menu::schedule::{Schedule, TimeOfDay};
+//!
+//! let gaming = Arc::new(AtomicBool::new(false));
+//!
+//! let schedule = Schedule::default()
+//!     .add_window(TimeOfDay::new(9, 0), TimeOfDay::new(18, 0))
+//!     .set_flag(gaming.clone());
+//!
+//! let config = Config::default().set_schedule(schedule);
+//! ```
+//!
+//! [`Config::set_schedule`]: crate::event_handler::Config::set_schedule
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+/// A local time of day with minute precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay {
+    /// Hour, `0..24`.
+    pub hour: u8,
+    /// Minute, `0..60`.
+    pub minute: u8,
+}
+
+impl TimeOfDay {
+    /// Creates a time of day.
+    ///
+    /// # Panics
+    /// Panics if `hour >= 24` or `minute >= 60`.
+    pub const fn new(hour: u8, minute: u8) -> Self {
+        assert!(hour < 24 && minute < 60, "invalid time of day");
+        Self { hour, minute }
+    }
+
+    /// Returns the current local time of day.
+    pub fn now() -> Self {
+        let t = unsafe { GetLocalTime() };
+        Self {
+            hour: t.wHour as u8,
+            minute: t.wMinute as u8,
+        }
+    }
+}
+
+/// A daily time window, `start` inclusive and `end` exclusive.
+///
+/// If `end` is earlier than `start`, the window spans midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    /// Beginning of the window.
+    pub start: TimeOfDay,
+    /// End of the window.
+    pub end: TimeOfDay,
+}
+
+impl TimeWindow {
+    /// Returns `true` if `time` falls within the window.
+    pub fn contains(&self, time: TimeOfDay) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+/// Determines when suppression is active.
+///
+/// The default schedule is always active. Adding time windows limits suppression to those
+/// windows; setting a flag additionally requires the flag to be `true`.
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    windows: Vec<TimeWindow>,
+    flag: Option<Arc<AtomicBool>>,
+}
+
+impl Schedule {
+    /// Adds a daily time window in which suppression is active.
+    ///
+    /// # Returns
+    /// A modified [`Schedule`] instance (builder pattern).
+    pub fn add_window(mut self, start: TimeOfDay, end: TimeOfDay) -> Self {
+        self.windows.push(TimeWindow { start, end });
+        self
+    }
+
+    /// Makes suppression depend on a flag controlled by the application,
+    /// e.g. "a gaming session is running".
+    ///
+    /// # Returns
+    /// A modified [`Schedule`] instance (builder pattern).
+    pub fn set_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.flag = Some(flag);
+        self
+    }
+
+    /// Returns `true` if suppression is active at the given time of day.
+    pub fn is_active_at(&self, time: TimeOfDay) -> bool {
+        let in_window = self.windows.is_empty() || self.windows.iter().any(|w| w.contains(time));
+        let flag_set = self.flag.as_ref().is_none_or(|f| f.load(Ordering::Relaxed));
+
+        in_window && flag_set
+    }
+
+    /// Returns `true` if suppression is active now.
+    pub fn is_active(&self) -> bool {
+        self.is_active_at(TimeOfDay::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn t(hour: u8, minute: u8) -> TimeOfDay {
+        TimeOfDay::new(hour, minute)
+    }
+
+    #[test]
+    fn window_includes_start_and_excludes_end() {
+        let window = TimeWindow {
+            start: t(9, 0),
+            end: t(18, 0),
+        };
+        assert!(!window.contains(t(8, 59)));
+        assert!(window.contains(t(9, 0)));
+        assert!(window.contains(t(17, 59)));
+        assert!(!window.contains(t(18, 0)));
+    }
+
+    #[test]
+    fn window_spans_midnight() {
+        let window = TimeWindow {
+            start: t(22, 0),
+            end: t(6, 30),
+        };
+        assert!(window.contains(t(22, 0)));
+        assert!(window.contains(t(23, 59)));
+        assert!(window.contains(t(0, 0)));
+        assert!(window.contains(t(6, 29)));
+        assert!(!window.contains(t(6, 30)));
+        assert!(!window.contains(t(12, 0)));
+        assert!(!window.contains(t(21, 59)));
+    }
+
+    #[test]
+    fn empty_window_never_contains() {
+        let window = TimeWindow {
+            start: t(12, 0),
+            end: t(12, 0),
+        };
+        assert!(!window.contains(t(12, 0)));
+        assert!(!window.contains(t(0, 0)));
+    }
+
+    #[test]
+    fn without_windows_is_always_active() {
+        let schedule = Schedule::default();
+        for hour in 0..24 {
+            assert!(schedule.is_active_at(t(hour, 0)));
+        }
+    }
+
+    #[test]
+    fn any_window_activates() {
+        let schedule = Schedule::default()
+            .add_window(t(9, 0), t(12, 0))
+            .add_window(t(23, 0), t(1, 0));
+        assert!(schedule.is_active_at(t(10, 0)));
+        assert!(schedule.is_active_at(t(0, 30)));
+        assert!(!schedule.is_active_at(t(12, 0)));
+        assert!(!schedule.is_active_at(t(1, 0)));
+    }
+
+    #[test]
+    fn flag_gates_the_windows() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let schedule = Schedule::default()
+            .add_window(t(9, 0), t(18, 0))
+            .set_flag(flag.clone());
+        assert!(!schedule.is_active_at(t(10, 0)));
+
+        flag.store(true, Ordering::Relaxed);
+        assert!(schedule.is_active_at(t(10, 0)));
+        assert!(!schedule.is_active_at(t(20, 0)));
+
+        let always = Schedule::default().set_flag(flag.clone());
+        assert!(always.is_active_at(t(20, 0)));
+        flag.store(false, Ordering::Relaxed);
+        assert!(!always.is_active_at(t(20, 0)));
+    }
+}
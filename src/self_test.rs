@@ -0,0 +1,149 @@
+//! A startup sanity check of the whole suppression path.
+//!
+//! [`JoinHandles::self_test`](crate::JoinHandles::self_test) injects a `Win` tap marked with
+//! [`SELF_TEST_INPUT_TAG`] and checks that the keyboard hook received it, that the event
+//! handler decided on the hold and, with the `detect` feature, that the Start menu stayed
+//! closed. Kiosk deployments can run it once after starting and alert if it fails:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! let handles = prevent_alt_win_menu::start(Default::default())?;
+//! let report = handles.self_test(Duration::from_secs(1))?;
+//! if !report.passed() {
+//!     eprintln!("suppression self-test failed: {report:?}");
+//! }
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! Unlike a probe of [`health::is_active`](crate::health::is_active), the tap reaches
+//! Windows like a real one: if suppression does not work, the Start menu opens. Run the
+//! self-test while the user does not type, since a concurrent hold can be taken for the
+//! synthetic one.
+
+use std::{
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU8, AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    INPUT, INPUT_0, INPUT_KEYBOARD, KEYBD_EVENT_FLAGS, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY,
+    KEYEVENTF_KEYUP, VK_LWIN,
+};
+
+use crate::{
+    error::{Error, Result},
+    input,
+    observer::Outcome,
+};
+
+/// The value stored in `KEYBDINPUT.dwExtraInfo` of the tap sent by the self-test.
+///
+/// Unlike other input injected by this crate, the hook passes it to the event handler, even
+/// with [`HookOptions::ignore_injected`](crate::keyboard_hook::HookOptions::ignore_injected).
+pub const SELF_TEST_INPUT_TAG: usize = 0x5041_5754; // "PAWT"
+
+static AT_HOOK: AtomicU64 = AtomicU64::new(0);
+static DECISIONS: AtomicU64 = AtomicU64::new(0);
+static LAST_OUTCOME: AtomicU8 = AtomicU8::new(0);
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// The result of a self-test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// Whether the keyboard hook received both events of the tap.
+    pub hook_received: bool,
+    /// What the event handler did with the tap, or `None` if it did not decide in time.
+    pub decision: Option<Outcome>,
+    /// Whether the Start menu opened after the tap, or `None` without the `detect` feature.
+    pub start_menu_opened: Option<bool>,
+    /// How long the self-test took.
+    pub duration: Duration,
+}
+
+impl SelfTestReport {
+    /// Returns `true` if the tap was received and suppressed, and the Start menu, if
+    /// checked, stayed closed.
+    pub fn passed(&self) -> bool {
+        self.hook_received
+            && self.decision == Some(Outcome::Suppressed)
+            && self.start_menu_opened != Some(true)
+    }
+}
+
+/// Injects the tap and waits up to `timeout` for the hook and the handler.
+pub(crate) fn run(timeout: Duration) -> Result<SelfTestReport> {
+    let _guard = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let at_hook = AT_HOOK.load(Ordering::Acquire);
+    let decisions = DECISIONS.load(Ordering::Acquire);
+
+    let start = Instant::now();
+    input::send(&[tap_input(KEYBD_EVENT_FLAGS(0)), tap_input(KEYEVENTF_KEYUP)])
+        .map_err(Error::InjectionFailed)?;
+
+    while DECISIONS.load(Ordering::Acquire) == decisions && start.elapsed() < timeout {
+        thread::sleep(Duration::from_millis(1));
+    }
+    let decision = (DECISIONS.load(Ordering::Acquire) != decisions)
+        .then(|| outcome_from_u8(LAST_OUTCOME.load(Ordering::Acquire)));
+
+    #[cfg(feature = "detect")]
+    let start_menu_opened = Some(crate::detect::wait_for_start_menu(
+        timeout.saturating_sub(start.elapsed()),
+    ));
+    #[cfg(not(feature = "detect"))]
+    let start_menu_opened = None;
+
+    Ok(SelfTestReport {
+        hook_received: AT_HOOK.load(Ordering::Acquire).wrapping_sub(at_hook) >= 2,
+        decision,
+        start_menu_opened,
+        duration: start.elapsed(),
+    })
+}
+
+fn tap_input(flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VK_LWIN,
+                dwFlags: flags | KEYEVENTF_EXTENDEDKEY,
+                dwExtraInfo: SELF_TEST_INPUT_TAG,
+                ..Default::default()
+            },
+        },
+    }
+}
+
+pub(crate) fn at_hook() {
+    AT_HOOK.fetch_add(1, Ordering::Release);
+}
+
+/// Records the outcome of a completed hold, which may be the one of the self-test.
+pub(crate) fn record_decision(outcome: Outcome) {
+    LAST_OUTCOME.store(outcome_to_u8(outcome), Ordering::Release);
+    DECISIONS.fetch_add(1, Ordering::Release);
+}
+
+fn outcome_to_u8(outcome: Outcome) -> u8 {
+    match outcome {
+        Outcome::Suppressed => 0,
+        Outcome::PassedThrough => 1,
+        Outcome::RateLimited => 2,
+        Outcome::Failed => 3,
+    }
+}
+
+fn outcome_from_u8(value: u8) -> Outcome {
+    match value {
+        0 => Outcome::Suppressed,
+        1 => Outcome::PassedThrough,
+        2 => Outcome::RateLimited,
+        _ => Outcome::Failed,
+    }
+}
@@ -0,0 +1,206 @@
+//! Tracking of workstation locks, the secure desktop and Remote Desktop sessions.
+//!
+//! While the workstation is locked or the secure desktop (UAC prompts, `Ctrl+Alt+Del`) is
+//! active, input goes to another desktop: injected keys cannot reach it, and a press seen
+//! before the switch is never matched by a release. [`start_session_watcher`] observes both
+//! states so that the event handler can pause suppression and discard pending holds when the
+//! user returns (see [`Config::set_pause_on_lock`]).
+//!
+//! Locks are reported by `WTSRegisterSessionNotification`; the secure desktop, which sends
+//! no notification, is detected by polling the input desktop.
+//!
+//! The same notifications tell whether the session is connected remotely. Injected keys pass
+//! through some RDP clients differently, so [`Config::set_remote_session_mode`] can pause
+//! suppression or use another dummy key in remote sessions.
+//!
+//! [`Config::set_pause_on_lock`]: crate::event_handler::Config::set_pause_on_lock
+//! [`Config::set_remote_session_mode`]: crate::event_handler::Config::set_remote_session_mode
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread,
+};
+
+use windows::{
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+        System::{
+            RemoteDesktop::{NOTIFY_FOR_THIS_SESSION, WTSRegisterSessionNotification},
+            StationsAndDesktops::{
+                CloseDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_READOBJECTS,
+                GetUserObjectInformationW, OpenInputDesktop, UOI_NAME,
+            },
+        },
+        UI::WindowsAndMessaging::{
+            DefWindowProcW, DispatchMessageW, GetMessageW, GetSystemMetrics, MSG, SM_REMOTESESSION,
+            SetTimer, WM_TIMER, WM_WTSSESSION_CHANGE, WTS_CONSOLE_CONNECT, WTS_REMOTE_CONNECT,
+            WTS_SESSION_LOCK, WTS_SESSION_UNLOCK,
+        },
+    },
+    core::w,
+};
+
+use crate::{
+    error::{Error, Result},
+    window::create_message_window,
+};
+
+/// How often the input desktop is polled, in milliseconds.
+const POLL_INTERVAL_MS: u32 = 500;
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+static SECURE_DESKTOP: AtomicBool = AtomicBool::new(false);
+static REMOTE: AtomicBool = AtomicBool::new(false);
+static RESUMES: AtomicU64 = AtomicU64::new(0);
+
+/// Returns `true` while the workstation is locked, as observed by the watcher started with
+/// [`start_session_watcher`].
+pub fn is_locked() -> bool {
+    LOCKED.load(Ordering::Relaxed)
+}
+
+/// Returns `true` while another desktop than the user's, such as the secure desktop,
+/// receives input, as observed by the watcher started with [`start_session_watcher`].
+pub fn is_secure_desktop_active() -> bool {
+    SECURE_DESKTOP.load(Ordering::Relaxed)
+}
+
+/// Returns `true` while the session is connected through Remote Desktop, as observed by the
+/// watcher started with [`start_session_watcher`].
+pub fn is_remote_session() -> bool {
+    REMOTE.load(Ordering::Relaxed)
+}
+
+/// Returns `true` if the workstation is locked or the secure desktop is active.
+pub fn is_paused() -> bool {
+    is_locked() || is_secure_desktop_active()
+}
+
+/// Returns how often the user returned from a lock or the secure desktop, or the session was
+/// connected to another client.
+///
+/// The value only increases; compare two readings to detect a return in between.
+pub fn resume_count() -> u64 {
+    RESUMES.load(Ordering::Relaxed)
+}
+
+/// Starts a thread that observes workstation locks, the secure desktop and Remote Desktop
+/// connections.
+///
+/// # Errors
+/// - Returns `Error::SessionNotificationFailed` if the notification window cannot be created
+///   or registered for session notifications.
+/// - Returns `Error::HookThreadCrashed` if the thread terminated unexpectedly.
+pub fn start_session_watcher() -> Result<thread::JoinHandle<()>> {
+    let (result_tx, result_rx) = oneshot::channel::<Result<()>>();
+
+    let join_handle = thread::spawn(move || {
+        let Some(hwnd) = create_message_window(w!("PreventAltWinMenuSession"), Some(window_proc))
+        else {
+            let _ = result_tx.send(Err(Error::SessionNotificationFailed));
+            return;
+        };
+        if unsafe { WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) }.is_err() {
+            let _ = result_tx.send(Err(Error::SessionNotificationFailed));
+            return;
+        }
+        unsafe { SetTimer(Some(hwnd), 1, POLL_INTERVAL_MS, None) };
+        REMOTE.store(
+            unsafe { GetSystemMetrics(SM_REMOTESESSION) } != 0,
+            Ordering::Relaxed,
+        );
+        let _ = result_tx.send(Ok(()));
+
+        #[cfg(feature = "log")]
+        log::info!("started session watcher");
+
+        let mut msg = MSG::default();
+        unsafe {
+            while GetMessageW(&mut msg, None, 0, 0).into() {
+                DispatchMessageW(&msg);
+            }
+        }
+    });
+
+    match result_rx.recv() {
+        Ok(Ok(())) => Ok(join_handle),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(Error::HookThreadCrashed),
+    }
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: u32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_WTSSESSION_CHANGE => match w_param.0 as u32 {
+            WTS_SESSION_LOCK => set_state(&LOCKED, true),
+            WTS_SESSION_UNLOCK => set_state(&LOCKED, false),
+            WTS_REMOTE_CONNECT => set_connection(true),
+            WTS_CONSOLE_CONNECT => set_connection(false),
+            _ => {}
+        },
+        WM_TIMER => set_state(&SECURE_DESKTOP, !is_input_desktop_default()),
+        _ => return unsafe { DefWindowProcW(hwnd, msg, w_param, l_param) },
+    }
+    LRESULT(0)
+}
+
+fn set_state(state: &AtomicBool, value: bool) {
+    let was_paused = is_paused();
+    state.store(value, Ordering::Relaxed);
+
+    if was_paused != is_paused() {
+        #[cfg(feature = "log")]
+        log::info!(
+            "suppression {}",
+            if is_paused() { "paused" } else { "resumed" }
+        );
+        if was_paused {
+            RESUMES.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn set_connection(remote: bool) {
+    #[cfg(feature = "log")]
+    log::info!(
+        "session connected {}",
+        if remote { "remotely" } else { "locally" }
+    );
+    REMOTE.store(remote, Ordering::Relaxed);
+    // Keys held on the previous client are never released on this one.
+    RESUMES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Returns `true` if the desktop receiving input is the user's `Default` desktop.
+fn is_input_desktop_default() -> bool {
+    // Opening the input desktop fails while the secure desktop is active.
+    let Ok(desktop) =
+        (unsafe { OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_READOBJECTS) })
+    else {
+        return false;
+    };
+
+    let mut name = [0u16; 64];
+    let mut len = 0;
+    let ok = unsafe {
+        GetUserObjectInformationW(
+            desktop.into(),
+            UOI_NAME,
+            Some(name.as_mut_ptr().cast()),
+            std::mem::size_of_val(&name) as u32,
+            Some(&mut len),
+        )
+    }
+    .is_ok();
+    unsafe {
+        let _ = CloseDesktop(desktop);
+    }
+
+    let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+    !ok || String::from_utf16_lossy(&name[..len]).eq_ignore_ascii_case("Default")
+}
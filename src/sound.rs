@@ -0,0 +1,64 @@
+//! Short system sounds as audible feedback.
+//!
+//! With [`Config::set_suppression_sound`], a sound is played whenever a menu is suppressed,
+//! and with [`HookOptions::toggle_sound`], whenever the hook is paused or resumed with
+//! [`HookCommand`]. This gives confirmation without a visual indicator, e.g. for users of
+//! screen readers:
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{event_handler::Config, sound::Sound};
+//!
+//! let config = Config::default().set_suppression_sound(Sound::Asterisk);
+//! let _handles = prevent_alt_win_menu::start(config)?;
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! Sounds are played with `MessageBeep`, which returns immediately, and follow the sound
+//! scheme the user configured in Windows.
+//!
+//! [`Config::set_suppression_sound`]: crate::event_handler::Config::set_suppression_sound
+//! [`HookOptions::toggle_sound`]: crate::keyboard_hook::HookOptions::toggle_sound
+//! [`HookCommand`]: crate::keyboard_hook::HookCommand
+
+use windows::Win32::UI::WindowsAndMessaging::{
+    MB_ICONASTERISK, MB_ICONEXCLAMATION, MB_ICONHAND, MB_ICONQUESTION, MB_OK, MESSAGEBOX_STYLE,
+    MessageBeep,
+};
+
+/// A system sound.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sound {
+    /// A simple beep, played through the speaker if no sound card is available.
+    #[default]
+    Beep,
+    /// The "Default Beep" sound of the scheme.
+    Default,
+    /// The "Asterisk" (information) sound of the scheme.
+    Asterisk,
+    /// The "Exclamation" (warning) sound of the scheme.
+    Exclamation,
+    /// The "Critical Stop" (error) sound of the scheme.
+    Hand,
+    /// The "Question" sound of the scheme.
+    Question,
+}
+
+/// Plays `sound` asynchronously.
+pub fn play(sound: Sound) {
+    let style = match sound {
+        Sound::Beep => MESSAGEBOX_STYLE(u32::MAX),
+        Sound::Default => MB_OK,
+        Sound::Asterisk => MB_ICONASTERISK,
+        Sound::Exclamation => MB_ICONEXCLAMATION,
+        Sound::Hand => MB_ICONHAND,
+        Sound::Question => MB_ICONQUESTION,
+    };
+    let result = unsafe { MessageBeep(style) };
+    #[cfg(feature = "log")]
+    if let Err(e) = result {
+        log::warn!("failed to play {:?}: {}", sound, e);
+    }
+    #[cfg(not(feature = "log"))]
+    let _ = result;
+}
@@ -0,0 +1,219 @@
+//! Serializable description of a suppression configuration.
+//!
+//! [`ConfigSpec`] covers the subset of [`Config`] that can be expressed as plain data:
+//! which triggers are handled, hold-duration thresholds, dummy keys, and per-application rules.
+//! It can be stored with any `serde` format and converted into a [`RuleSet`] or a runtime
+//! [`Config`] with [`From`]/[`Into`].
+//!
+//! ```
+//! use prevent_alt_win_menu::event_handler::Config;
+//! use prevent_alt_win_menu::spec::{ConfigSpec, RuleSpec};
+//!
+//! let spec = ConfigSpec {
+//!     rules: vec![RuleSpec {
+//!         process: Some("game.exe".to_owned()),
+//!         suppress: false,
+//!         ..Default::default()
+//!     }],
+//!     ..Default::default()
+//! };
+//!
+//! let config: Config = spec.into();
+//! ```
+//!
+//! With the `toml` or `json` feature enabled, a spec can also be loaded from a file
+//! with [`ConfigSpec::from_path`] or [`Config::from_path`].
+
+#[cfg(any(feature = "toml", feature = "json"))]
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use windows::Win32::UI::Input::KeyboardAndMouse::{VIRTUAL_KEY, VK__none_};
+
+#[cfg(any(feature = "toml", feature = "json"))]
+use crate::error::{Error, Result};
+use crate::{
+    event_handler::{Config, MenuTrigger, Side},
+    rules::{Action, Rule, RuleSet},
+};
+
+/// A serializable subset of [`Config`].
+///
+/// Rules are evaluated in order and the first matching rule decides the outcome.
+/// If no rule matches, the menu is suppressed using [`ConfigSpec::dummy_key`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigSpec {
+    /// Triggers to handle. Releases of any other trigger are never suppressed.
+    pub triggers: Vec<MenuTrigger>,
+    /// Virtual key code sent when a rule does not specify its own. Defaults to `VK__none_`.
+    pub dummy_key: u16,
+    /// Ordered list of rules.
+    pub rules: Vec<RuleSpec>,
+    /// Block `Alt+Space` inside the keyboard hook. See [`Config::set_block_alt_space`].
+    pub block_alt_space: bool,
+    /// Block `Shift+F10` inside the keyboard hook. See [`Config::set_block_shift_f10`].
+    pub block_shift_f10: bool,
+}
+
+impl Default for ConfigSpec {
+    fn default() -> Self {
+        Self {
+            triggers: vec![MenuTrigger::Win, MenuTrigger::Alt],
+            dummy_key: VK__none_.0,
+            rules: Vec::new(),
+            block_alt_space: false,
+            block_shift_f10: false,
+        }
+    }
+}
+
+/// A single rule of a [`ConfigSpec`].
+///
+/// All conditions that are set must hold for the rule to match.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSpec {
+    /// Matches only releases of this trigger.
+    pub trigger: Option<MenuTrigger>,
+    /// Matches only releases of the left or right key.
+    pub side: Option<Side>,
+    /// Matches only if the key was held for at least this many milliseconds.
+    pub min_hold_ms: Option<u64>,
+    /// Matches only if the key was held for at most this many milliseconds.
+    pub max_hold_ms: Option<u64>,
+    /// Matches only if the foreground process has this executable name (case-insensitive),
+    /// e.g. `"notepad.exe"`.
+    pub process: Option<String>,
+    /// Whether to suppress the menu when this rule matches.
+    pub suppress: bool,
+    /// Virtual key code to send instead of [`ConfigSpec::dummy_key`].
+    pub dummy_key: Option<u16>,
+}
+
+impl Default for RuleSpec {
+    fn default() -> Self {
+        Self {
+            trigger: None,
+            side: None,
+            min_hold_ms: None,
+            max_hold_ms: None,
+            process: None,
+            suppress: true,
+            dummy_key: None,
+        }
+    }
+}
+
+impl RuleSpec {
+    /// Converts into the [`Rule`] evaluated by the handler, falling back to `dummy_key` if
+    /// the rule does not specify its own.
+    fn into_rule(self, dummy_key: u16) -> Rule {
+        let action = if self.suppress {
+            Action::Suppress(VIRTUAL_KEY(self.dummy_key.unwrap_or(dummy_key)))
+        } else {
+            Action::PassThrough
+        };
+        Rule {
+            trigger: self.trigger,
+            side: self.side,
+            min_hold: self.min_hold_ms.map(Duration::from_millis),
+            max_hold: self.max_hold_ms.map(Duration::from_millis),
+            process: self.process,
+            action,
+        }
+    }
+}
+
+impl From<ConfigSpec> for RuleSet {
+    fn from(spec: ConfigSpec) -> Self {
+        let disabled = [MenuTrigger::Win, MenuTrigger::Alt]
+            .into_iter()
+            .filter(|t| !spec.triggers.contains(t))
+            .map(|t| Rule {
+                trigger: Some(t),
+                action: Action::PassThrough,
+                ..Default::default()
+            });
+
+        let rules = spec.rules.into_iter().map(|r| r.into_rule(spec.dummy_key));
+
+        Self {
+            rules: disabled.chain(rules).collect(),
+            default_action: Action::Suppress(VIRTUAL_KEY(spec.dummy_key)),
+        }
+    }
+}
+
+impl From<ConfigSpec> for Config {
+    fn from(spec: ConfigSpec) -> Self {
+        Config::default().set_spec(spec)
+    }
+}
+
+impl Config {
+    /// Applies the options described by `spec`, keeping all options a [`ConfigSpec`] does not
+    /// cover, such as observers or callbacks other than `on_released`.
+    ///
+    /// # Returns
+    /// A modified [`Config`] instance (builder pattern).
+    pub fn set_spec(self, spec: ConfigSpec) -> Self {
+        self.set_block_alt_space(spec.block_alt_space)
+            .set_block_shift_f10(spec.block_shift_f10)
+            .set_rules(spec.into())
+    }
+}
+
+#[cfg(any(feature = "toml", feature = "json"))]
+impl ConfigSpec {
+    /// Reads a spec from a TOML or JSON file.
+    ///
+    /// The format is chosen from the file extension (`.toml` or `.json`).
+    ///
+    /// # Errors
+    /// - Returns `Error::ConfigRead` if the file cannot be read.
+    /// - Returns `Error::ConfigParse` if the file contents are invalid.
+    /// - Returns `Error::UnsupportedConfigFormat` if the extension is unknown
+    ///   or the corresponding feature is disabled.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(Error::ConfigRead)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "toml")]
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => {
+                toml::from_str(&text).map_err(|e| Error::ConfigParse(e.to_string()))
+            }
+            #[cfg(feature = "json")]
+            Some(ext) if ext.eq_ignore_ascii_case("json") => {
+                serde_json::from_str(&text).map_err(|e| Error::ConfigParse(e.to_string()))
+            }
+            _ => Err(Error::UnsupportedConfigFormat(path.to_owned())),
+        }
+    }
+}
+
+#[cfg(any(feature = "toml", feature = "json"))]
+impl Config {
+    /// Builds a [`Config`] from a TOML or JSON file describing a [`ConfigSpec`].
+    ///
+    /// See [`ConfigSpec::from_path`] for the supported formats and errors.
+    ///
+    /// # Example
+    /// ```toml
+    /// triggers = ["Win", "Alt"]
+    ///
+    /// [[rules]]
+    /// trigger = "Alt"
+    /// max_hold_ms = 300
+    /// suppress = false
+    ///
+    /// [[rules]]
+    /// process = "game.exe"
+    /// suppress = false
+    /// ```
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        ConfigSpec::from_path(path).map(Into::into)
+    }
+}
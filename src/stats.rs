@@ -0,0 +1,458 @@
+//! Statistics about the event handling of this process.
+//!
+//! The event handler records how long each event took from the hook procedure until the
+//! handler was done with it. If this latency grows, the menu may briefly appear before the
+//! dummy key arrives; [`latency`] shows whether the handler keeps up.
+//!
+//! ```no_run
+//! let stats = prevent_alt_win_menu::stats::latency();
+//! println!("p99 latency over {} events: {:?}", stats.count, stats.p99);
+//! ```
+//!
+//! With [`Config::set_collect_stats`](crate::event_handler::Config::set_collect_stats),
+//! the handler also counts what it did, see [`counters`].
+//!
+//! With the `metrics` feature, the handler emits the following through the [`metrics`]
+//! facade. This is independent of `set_collect_stats`, which only controls the counters of
+//! this module:
+//!
+//! - `paw.suppressed`: counter of suppressed menus, labeled with `trigger` (`win`, `alt`).
+//! - `paw.hold_duration_ms`: histogram of uninterrupted hold durations, labeled with
+//!   `trigger`.
+//! - `paw.injection_errors`: counter of failed injections.
+//!
+//! Latency recording is lock-free and does not allocate.
+
+use std::{
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::event_handler::MenuTrigger;
+
+static LATENCY: Histogram = Histogram::new();
+static RATE_LIMITED: AtomicU64 = AtomicU64::new(0);
+
+static EVENTS: AtomicU64 = AtomicU64::new(0);
+static WIN_SUPPRESSIONS: AtomicU64 = AtomicU64::new(0);
+static ALT_SUPPRESSIONS: AtomicU64 = AtomicU64::new(0);
+static PASS_THROUGHS: AtomicU64 = AtomicU64::new(0);
+static INJECTION_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// A snapshot of what the event handler did, returned by [`counters`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    /// The number of events handled.
+    pub events: u64,
+    /// The number of `Win` releases whose menu was suppressed.
+    pub win_suppressions: u64,
+    /// The number of `Alt` releases whose menu was suppressed.
+    pub alt_suppressions: u64,
+    /// The number of releases passed through without suppression, e.g. because the hold
+    /// was interrupted or outside the schedule.
+    pub pass_throughs: u64,
+    /// The number of injections that failed.
+    pub injection_failures: u64,
+    /// The number of suppressions skipped because of the rate limit, see [`rate_limited`].
+    pub rate_limited: u64,
+}
+
+impl Stats {
+    /// Returns the number of suppressions of `trigger`.
+    pub fn suppressions_of(&self, trigger: MenuTrigger) -> u64 {
+        match trigger {
+            MenuTrigger::Win => self.win_suppressions,
+            MenuTrigger::Alt => self.alt_suppressions,
+        }
+    }
+
+    /// Returns the number of suppressions of all triggers.
+    pub fn suppressions(&self) -> u64 {
+        self.win_suppressions + self.alt_suppressions
+    }
+}
+
+/// Returns the counters collected since the start or the last [`reset_counters`].
+///
+/// They stay zero unless
+/// [`Config::set_collect_stats`](crate::event_handler::Config::set_collect_stats) is enabled.
+pub fn counters() -> Stats {
+    Stats {
+        events: EVENTS.load(Ordering::Relaxed),
+        win_suppressions: WIN_SUPPRESSIONS.load(Ordering::Relaxed),
+        alt_suppressions: ALT_SUPPRESSIONS.load(Ordering::Relaxed),
+        pass_throughs: PASS_THROUGHS.load(Ordering::Relaxed),
+        injection_failures: INJECTION_FAILURES.load(Ordering::Relaxed),
+        rate_limited: RATE_LIMITED.load(Ordering::Relaxed),
+    }
+}
+
+/// Resets all counters of [`counters`] and the [`hold_durations`] to zero.
+pub fn reset_counters() {
+    for counter in [
+        &EVENTS,
+        &WIN_SUPPRESSIONS,
+        &ALT_SUPPRESSIONS,
+        &PASS_THROUGHS,
+        &INJECTION_FAILURES,
+        &RATE_LIMITED,
+    ] {
+        counter.store(0, Ordering::Relaxed);
+    }
+    let mut histogram = HOLD_DURATIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    histogram.win.fill(0);
+    histogram.alt.fill(0);
+}
+
+/// The default bounds of [`HoldDurations`], in milliseconds.
+pub(crate) const DEFAULT_HOLD_BUCKETS: [u64; 8] = [50, 100, 150, 200, 300, 500, 1000, 2000];
+
+static HOLD_DURATIONS: Mutex<HoldDurations> = Mutex::new(HoldDurations {
+    bounds: Vec::new(),
+    win: Vec::new(),
+    alt: Vec::new(),
+});
+
+/// A histogram of how long trigger keys were held, returned by [`hold_durations`].
+///
+/// Only holds without other keys pressed in between are recorded, i.e. the holds that are
+/// candidates for taps. Use it to choose
+/// [`Config::set_tap_threshold`](crate::event_handler::Config::set_tap_threshold) from
+/// your own typing instead of guessing.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HoldDurations {
+    /// The upper bounds of the buckets, ascending, see
+    /// [`Config::set_hold_duration_buckets`](crate::event_handler::Config::set_hold_duration_buckets).
+    pub bounds: Vec<Duration>,
+    /// The number of `Win` holds per bucket. The last element counts holds longer than the
+    /// last bound.
+    pub win: Vec<u64>,
+    /// The number of `Alt` holds per bucket, like [`HoldDurations::win`].
+    pub alt: Vec<u64>,
+}
+
+impl HoldDurations {
+    /// Returns the counts of `trigger`, one per bound and one for longer holds.
+    pub fn counts_of(&self, trigger: MenuTrigger) -> &[u64] {
+        match trigger {
+            MenuTrigger::Win => &self.win,
+            MenuTrigger::Alt => &self.alt,
+        }
+    }
+
+    /// Returns the buckets of `trigger` as pairs of their upper bound and count. The bound
+    /// of the last bucket, which counts longer holds, is `None`.
+    pub fn buckets(&self, trigger: MenuTrigger) -> impl Iterator<Item = (Option<Duration>, u64)> {
+        let bounds = self.bounds.iter().copied().map(Some).chain([None]);
+        bounds.zip(self.counts_of(trigger).iter().copied())
+    }
+}
+
+/// Returns the hold durations recorded since the start or the last [`reset_counters`].
+///
+/// Stays empty unless
+/// [`Config::set_collect_stats`](crate::event_handler::Config::set_collect_stats) is enabled.
+pub fn hold_durations() -> HoldDurations {
+    HOLD_DURATIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Records a hold of `trigger` for `held`, sorted into buckets with the upper `bounds`.
+///
+/// The metric is always emitted; the histogram of [`hold_durations`] is updated only if
+/// `collect` is set.
+pub(crate) fn record_hold(
+    trigger: MenuTrigger,
+    held: Duration,
+    bounds: &[Duration],
+    collect: bool,
+) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!("paw.hold_duration_ms", "trigger" => trigger_label(trigger))
+        .record(held.as_secs_f64() * 1000.0);
+    if !collect {
+        return;
+    }
+
+    let mut histogram = HOLD_DURATIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if histogram.bounds != bounds {
+        *histogram = HoldDurations {
+            bounds: bounds.to_vec(),
+            win: vec![0; bounds.len() + 1],
+            alt: vec![0; bounds.len() + 1],
+        };
+    }
+    let bucket = bounds.partition_point(|&bound| bound < held);
+    match trigger {
+        MenuTrigger::Win => histogram.win[bucket] += 1,
+        MenuTrigger::Alt => histogram.alt[bucket] += 1,
+    }
+}
+
+pub(crate) fn record_event() {
+    EVENTS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records a suppression of `trigger`, counting it only if `collect` is set.
+pub(crate) fn record_suppression(trigger: MenuTrigger, collect: bool) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("paw.suppressed", "trigger" => trigger_label(trigger)).increment(1);
+    if !collect {
+        return;
+    }
+    match trigger {
+        MenuTrigger::Win => &WIN_SUPPRESSIONS,
+        MenuTrigger::Alt => &ALT_SUPPRESSIONS,
+    }
+    .fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_pass_through() {
+    PASS_THROUGHS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records a failed injection, counting it only if `collect` is set.
+pub(crate) fn record_injection_failure(collect: bool) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("paw.injection_errors").increment(1);
+    if collect {
+        INJECTION_FAILURES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "metrics")]
+fn trigger_label(trigger: MenuTrigger) -> &'static str {
+    match trigger {
+        MenuTrigger::Win => "win",
+        MenuTrigger::Alt => "alt",
+    }
+}
+
+/// Percentiles of the time from the hook procedure receiving an event to the handler
+/// finishing it.
+///
+/// Percentiles are upper bounds with a relative error of at most 25%.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencyStats {
+    /// The number of events recorded.
+    pub count: u64,
+    /// The mean latency.
+    pub mean: Duration,
+    /// The median latency.
+    pub p50: Duration,
+    /// The 90th percentile.
+    pub p90: Duration,
+    /// The 99th percentile.
+    pub p99: Duration,
+    /// The highest latency recorded.
+    pub max: Duration,
+}
+
+/// Returns the latency statistics of all events handled since the start or the last
+/// [`reset_latency`].
+///
+/// Events of backends without timestamps (see
+/// [`MenuTriggerEvent::received_at`](crate::event_handler::MenuTriggerEvent::received_at))
+/// are not recorded.
+pub fn latency() -> LatencyStats {
+    let percentile = |q| Duration::from_micros(LATENCY.percentile(q));
+    let count = LATENCY.count();
+    LatencyStats {
+        count,
+        mean: Duration::from_micros(LATENCY.sum() / count.max(1)),
+        p50: percentile(0.5),
+        p90: percentile(0.9),
+        p99: percentile(0.99),
+        max: Duration::from_micros(LATENCY.max()),
+    }
+}
+
+/// Discards all recorded latencies.
+pub fn reset_latency() {
+    LATENCY.reset();
+}
+
+/// Returns the number of suppressions skipped because of
+/// [`Config::set_injection_rate_limit`](crate::event_handler::Config::set_injection_rate_limit).
+pub fn rate_limited() -> u64 {
+    RATE_LIMITED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn record_rate_limited() {
+    RATE_LIMITED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records the latency of an event received by the hook at `received_at`.
+pub(crate) fn record_latency(received_at: Instant) {
+    LATENCY.record(received_at.elapsed().as_micros() as u64);
+}
+
+/// The number of buckets: four per power of two of a `u64`.
+const BUCKETS: usize = 252;
+
+/// A lock-free histogram of `u64` values with four sub-buckets per power of two.
+pub(crate) struct Histogram {
+    buckets: [AtomicU64; BUCKETS],
+    sum: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Histogram {
+    pub(crate) const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; BUCKETS],
+            sum: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn record(&self, value: u64) {
+        self.buckets[bucket_of(value)].fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value, Ordering::Relaxed);
+        self.max.fetch_max(value, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count(&self) -> u64 {
+        self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).sum()
+    }
+
+    pub(crate) fn sum(&self) -> u64 {
+        self.sum.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn max(&self) -> u64 {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    /// Returns an upper bound of the `q` quantile (`0.0..=1.0`), or `0` if nothing was recorded.
+    pub(crate) fn percentile(&self, q: f64) -> u64 {
+        let target = (q * self.count() as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            if seen >= target {
+                return upper_bound(index).min(self.max());
+            }
+        }
+        0
+    }
+
+    pub(crate) fn reset(&self) {
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+        self.sum.store(0, Ordering::Relaxed);
+        self.max.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Values below 4 have a bucket each; above, each power of two is split into four.
+fn bucket_of(value: u64) -> usize {
+    if value < 4 {
+        return value as usize;
+    }
+    let exp = 63 - value.leading_zeros() as usize;
+    let sub = (value >> (exp - 2)) as usize & 3;
+    4 * (exp - 1) + sub
+}
+
+fn upper_bound(index: usize) -> u64 {
+    if index < 4 {
+        return index as u64;
+    }
+    let (exp, sub) = (index / 4 + 1, (index % 4) as u64);
+    let lower = (4 + sub) << (exp - 2);
+    lower + ((1 << (exp - 2)) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_values_have_their_own_buckets() {
+        for value in 0..4 {
+            assert_eq!(bucket_of(value), value as usize);
+            assert_eq!(upper_bound(value as usize), value);
+        }
+        assert_eq!(bucket_of(4), 4);
+        assert_eq!(upper_bound(4), 4);
+        assert_eq!(bucket_of(8), 8);
+        assert_eq!(bucket_of(9), 8);
+        assert_eq!(upper_bound(8), 9);
+    }
+
+    #[test]
+    fn buckets_are_contiguous_up_to_u64_max() {
+        assert_eq!(bucket_of(u64::MAX), BUCKETS - 1);
+        assert_eq!(upper_bound(BUCKETS - 1), u64::MAX);
+        for index in 0..BUCKETS - 1 {
+            let bound = upper_bound(index);
+            assert_eq!(bucket_of(bound), index);
+            assert_eq!(bucket_of(bound + 1), index + 1);
+        }
+    }
+
+    #[test]
+    fn upper_bounds_are_within_25_percent() {
+        for value in [4, 5, 17, 100, 1_000, 123_456, 1 << 40, u64::MAX / 3] {
+            let bound = upper_bound(bucket_of(value));
+            assert!(bound >= value);
+            assert!(bound - value <= value / 4, "{value} -> {bound}");
+        }
+    }
+
+    #[test]
+    fn percentile_is_an_upper_bound_capped_by_max() {
+        let histogram = Histogram::new();
+        assert_eq!(histogram.percentile(0.5), 0);
+
+        for value in 1..=100 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.count(), 100);
+        assert_eq!(histogram.sum(), 5050);
+        assert_eq!(histogram.percentile(0.0), 1);
+        // The 50th value falls into the bucket 48..=55.
+        assert_eq!(histogram.percentile(0.5), 55);
+        assert_eq!(histogram.percentile(1.0), 100);
+
+        histogram.reset();
+        assert_eq!(histogram.count(), 0);
+        assert_eq!(histogram.percentile(0.5), 0);
+    }
+
+    #[test]
+    fn record_hold_resets_when_the_bounds_change() {
+        let ms = Duration::from_millis;
+        // Unusual bounds, so that the histogram starts from zero.
+        let bounds = [ms(101), ms(203)];
+        record_hold(MenuTrigger::Win, ms(150), &bounds, true);
+        record_hold(MenuTrigger::Win, ms(101), &bounds, true);
+        record_hold(MenuTrigger::Alt, ms(250), &bounds, true);
+        record_hold(MenuTrigger::Alt, ms(250), &bounds, false);
+
+        let durations = hold_durations();
+        assert_eq!(durations.bounds, bounds);
+        assert_eq!(durations.counts_of(MenuTrigger::Win), [1, 1, 0]);
+        assert_eq!(durations.counts_of(MenuTrigger::Alt), [0, 0, 1]);
+
+        let bounds = [ms(57)];
+        record_hold(MenuTrigger::Win, ms(10), &bounds, true);
+
+        let durations = hold_durations();
+        assert_eq!(durations.bounds, bounds);
+        assert_eq!(durations.counts_of(MenuTrigger::Win), [1, 0]);
+        assert_eq!(durations.counts_of(MenuTrigger::Alt), [0, 0]);
+    }
+}
@@ -0,0 +1,147 @@
+//! Techniques for suppressing the menu once the decision to do so has been made.
+//!
+//! The event handler decides *whether* to suppress through `on_released` and then delegates
+//! *how* to a [`SuppressionStrategy`]. The default, [`DummyKeyUp`], sends a key-up event of
+//! the dummy key. Implement the trait to plug in alternative techniques without forking the
+//! handler.
+
+use std::io;
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{VIRTUAL_KEY, VK__none_, VK_LCONTROL};
+
+use crate::event_handler::{
+    HoldEvent, KeyboardEvent, send_key_tap, send_keydown, send_keyup, send_keyup_scancode,
+};
+
+/// A technique for suppressing the menu after a trigger key has been released.
+pub trait SuppressionStrategy<T = KeyboardEvent>: Send + Sync {
+    /// Called when a trigger key is pressed, before it is known whether the menu
+    /// will be suppressed. The default implementation does nothing.
+    fn on_press(&self, _press: &T) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Returns whether [`SuppressionStrategy::on_press`] injects input, which must then be
+    /// completed on release whatever the decision. The default implementation returns `false`.
+    ///
+    /// The injection rate limit is applied once per hold: on press if this returns `true`,
+    /// otherwise when the menu is suppressed.
+    fn injects_on_press(&self) -> bool {
+        false
+    }
+
+    /// Suppresses the menu for the given hold.
+    ///
+    /// `dummy_key` is the key chosen by `on_released` and the configured
+    /// [`DummyKeyStrategy`](crate::event_handler::DummyKeyStrategy).
+    /// Strategies that do not inject a key may ignore it.
+    fn suppress(&self, hold: &HoldEvent<T>, dummy_key: VIRTUAL_KEY) -> io::Result<()>;
+
+    /// Called instead of [`SuppressionStrategy::suppress`] when `on_released` decided not to
+    /// suppress the menu. The default implementation does nothing.
+    ///
+    /// If [`SuppressionStrategy::injects_on_press`] returns `true`, this is also called for
+    /// holds discarded before their release, for example when a system chord is recognized or
+    /// the foreground window changes. The release of such a hold is then its press event.
+    fn pass_through(&self, _hold: &HoldEvent<T>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sends a key-up event of the dummy key right after the trigger key is released.
+///
+/// Windows then treats the release as part of a hotkey sequence and does not show the menu.
+/// This is the default strategy.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DummyKeyUp;
+
+impl<T> SuppressionStrategy<T> for DummyKeyUp {
+    fn suppress(&self, _hold: &HoldEvent<T>, dummy_key: VIRTUAL_KEY) -> io::Result<()> {
+        send_keyup(dummy_key)
+    }
+}
+
+/// Like [`DummyKeyUp`], but injects a scan code (`KEYEVENTF_SCANCODE`) instead of a virtual key.
+///
+/// Some games and other low-level consumers only honor scan-code input. The `dummy_key`
+/// chosen by `on_released` is ignored. The default scan code is `0x76` (`F24`).
+#[derive(Debug, Clone, Copy)]
+pub struct ScanCodeKeyUp {
+    /// The scan code to release, see [`send_keyup_scancode`].
+    pub scan: u16,
+}
+
+impl Default for ScanCodeKeyUp {
+    fn default() -> Self {
+        Self { scan: 0x76 }
+    }
+}
+
+impl<T> SuppressionStrategy<T> for ScanCodeKeyUp {
+    fn suppress(&self, _hold: &HoldEvent<T>, _dummy_key: VIRTUAL_KEY) -> io::Result<()> {
+        send_keyup_scancode(self.scan)
+    }
+}
+
+/// Presses the dummy key as soon as the trigger key goes down and releases it after the
+/// trigger key is released.
+///
+/// Because Windows sees another key while the trigger is held, the menu is suppressed more
+/// reliably on some systems than with [`DummyKeyUp`]. The decision of `on_released` cannot
+/// undo the early key-down, so the menu is suppressed even when it returns `None`; combine
+/// this strategy with [`Config::set_strategy_for`] to apply it to specific triggers only.
+///
+/// [`Config::set_strategy_for`]: crate::event_handler::Config::set_strategy_for
+#[derive(Debug, Clone, Copy)]
+pub struct PreemptiveKeyDown {
+    /// The key pressed while the trigger is held.
+    pub key: VIRTUAL_KEY,
+}
+
+impl Default for PreemptiveKeyDown {
+    fn default() -> Self {
+        Self { key: VK__none_ }
+    }
+}
+
+impl<T> SuppressionStrategy<T> for PreemptiveKeyDown {
+    fn on_press(&self, _press: &T) -> io::Result<()> {
+        send_keydown(self.key)
+    }
+
+    fn injects_on_press(&self) -> bool {
+        true
+    }
+
+    fn suppress(&self, _hold: &HoldEvent<T>, _dummy_key: VIRTUAL_KEY) -> io::Result<()> {
+        send_keyup(self.key)
+    }
+
+    fn pass_through(&self, _hold: &HoldEvent<T>) -> io::Result<()> {
+        send_keyup(self.key)
+    }
+}
+
+/// Taps a real, innocuous modifier key (left Ctrl by default) after the trigger key is released,
+/// instead of sending the reserved `VK__none_`.
+///
+/// Some RDP and virtual-machine environments swallow reserved virtual keys, which makes
+/// [`DummyKeyUp`] ineffective there. A Ctrl tap is forwarded like any real key press.
+/// The `dummy_key` chosen by `on_released` is ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct ModifierTap {
+    /// The modifier key to tap.
+    pub key: VIRTUAL_KEY,
+}
+
+impl Default for ModifierTap {
+    fn default() -> Self {
+        Self { key: VK_LCONTROL }
+    }
+}
+
+impl<T> SuppressionStrategy<T> for ModifierTap {
+    fn suppress(&self, _hold: &HoldEvent<T>, _dummy_key: VIRTUAL_KEY) -> io::Result<()> {
+        send_key_tap(self.key)
+    }
+}
@@ -0,0 +1,190 @@
+//! Keyboard events as a runtime-agnostic [`Stream`] (`stream` feature).
+//!
+//! [`EventStream`] implements [`futures_core::Stream`] and wakes the polling task directly
+//! from the hook thread, so it works with any executor, e.g. smol or async-std:
+//!
+//! ```no_run
+//! use std::{future::poll_fn, pin::pin};
+//!
+//! use futures_core::Stream;
+//!
+//! # async fn run() -> prevent_alt_win_menu::error::Result<()> {
+//! let (events, hook) = prevent_alt_win_menu::stream::start_event_stream()?;
+//! let mut holds = pin!(events.holds());
+//! // Or `holds.next().await` with the `StreamExt` of `futures` or `futures-lite`.
+//! while let Some(hold) = poll_fn(|cx| holds.as_mut().poll_next(cx)).await {
+//!     println!("{} released", hold.trigger);
+//! }
+//! hook.shutdown().ok();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Events are queued as for [`crate::keyboard_hook::start_keyboard_hook`], so the hook
+//! procedure does not allocate or wait for the task.
+
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use futures_core::Stream;
+
+use crate::{
+    error::Result,
+    event_handler::{HoldEvent, HoldStates, KeyboardEvent, MenuTriggerEvent, TrackingOptions},
+    health,
+    keyboard_hook::{self, HookOptions, HookThread},
+    queue::{self, Consumer, PopError, Producer},
+};
+
+/// Starts a global keyboard hook whose events are received from an [`EventStream`].
+///
+/// # Errors
+/// Same as [`keyboard_hook::start_keyboard_hook`].
+pub fn start_event_stream() -> Result<(EventStream, HookThread)> {
+    start_event_stream_with(HookOptions::default())
+}
+
+/// Like [`start_event_stream`], with [`HookOptions`] applied inside the hook.
+///
+/// # Errors
+/// Same as [`keyboard_hook::start_keyboard_hook`].
+pub fn start_event_stream_with(options: HookOptions) -> Result<(EventStream, HookThread)> {
+    let (producer, consumer) = queue::queue();
+    let waker = Arc::new(WakerSlot::default());
+    let sender = StreamSender {
+        queue: producer,
+        waker: WakeOnDrop(waker.clone()),
+    };
+    let hook = keyboard_hook::start_keyboard_hook_callback(options, move |event| {
+        health::hook_event(sender.queue.push(event));
+        sender.waker.0.wake();
+    })?;
+    Ok((
+        EventStream {
+            queue: consumer,
+            waker,
+        },
+        hook,
+    ))
+}
+
+/// The task waiting for events, if any.
+#[derive(Default)]
+struct WakerSlot(Mutex<Option<Waker>>);
+
+impl WakerSlot {
+    /// Wakes the waiting task, without blocking.
+    ///
+    /// If the slot is locked, the task is registering right now and checks the queue
+    /// afterwards, so it is not woken here.
+    fn wake(&self) {
+        if let Ok(mut waker) = self.0.try_lock()
+            && let Some(waker) = waker.take()
+        {
+            waker.wake();
+        }
+    }
+
+    fn register(&self, waker: &Waker) {
+        let mut slot = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        match &mut *slot {
+            Some(current) if current.will_wake(waker) => {}
+            slot => *slot = Some(waker.clone()),
+        }
+    }
+}
+
+/// The hook's end of an [`EventStream`]. Ends the stream when the hook thread drops it.
+struct StreamSender {
+    // Dropped in this order: the queue is closed before the task is woken.
+    queue: Producer<KeyboardEvent>,
+    waker: WakeOnDrop,
+}
+
+struct WakeOnDrop(Arc<WakerSlot>);
+
+impl Drop for WakeOnDrop {
+    fn drop(&mut self) {
+        self.0.wake();
+    }
+}
+
+/// A [`Stream`] of the events of a keyboard hook started with [`start_event_stream`].
+///
+/// The stream ends when the hook thread exits. Events are filtered like
+/// [`KeyboardEvents`](crate::keyboard_hook::KeyboardEvents) does.
+pub struct EventStream {
+    queue: Consumer<KeyboardEvent>,
+    waker: Arc<WakerSlot>,
+}
+
+impl EventStream {
+    /// Turns the stream into a stream of [`HoldEvent`]s, yielded whenever a trigger key is
+    /// released.
+    ///
+    /// Holds are tracked as by [`EventIteratorExt::holds`](crate::adapters::EventIteratorExt::holds).
+    pub fn holds(self) -> HoldStream {
+        HoldStream {
+            events: self,
+            states: HoldStates::default(),
+        }
+    }
+
+    /// Takes the next event without waiting, or `Err(PopError::Closed)` once the hook has
+    /// exited.
+    fn try_next(&self) -> std::result::Result<KeyboardEvent, PopError> {
+        let event = self.queue.pop_timeout(Duration::ZERO)?;
+        health::handler_event(event.timestamp);
+        Ok(event)
+    }
+}
+
+impl Stream for EventStream {
+    type Item = KeyboardEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<KeyboardEvent>> {
+        match self.try_next() {
+            Ok(event) => return Poll::Ready(Some(event)),
+            Err(PopError::Closed) => return Poll::Ready(None),
+            Err(PopError::Timeout) => {}
+        }
+
+        self.waker.register(cx.waker());
+        // An event may have arrived while registering.
+        match self.try_next() {
+            Ok(event) => Poll::Ready(Some(event)),
+            Err(PopError::Closed) => Poll::Ready(None),
+            Err(PopError::Timeout) => Poll::Pending,
+        }
+    }
+}
+
+/// A [`Stream`] of [`HoldEvent`]s, returned by [`EventStream::holds`].
+pub struct HoldStream {
+    events: EventStream,
+    states: HoldStates<KeyboardEvent>,
+}
+
+impl Stream for HoldStream {
+    type Item = HoldEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<HoldEvent>> {
+        let this = &mut *self;
+        loop {
+            let Some(event) = std::task::ready!(Pin::new(&mut this.events).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            let trigger = event.menu_trigger();
+            if let Some(hold) = this
+                .states
+                .update(event, trigger, TrackingOptions::default())
+            {
+                return Poll::Ready(Some(hold));
+            }
+        }
+    }
+}
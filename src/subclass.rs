@@ -0,0 +1,142 @@
+//! In-process suppression of the menu for the caller's own windows, without any hook.
+//!
+//! Pressing and releasing Alt (or F10) makes `DefWindowProc` send `WM_SYSCOMMAND` with
+//! `SC_KEYMENU`, which activates the menu bar. Subclassing a window and swallowing that
+//! command stops the menu in that window only. No global hook is installed and no input is
+//! injected, so this is a lighter alternative for applications that only care about their
+//! own UI. The Start menu opened by the Windows key is not affected.
+//!
+//! All functions must be called on the thread that created the window.
+//!
+//! With the `raw-window-handle` feature, [`prevent_menu_in`] accepts any window that
+//! implements [`HasWindowHandle`](raw_window_handle::HasWindowHandle), such as a winit,
+//! egui or tauri window:
+//!
+//! ```ignore
+//! prevent_alt_win_menu::subclass::prevent_menu_in(&window, Default::default())?;
+//! ```
+
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    UI::{
+        Shell::{DefSubclassProc, RemoveWindowSubclass, SetWindowSubclass},
+        WindowsAndMessaging::{SC_KEYMENU, WM_NCDESTROY, WM_SYSCOMMAND},
+    },
+};
+
+use crate::error::{Error, Result};
+
+const SUBCLASS_ID: usize = 0x5041_574D;
+
+/// Options for [`prevent_menu_in_window`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubclassOptions {
+    /// Also swallow `Alt+Space`, which opens the window (system) menu.
+    pub block_alt_space: bool,
+}
+
+/// Subclasses `hwnd` so that releasing Alt or F10 no longer activates its menu bar.
+///
+/// Calling this again for the same window replaces the options.
+///
+/// # Errors
+/// Returns `Error::SubclassFailed` if the window cannot be subclassed, e.g. because it
+/// belongs to another thread.
+pub fn prevent_menu_in_window(hwnd: HWND, options: SubclassOptions) -> Result<()> {
+    let ok = unsafe {
+        SetWindowSubclass(
+            hwnd,
+            Some(subclass_proc),
+            SUBCLASS_ID,
+            options.block_alt_space as usize,
+        )
+    };
+    if ok.as_bool() {
+        Ok(())
+    } else {
+        Err(Error::SubclassFailed)
+    }
+}
+
+/// Subclasses the window behind a [`RawWindowHandle`](raw_window_handle::RawWindowHandle).
+///
+/// See [`prevent_menu_in_window`].
+///
+/// # Errors
+/// Returns `Error::UnsupportedWindowHandle` if the handle is not a Win32 handle, or
+/// `Error::SubclassFailed` if the window cannot be subclassed.
+#[cfg(feature = "raw-window-handle")]
+pub fn prevent_menu_in_raw_window(
+    handle: raw_window_handle::RawWindowHandle,
+    options: SubclassOptions,
+) -> Result<()> {
+    match handle {
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            prevent_menu_in_window(HWND(handle.hwnd.get() as *mut _), options)
+        }
+        _ => Err(Error::UnsupportedWindowHandle),
+    }
+}
+
+/// Subclasses a window from a windowing library, e.g. a winit window.
+///
+/// See [`prevent_menu_in_window`].
+///
+/// # Errors
+/// Returns `Error::UnsupportedWindowHandle` if the window handle is unavailable or not a
+/// Win32 handle, or `Error::SubclassFailed` if the window cannot be subclassed.
+#[cfg(feature = "raw-window-handle")]
+pub fn prevent_menu_in(
+    window: &impl raw_window_handle::HasWindowHandle,
+    options: SubclassOptions,
+) -> Result<()> {
+    let handle = window
+        .window_handle()
+        .map_err(|_| Error::UnsupportedWindowHandle)?;
+    prevent_menu_in_raw_window(handle.as_raw(), options)
+}
+
+/// Removes the subclass installed by [`prevent_menu_in_window`].
+///
+/// # Errors
+/// Returns `Error::SubclassFailed` if the window was not subclassed.
+pub fn allow_menu_in_window(hwnd: HWND) -> Result<()> {
+    let ok = unsafe { RemoveWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID) };
+    if ok.as_bool() {
+        Ok(())
+    } else {
+        Err(Error::SubclassFailed)
+    }
+}
+
+unsafe extern "system" fn subclass_proc(
+    hwnd: HWND,
+    msg: u32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+    _id: usize,
+    block_alt_space: usize,
+) -> LRESULT {
+    match msg {
+        WM_SYSCOMMAND if (w_param.0 & 0xFFF0) as u32 == SC_KEYMENU => {
+            // `l_param` is the character that accompanied the key: 0 for a bare Alt or F10,
+            // ' ' for Alt+Space, or a mnemonic such as 'F' for Alt+F.
+            let blocked = match l_param.0 {
+                0 => true,
+                0x20 => block_alt_space != 0,
+                _ => false,
+            };
+            if blocked {
+                #[cfg(feature = "log")]
+                log::debug!("swallowed SC_KEYMENU for {:?}", hwnd);
+                return LRESULT(0);
+            }
+        }
+        WM_NCDESTROY => unsafe {
+            let _ = RemoveWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID);
+        },
+        _ => {}
+    }
+
+    unsafe { DefSubclassProc(hwnd, msg, w_param, l_param) }
+}
@@ -0,0 +1,57 @@
+//! Splitting one event source into several receivers.
+//!
+//! [`tee`] lets code that already consumes the events of a hook add another consumer, such
+//! as logging or statistics, without restructuring:
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{event_handler::{Config, start_event_handler}, tee::tee};
+//!
+//! let (events, _hook) = prevent_alt_win_menu::keyboard_hook::start_keyboard_hook()?;
+//! let [handler_rx, log_rx] = tee(events);
+//! start_event_handler(handler_rx, Config::default());
+//! for event in log_rx {
+//!     println!("{:?} {:?}", event.kbd.vkCode, event.wm_key_state);
+//! }
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! To receive the events of a running [`HookThread`](crate::keyboard_hook::HookThread)
+//! without taking over its consumer, see
+//! [`HookThread::subscribe`](crate::keyboard_hook::HookThread::subscribe).
+
+use std::{sync::mpsc, thread};
+
+/// Forwards every item of `source` to `N` receivers, cloning it for each.
+///
+/// A thread takes items from `source` as they arrive and sends them on through unbounded
+/// channels, so a slow receiver does not hold back the others. It stops once `source` ends,
+/// which ends all receivers, or once every receiver has been dropped.
+pub fn tee<T, I, const N: usize>(source: I) -> [mpsc::Receiver<T>; N]
+where
+    T: Clone + Send + 'static,
+    I: IntoIterator<Item = T> + Send + 'static,
+{
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..N).map(|_| mpsc::channel()).unzip();
+    let mut senders: Vec<Option<mpsc::Sender<T>>> = senders.into_iter().map(Some).collect();
+
+    thread::spawn(move || {
+        for item in source {
+            for slot in &mut senders {
+                if let Some(tx) = slot
+                    && tx.send(item.clone()).is_err()
+                {
+                    *slot = None;
+                }
+            }
+            if senders.iter().all(Option::is_none) {
+                break;
+            }
+        }
+        #[cfg(feature = "log")]
+        log::debug!("tee stopped");
+    });
+
+    receivers
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("exactly N receivers were created"))
+}
@@ -0,0 +1,153 @@
+//! Keyboard hook limited to the calling thread (`WH_KEYBOARD`).
+//!
+//! Applications that only want to stop the Alt menu in their own windows do not need a
+//! system-wide low-level hook, which some antivirus heuristics flag. The hook installed here
+//! only sees keyboard messages retrieved by the calling thread's message loop, and delivers
+//! them as [`KeyboardEvent`]s through a channel, like [`crate::keyboard_hook`].
+//!
+//! The calling thread must pump messages (e.g. a GUI thread), and the returned [`ThreadHook`]
+//! must be kept alive for as long as the hook should stay installed.
+
+use std::{cell::RefCell, sync::mpsc, time::Instant};
+
+use windows::{
+    Win32::{
+        Foundation::{LPARAM, LRESULT, WPARAM},
+        System::Threading::GetCurrentThreadId,
+        UI::WindowsAndMessaging::{
+            CallNextHookEx, GetMessageExtraInfo, GetMessageTime, HC_ACTION, HHOOK, KBDLLHOOKSTRUCT,
+            KBDLLHOOKSTRUCT_FLAGS, LLKHF_ALTDOWN, LLKHF_EXTENDED, LLKHF_UP, SetWindowsHookExW,
+            WH_KEYBOARD,
+        },
+    },
+    core::Owned,
+};
+
+use crate::{
+    error::{Error, Result},
+    event_handler::{INJECTED_INPUT_TAG, KeyboardEvent, WmKeyState},
+};
+
+thread_local! {
+    static THREAD_HOOK_SENDER: RefCell<Option<mpsc::Sender<KeyboardEvent>>> =
+        const { RefCell::new(None) };
+}
+
+/// A keyboard hook installed on the current thread by [`start_thread_keyboard_hook`].
+///
+/// The hook is removed when this value is dropped.
+pub struct ThreadHook {
+    _hook: Owned<HHOOK>,
+}
+
+impl Drop for ThreadHook {
+    fn drop(&mut self) {
+        THREAD_HOOK_SENDER.with(|s| s.borrow_mut().take());
+    }
+}
+
+/// Installs a keyboard hook for the calling thread only.
+///
+/// Unlike [`crate::keyboard_hook::start_keyboard_hook`], no thread is spawned. Events are
+/// produced while the calling thread retrieves keyboard messages, so the thread must run a
+/// message loop. Pass the receiver to
+/// [`start_event_handler`](crate::event_handler::start_event_handler) to suppress the menu
+/// in the thread's windows.
+///
+/// # Errors
+/// Returns `Error::HookRegistrationFailed` if the hook fails to register, or if a thread
+/// hook is already installed on the calling thread.
+pub fn start_thread_keyboard_hook() -> Result<(mpsc::Receiver<KeyboardEvent>, ThreadHook)> {
+    let already_installed = THREAD_HOOK_SENDER.with(|s| s.borrow().is_some());
+    if already_installed {
+        return Err(Error::HookRegistrationFailed(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "a thread keyboard hook is already installed on this thread",
+        )));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    THREAD_HOOK_SENDER.with(|s| *s.borrow_mut() = Some(tx));
+
+    let hook =
+        unsafe { SetWindowsHookExW(WH_KEYBOARD, Some(keyboard_proc), None, GetCurrentThreadId()) };
+
+    match hook {
+        Ok(hook) => {
+            #[cfg(feature = "log")]
+            log::info!("registered thread keyboard hook");
+            Ok((
+                rx,
+                ThreadHook {
+                    _hook: unsafe { Owned::new(hook) },
+                },
+            ))
+        }
+        Err(e) => {
+            THREAD_HOOK_SENDER.with(|s| s.borrow_mut().take());
+            Err(Error::HookRegistrationFailed(e.into()))
+        }
+    }
+}
+
+unsafe extern "system" fn keyboard_proc(n_code: i32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    if n_code == HC_ACTION as i32 {
+        let mut event = event_from_params(w_param, l_param);
+        event.redact_if_private();
+
+        if event.kbd.dwExtraInfo != INJECTED_INPUT_TAG {
+            THREAD_HOOK_SENDER.with(|s| {
+                if let Some(sender) = s.borrow().as_ref()
+                    && let Err(_e) = sender.send(event)
+                {
+                    #[cfg(feature = "log")]
+                    log::error!("{}", _e);
+                }
+            });
+        }
+    }
+
+    unsafe { CallNextHookEx(None, n_code, w_param, l_param) }
+}
+
+/// Builds a [`KeyboardEvent`] from the keystroke message flags passed to a `WH_KEYBOARD` hook.
+///
+/// See [Keystroke Message Flags](https://learn.microsoft.com/en-us/windows/win32/inputdev/about-keyboard-input#keystroke-message-flags).
+fn event_from_params(w_param: WPARAM, l_param: LPARAM) -> KeyboardEvent {
+    let flags = l_param.0 as u32;
+    let extended = flags & (1 << 24) != 0;
+    let alt_down = flags & (1 << 29) != 0;
+    let was_down = flags & (1 << 30) != 0;
+    let up = flags & (1 << 31) != 0;
+
+    let mut ll_flags = KBDLLHOOKSTRUCT_FLAGS(0);
+    if extended {
+        ll_flags |= LLKHF_EXTENDED;
+    }
+    if alt_down {
+        ll_flags |= LLKHF_ALTDOWN;
+    }
+    if up {
+        ll_flags |= LLKHF_UP;
+    }
+
+    let wm_key_state = match (up, alt_down) {
+        (false, false) => WmKeyState::KeyDown,
+        (true, false) => WmKeyState::KeyUp,
+        (false, true) => WmKeyState::SysKeyDown,
+        (true, true) => WmKeyState::SysKeyUp,
+    };
+
+    KeyboardEvent {
+        kbd: KBDLLHOOKSTRUCT {
+            vkCode: w_param.0 as u32,
+            scanCode: (flags >> 16) & 0xFF,
+            flags: ll_flags,
+            time: unsafe { GetMessageTime() } as u32,
+            dwExtraInfo: unsafe { GetMessageExtraInfo() }.0 as usize,
+        },
+        wm_key_state,
+        timestamp: Instant::now(),
+        repeat: !up && was_down,
+    }
+}
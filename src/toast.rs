@@ -0,0 +1,105 @@
+//! Windows notifications about suppressed menus (`toast` feature).
+//!
+//! Users of a kiosk who press the Windows key and see nothing happen tend to assume the
+//! device is broken. [`notify`] shows a notification explaining that the menu is disabled,
+//! and is meant to be passed to
+//! [`Config::set_suppression_notice`](crate::event_handler::Config::set_suppression_notice),
+//! which limits it to the first few suppressions:
+//!
+//! ```no_run
+//! use prevent_alt_win_menu::{event_handler::Config, toast};
+//!
+//! let config = Config::default().set_suppression_notice(3, toast::notify);
+//! let _handles = prevent_alt_win_menu::start(config)?;
+//! # Ok::<(), prevent_alt_win_menu::error::Error>(())
+//! ```
+//!
+//! The notification is shown through a temporary notification area icon, which Windows 10
+//! and later display as a toast. It requires no registration of the application.
+
+use std::{thread, time::Duration};
+
+use windows::{
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+        UI::{
+            Shell::{
+                NIF_ICON, NIF_INFO, NIF_TIP, NIIF_INFO, NIM_ADD, NIM_DELETE, NOTIFYICONDATAW,
+                Shell_NotifyIconW,
+            },
+            WindowsAndMessaging::{DefWindowProcW, DestroyWindow, IDI_INFORMATION, LoadIconW},
+        },
+    },
+    core::w,
+};
+
+use crate::{event_handler::MenuTrigger, window::create_message_window};
+
+/// How long the notification icon stays, which bounds how long the toast is shown.
+const DISPLAY_DURATION: Duration = Duration::from_secs(10);
+
+/// Shows a notification that the menu of `trigger` is disabled on this device.
+///
+/// Returns immediately; the notification is shown from a short-lived thread.
+pub fn notify(trigger: MenuTrigger) {
+    let message = match trigger {
+        MenuTrigger::Win => "The Start menu is disabled on this device.",
+        MenuTrigger::Alt => "The menu bar is disabled on this device.",
+    };
+    show("Menu disabled", message);
+}
+
+/// Shows a notification with the given title and message.
+///
+/// Texts longer than the limits of Windows (63 characters for the title, 255 for the
+/// message) are truncated. Returns immediately; the notification is shown from a
+/// short-lived thread.
+pub fn show(title: &str, message: &str) {
+    let (title, message) = (title.to_owned(), message.to_owned());
+    thread::spawn(move || {
+        let Some(hwnd) = create_message_window(w!("PreventAltWinMenuToast"), Some(window_proc))
+        else {
+            #[cfg(feature = "log")]
+            log::warn!("failed to create the window for a notification");
+            return;
+        };
+
+        let mut data = NOTIFYICONDATAW {
+            cbSize: size_of::<NOTIFYICONDATAW>() as u32,
+            hWnd: hwnd,
+            uID: 1,
+            uFlags: NIF_ICON | NIF_TIP | NIF_INFO,
+            hIcon: unsafe { LoadIconW(None, IDI_INFORMATION) }.unwrap_or_default(),
+            dwInfoFlags: NIIF_INFO,
+            ..Default::default()
+        };
+        copy_truncated(&mut data.szTip, &title);
+        copy_truncated(&mut data.szInfoTitle, &title);
+        copy_truncated(&mut data.szInfo, &message);
+
+        if unsafe { Shell_NotifyIconW(NIM_ADD, &data) }.as_bool() {
+            thread::sleep(DISPLAY_DURATION);
+            let _ = unsafe { Shell_NotifyIconW(NIM_DELETE, &data) };
+        } else {
+            #[cfg(feature = "log")]
+            log::warn!("failed to show a notification");
+        }
+        let _ = unsafe { DestroyWindow(hwnd) };
+    });
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: u32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    unsafe { DefWindowProcW(hwnd, msg, w_param, l_param) }
+}
+
+/// Copies `text` into the null-terminated UTF-16 buffer `buffer`, truncating it if needed.
+fn copy_truncated(buffer: &mut [u16], text: &str) {
+    let units: Vec<u16> = text.encode_utf16().take(buffer.len() - 1).collect();
+    buffer[..units.len()].copy_from_slice(&units);
+    buffer[units.len()] = 0;
+}
@@ -0,0 +1,155 @@
+//! Hot-reloading of configuration files.
+//!
+//! Watches a configuration file (see [`ConfigSpec::from_path`]) and applies changes to a running
+//! event handler through a [`SharedConfig`].
+//!
+//! In most cases, [`crate::start_with_config_file`] is all you need.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{PoisonError, mpsc},
+    thread,
+};
+
+use notify::{Event, RecursiveMode, Watcher};
+
+use crate::{
+    error::{Error, Result},
+    event_handler::{SharedConfig, read_config},
+    keyboard_hook::{self, HookCommand},
+    spec::ConfigSpec,
+};
+
+/// Spawns a thread that reloads `config` whenever the file at `path` changes.
+///
+/// Only the options described by the file's [`ConfigSpec`] are replaced, atomically; all
+/// other options, such as observers, `on_tap` or the suppression strategy, keep the values set
+/// in code. If the file cannot be read or parsed, the error is reported through `on_error`
+/// and the previous configuration stays active.
+///
+/// Options applied inside the keyboard hook, such as `block_alt_space`, are not known to
+/// this watcher's hook; changes to them are logged and ignored. Use
+/// [`crate::start_with_config_file`] to apply them as well.
+///
+/// # Errors
+/// Returns `Error::ConfigWatch` if the file system watcher cannot be set up.
+pub fn watch_config_file(
+    path: impl AsRef<Path>,
+    config: SharedConfig,
+) -> Result<thread::JoinHandle<()>> {
+    watch(path.as_ref(), config, None)
+}
+
+/// Like [`watch_config_file`], but also applies changed [`HookOptions`] to the hook thread
+/// with the Win32 ID `hook_thread`.
+///
+/// [`HookOptions`]: crate::keyboard_hook::HookOptions
+pub(crate) fn watch(
+    path: &Path,
+    config: SharedConfig,
+    hook_thread: Option<u32>,
+) -> Result<thread::JoinHandle<()>> {
+    let path = path.to_owned();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    };
+
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx).map_err(Error::ConfigWatch)?;
+    // Watch the directory rather than the file so that editors replacing the file are seen.
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(Error::ConfigWatch)?;
+
+    Ok(thread::spawn(move || {
+        let _watcher = watcher;
+
+        #[cfg(feature = "log")]
+        log::debug!("watching {}", path.display());
+
+        for event in rx {
+            match event {
+                Ok(event) if is_relevant(&event, &path) => reload(&path, &config, hook_thread),
+                Ok(_) => {}
+                Err(e) => (read_config(&config).on_error)(&Error::ConfigWatch(e)),
+            }
+        }
+    }))
+}
+
+fn is_relevant(event: &Event, path: &Path) -> bool {
+    (event.kind.is_modify() || event.kind.is_create())
+        && event
+            .paths
+            .iter()
+            .any(|p| p.file_name() == path.file_name())
+}
+
+fn reload(path: &Path, config: &SharedConfig, hook_thread: Option<u32>) {
+    match ConfigSpec::from_path(path) {
+        Ok(spec) => {
+            let mut current = config.write().unwrap_or_else(PoisonError::into_inner);
+            let hook = current.hook.clone();
+            let mut new_config = std::mem::take(&mut *current).set_spec(spec);
+            if new_config.hook != hook {
+                let applied = match hook_thread {
+                    Some(thread_id) => keyboard_hook::send_command(
+                        thread_id,
+                        HookCommand::SetOptions(new_config.hook.clone()),
+                    )
+                    .map_err(|e| (new_config.on_error)(&e))
+                    .is_ok(),
+                    None => {
+                        #[cfg(feature = "log")]
+                        log::warn!(
+                            "hook options in {} changed, restart to apply",
+                            path.display()
+                        );
+                        false
+                    }
+                };
+                if !applied {
+                    // Keep describing the options the hook actually runs with.
+                    new_config.hook = hook;
+                }
+            }
+            *current = new_config;
+
+            #[cfg(feature = "log")]
+            log::info!("reloaded {}", path.display());
+        }
+        Err(e) => {
+            #[cfg(feature = "log")]
+            log::error!("failed to reload {}: {}", path.display(), e);
+            (read_config(config).on_error)(&e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use super::*;
+    use crate::{event_handler::Config, observer::Observer};
+
+    struct Silent;
+
+    impl Observer for Silent {}
+
+    #[test]
+    fn reload_keeps_options_set_in_code() {
+        let path = std::env::temp_dir().join(format!("paw-reload-{}.toml", std::process::id()));
+        std::fs::write(&path, "block_shift_f10 = true\n").unwrap();
+        let config = Arc::new(RwLock::new(Config::default().set_observer(Silent)));
+
+        reload(&path, &config, None);
+        let _ = std::fs::remove_file(&path);
+
+        let config = config.read().unwrap();
+        assert!(config.observer.is_some());
+        // Without a hook thread, hook options stay as they were.
+        assert!(!config.hook.block_shift_f10);
+    }
+}
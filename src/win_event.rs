@@ -0,0 +1,191 @@
+//! System events about menus and foreground changes, delivered through a channel.
+//!
+//! Wraps `SetWinEventHook` for `EVENT_SYSTEM_FOREGROUND`, `EVENT_SYSTEM_MENUSTART`/`END` and
+//! `EVENT_SYSTEM_MENUPOPUPSTART`/`END`. Observing when a menu actually opens is the building
+//! block for verifying suppression and for menu-open callbacks.
+
+use std::{cell::OnceCell, sync::mpsc, thread};
+
+use windows::Win32::{
+    Foundation::HWND,
+    UI::{
+        Accessibility::{HWINEVENTHOOK, SetWinEventHook, UnhookWinEvent},
+        WindowsAndMessaging::{
+            DispatchMessageW, EVENT_SYSTEM_FOREGROUND, EVENT_SYSTEM_MENUEND,
+            EVENT_SYSTEM_MENUPOPUPEND, EVENT_SYSTEM_MENUPOPUPSTART, EVENT_SYSTEM_MENUSTART,
+            GetMessageW, MSG, WINEVENT_OUTOFCONTEXT,
+        },
+    },
+};
+
+use crate::error::{Error, Result};
+
+/// Receives the events of a WinEvent hook on its thread.
+pub(crate) type WinEventSink = Box<dyn Fn(WinEvent) + Send>;
+
+thread_local! {
+    static WIN_EVENT_SINK: OnceCell<WinEventSink> = const { OnceCell::new() };
+}
+
+/// The kind of a [`WinEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WinEventKind {
+    /// The foreground window changed (`EVENT_SYSTEM_FOREGROUND`).
+    Foreground,
+    /// A menu bar was activated (`EVENT_SYSTEM_MENUSTART`), e.g. by releasing Alt.
+    MenuStart,
+    /// A menu bar was deactivated (`EVENT_SYSTEM_MENUEND`).
+    MenuEnd,
+    /// A pop-up menu was displayed (`EVENT_SYSTEM_MENUPOPUPSTART`), e.g. the window menu.
+    MenuPopupStart,
+    /// A pop-up menu was closed (`EVENT_SYSTEM_MENUPOPUPEND`).
+    MenuPopupEnd,
+}
+
+impl WinEventKind {
+    fn from_event(event: u32) -> Option<Self> {
+        Some(match event {
+            EVENT_SYSTEM_FOREGROUND => Self::Foreground,
+            EVENT_SYSTEM_MENUSTART => Self::MenuStart,
+            EVENT_SYSTEM_MENUEND => Self::MenuEnd,
+            EVENT_SYSTEM_MENUPOPUPSTART => Self::MenuPopupStart,
+            EVENT_SYSTEM_MENUPOPUPEND => Self::MenuPopupEnd,
+            _ => return None,
+        })
+    }
+}
+
+/// A system event reported by the WinEvent hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WinEvent {
+    /// What happened.
+    pub kind: WinEventKind,
+    /// The raw handle of the window that generated the event. Use [`WinEvent::hwnd`] to get
+    /// an `HWND`.
+    pub handle: usize,
+    /// The object that generated the event (`idObject`).
+    pub id_object: i32,
+    /// The child element that generated the event (`idChild`).
+    pub id_child: i32,
+    /// The thread that generated the event.
+    pub thread_id: u32,
+    /// The tick count at which the event was generated.
+    pub time: u32,
+}
+
+impl WinEvent {
+    /// Returns the window handle.
+    pub fn hwnd(&self) -> HWND {
+        HWND(self.handle as *mut _)
+    }
+}
+
+/// Starts a thread that observes menu and foreground events and sends them through a channel.
+///
+/// # Errors
+/// - Returns `Error::WinEventHookFailed` if the WinEvent hook cannot be installed.
+/// - Returns `Error::HookThreadCrashed` if the hook thread terminated unexpectedly.
+pub fn start_win_event_hook() -> Result<(mpsc::Receiver<WinEvent>, thread::JoinHandle<()>)> {
+    let (tx, rx) = mpsc::channel();
+    let (result_tx, result_rx) = oneshot::channel::<Result<()>>();
+
+    let join_handle = thread::spawn(move || {
+        let sink = Box::new(move |event| {
+            if let Err(_e) = tx.send(event) {
+                #[cfg(feature = "log")]
+                log::error!("{}", _e);
+            }
+        });
+        let _hook = match install_win_event_hook(sink) {
+            Ok(hook) => hook,
+            Err(e) => {
+                let _ = result_tx.send(Err(e));
+                return;
+            }
+        };
+        let _ = result_tx.send(Ok(()));
+
+        let mut msg = MSG::default();
+        unsafe {
+            while GetMessageW(&mut msg, None, 0, 0).into() {
+                DispatchMessageW(&msg);
+            }
+        }
+    });
+
+    match result_rx.recv() {
+        Ok(Ok(())) => Ok((rx, join_handle)),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(Error::HookThreadCrashed),
+    }
+}
+
+/// A WinEvent hook installed by [`install_win_event_hook`], removed when dropped.
+pub(crate) struct WinEventHook(HWINEVENTHOOK);
+
+impl Drop for WinEventHook {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = UnhookWinEvent(self.0);
+        }
+    }
+}
+
+/// Installs the WinEvent hook on the calling thread, which must pump messages, passing
+/// events to `sink`. Only one sink can be set per thread.
+pub(crate) fn install_win_event_hook(sink: WinEventSink) -> Result<WinEventHook> {
+    if WIN_EVENT_SINK.with(|s| s.set(sink)).is_err() {
+        return Err(Error::WinEventHookFailed);
+    }
+
+    // The event constants are contiguous, from EVENT_SYSTEM_FOREGROUND (3)
+    // to EVENT_SYSTEM_MENUPOPUPEND (7).
+    let hook = unsafe {
+        SetWinEventHook(
+            EVENT_SYSTEM_FOREGROUND,
+            EVENT_SYSTEM_MENUPOPUPEND,
+            None,
+            Some(win_event_proc),
+            0,
+            0,
+            WINEVENT_OUTOFCONTEXT,
+        )
+    };
+    if hook.is_invalid() {
+        return Err(Error::WinEventHookFailed);
+    }
+
+    #[cfg(feature = "log")]
+    log::info!("started WinEvent hook");
+
+    Ok(WinEventHook(hook))
+}
+
+unsafe extern "system" fn win_event_proc(
+    _hook: HWINEVENTHOOK,
+    event: u32,
+    hwnd: HWND,
+    id_object: i32,
+    id_child: i32,
+    event_thread: u32,
+    event_time: u32,
+) {
+    let Some(kind) = WinEventKind::from_event(event) else {
+        return;
+    };
+
+    let event = WinEvent {
+        kind,
+        handle: hwnd.0 as usize,
+        id_object,
+        id_child,
+        thread_id: event_thread,
+        time: event_time,
+    };
+
+    WIN_EVENT_SINK.with(|s| {
+        if let Some(sink) = s.get() {
+            sink(event);
+        }
+    });
+}
@@ -0,0 +1,45 @@
+//! Message-only windows for threads that need to receive notifications.
+
+use windows::{
+    Win32::{
+        Foundation::HWND,
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, HWND_MESSAGE, RegisterClassW, WINDOW_EX_STYLE, WINDOW_STYLE,
+            WNDCLASSW, WNDPROC,
+        },
+    },
+    core::PCWSTR,
+};
+
+/// Creates a message-only window of the given class on the calling thread, registering the
+/// class with `window_proc` if needed.
+pub(crate) fn create_message_window(class_name: PCWSTR, window_proc: WNDPROC) -> Option<HWND> {
+    let instance = unsafe { GetModuleHandleW(None) }.ok()?;
+    let class = WNDCLASSW {
+        lpfnWndProc: window_proc,
+        hInstance: instance.into(),
+        lpszClassName: class_name,
+        ..Default::default()
+    };
+    // Registering fails harmlessly if an earlier call already registered the class.
+    unsafe { RegisterClassW(&class) };
+
+    unsafe {
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            class_name,
+            None,
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            Some(HWND_MESSAGE),
+            None,
+            Some(instance.into()),
+            None,
+        )
+    }
+    .ok()
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use prevent_alt_win_menu::detect::{start_menu_is_open, wait_for_start_menu};
+use uiautomation::UIAutomation;
+
+#[test]
+fn show_start_menu() {
+    let automation = UIAutomation::new().unwrap();
+
+    assert_no_start_menu();
+
+    send_win(&automation);
+
+    assert!(wait_for_start_menu(Duration::from_millis(500)));
+
+    send_win(&automation);
+
+    assert_no_start_menu();
+}
+
+#[test]
+fn prevent_to_show_start_menu() {
+    prevent_alt_win_menu::start(Default::default()).unwrap();
+
+    let automation = UIAutomation::new().unwrap();
+
+    assert_no_start_menu();
+
+    send_win(&automation);
+
+    assert_no_start_menu();
+}
+
+fn send_win(automation: &UIAutomation) {
+    let root = automation.get_root_element().unwrap();
+    root.send_keys("{Win}", 0).unwrap();
+}
+
+#[track_caller]
+fn assert_no_start_menu() {
+    std::thread::sleep(Duration::from_millis(500));
+
+    assert!(!start_menu_is_open());
+}
@@ -0,0 +1,35 @@
+use prevent_alt_win_menu::{
+    event_handler::{
+        INJECTED_INPUT_TAG, InputBatch,
+        KeyboardAndMouse::{KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, VK_F24},
+        send_key_tap,
+    },
+    input::{RecordingSender, set_input_sender},
+};
+
+#[test]
+fn recording_sender_captures_injected_input() {
+    let recorder = RecordingSender::default();
+    set_input_sender(recorder.clone());
+
+    send_key_tap(VK_F24).unwrap();
+    let inputs = recorder.inputs();
+    let (down, up) = unsafe { (inputs[0].Anonymous.ki, inputs[1].Anonymous.ki) };
+
+    assert_eq!(inputs.len(), 2);
+    assert_eq!(down.wVk, VK_F24);
+    assert!(!down.dwFlags.contains(KEYEVENTF_KEYUP));
+    assert!(up.dwFlags.contains(KEYEVENTF_KEYUP));
+    assert_eq!(up.dwExtraInfo, INJECTED_INPUT_TAG);
+
+    recorder.clear();
+    InputBatch::default().unicode("ab").send().unwrap();
+    let inputs = recorder.inputs();
+
+    assert_eq!(inputs.len(), 4);
+    assert!(
+        inputs
+            .iter()
+            .all(|i| unsafe { i.Anonymous.ki.dwFlags }.contains(KEYEVENTF_UNICODE))
+    );
+}
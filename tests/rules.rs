@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use prevent_alt_win_menu::{
+    event_handler::{KeyboardAndMouse::VK_F24, MenuTrigger, Side},
+    rules::{Action, Rule, RuleSet},
+};
+
+fn rules() -> RuleSet {
+    RuleSet::new(vec![
+        Rule {
+            trigger: Some(MenuTrigger::Alt),
+            max_hold: Some(Duration::from_millis(300)),
+            action: Action::PassThrough,
+            ..Default::default()
+        },
+        Rule {
+            process: Some("game.exe".to_owned()),
+            action: Action::Suppress(VK_F24),
+            ..Default::default()
+        },
+    ])
+}
+
+#[test]
+fn first_matching_rule_wins() {
+    let action = rules().evaluate_with(
+        MenuTrigger::Alt,
+        None,
+        Duration::from_millis(100),
+        Some("game.exe"),
+    );
+
+    assert_eq!(action, Action::PassThrough);
+}
+
+#[test]
+fn process_match_is_case_insensitive() {
+    let action = rules().evaluate_with(
+        MenuTrigger::Win,
+        None,
+        Duration::from_millis(100),
+        Some("Game.EXE"),
+    );
+
+    assert_eq!(action, Action::Suppress(VK_F24));
+}
+
+#[test]
+fn falls_back_to_default_action() {
+    let action = rules().evaluate_with(MenuTrigger::Alt, None, Duration::from_millis(500), None);
+
+    assert_eq!(action, Action::default());
+}
+
+#[test]
+fn side_restricts_rule() {
+    let rules = RuleSet::new(vec![Rule {
+        trigger: Some(MenuTrigger::Win),
+        side: Some(Side::Right),
+        action: Action::PassThrough,
+        ..Default::default()
+    }]);
+
+    let left = rules.evaluate_with(MenuTrigger::Win, Some(Side::Left), Duration::ZERO, None);
+    let right = rules.evaluate_with(MenuTrigger::Win, Some(Side::Right), Duration::ZERO, None);
+
+    assert_eq!(left, Action::default());
+    assert_eq!(right, Action::PassThrough);
+}
+
+#[test]
+fn hold_bounds_are_inclusive() {
+    let rules = RuleSet::new(vec![Rule {
+        min_hold: Some(Duration::from_millis(100)),
+        max_hold: Some(Duration::from_millis(200)),
+        action: Action::PassThrough,
+        ..Default::default()
+    }]);
+    let evaluate =
+        |ms| rules.evaluate_with(MenuTrigger::Win, None, Duration::from_millis(ms), None);
+
+    assert_eq!(evaluate(99), Action::default());
+    assert_eq!(evaluate(100), Action::PassThrough);
+    assert_eq!(evaluate(200), Action::PassThrough);
+    assert_eq!(evaluate(201), Action::default());
+}